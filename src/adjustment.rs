@@ -0,0 +1,179 @@
+use crate::color::Color;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    All,
+}
+
+impl Channel {
+    pub fn map(&self, color: Color, f: impl Fn(f32) -> f32) -> Color {
+        match self {
+            Channel::Red => Color::new(f(color.red), color.green, color.blue),
+            Channel::Green => Color::new(color.red, f(color.green), color.blue),
+            Channel::Blue => Color::new(color.red, color.green, f(color.blue)),
+            Channel::All => Color::new(f(color.red), f(color.green), f(color.blue)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Levels {
+    pub in_black: f32,
+    pub in_white: f32,
+    pub gamma: f32,
+    pub out_black: f32,
+    pub out_white: f32,
+}
+
+impl Levels {
+    pub fn new(in_black: f32, in_white: f32, gamma: f32, out_black: f32, out_white: f32) -> Levels {
+        Levels {
+            in_black,
+            in_white,
+            gamma,
+            out_black,
+            out_white,
+        }
+    }
+
+    pub fn set_input(mut self, black: f32, white: f32) -> Self {
+        self.in_black = black;
+        self.in_white = white;
+        self
+    }
+
+    pub fn set_gamma(mut self, new: f32) -> Self {
+        self.gamma = new;
+        self
+    }
+
+    pub fn set_output(mut self, black: f32, white: f32) -> Self {
+        self.out_black = black;
+        self.out_white = white;
+        self
+    }
+
+    pub fn apply(&self, x: f32) -> f32 {
+        let range = (self.in_white - self.in_black).max(0.00001);
+        let normalized = ((x - self.in_black) / range).clamp(0.0, 1.0);
+        let adjusted = normalized.powf(1.0 / self.gamma);
+        self.out_black + (self.out_white - self.out_black) * adjusted
+    }
+}
+
+impl Default for Levels {
+    fn default() -> Levels {
+        Levels::new(0.0, 1.0, 1.0, 0.0, 1.0)
+    }
+}
+
+// A tone curve through control points, linearly interpolated between them
+// and held flat beyond the first and last point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    points: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    pub fn new(points: &[(f32, f32)]) -> Curve {
+        let mut points = points.to_vec();
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Curve { points }
+    }
+
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    pub fn apply(&self, x: f32) -> f32 {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return x,
+        };
+        if x <= first.0 {
+            return first.1;
+        }
+        if x >= last.0 {
+            return last.1;
+        }
+        for pair in self.points.windows(2) {
+            let (x0, y0) = pair[0];
+            let (x1, y1) = pair[1];
+            if x <= x1 {
+                if x1 - x0 <= 0.0 {
+                    return y1;
+                }
+                return y0 + (y1 - y0) * (x - x0) / (x1 - x0);
+            }
+        }
+        last.1
+    }
+}
+
+impl Default for Curve {
+    fn default() -> Curve {
+        Curve::new(&[(0.0, 0.0), (1.0, 1.0)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn default_levels_are_identity() {
+        let l = Levels::default();
+        for x in [0.0, 0.25, 0.5, 1.0] {
+            assert!(fp_equal(l.apply(x), x));
+        }
+    }
+
+    #[test]
+    fn levels_stretch_input_range() {
+        let l = Levels::default().set_input(0.2, 0.6);
+        assert!(fp_equal(l.apply(0.1), 0.0));
+        assert!(fp_equal(l.apply(0.4), 0.5));
+        assert!(fp_equal(l.apply(0.8), 1.0));
+    }
+
+    #[test]
+    fn levels_gamma_brightens_midtones() {
+        let l = Levels::default().set_gamma(2.0);
+        assert!(fp_equal(l.apply(0.25), 0.5));
+    }
+
+    #[test]
+    fn levels_compress_output_range() {
+        let l = Levels::default().set_output(0.1, 0.9);
+        assert!(fp_equal(l.apply(0.0), 0.1));
+        assert!(fp_equal(l.apply(1.0), 0.9));
+    }
+
+    #[test]
+    fn curve_interpolates_between_points() {
+        let c = Curve::new(&[(1.0, 1.0), (0.0, 0.0), (0.5, 0.7)]);
+        assert!(fp_equal(c.apply(0.25), 0.35));
+        assert!(fp_equal(c.apply(0.5), 0.7));
+        assert!(fp_equal(c.apply(0.75), 0.85));
+    }
+
+    #[test]
+    fn curve_is_flat_outside_its_points() {
+        let c = Curve::new(&[(0.2, 0.1), (0.8, 0.9)]);
+        assert!(fp_equal(c.apply(0.0), 0.1));
+        assert!(fp_equal(c.apply(2.0), 0.9));
+    }
+
+    #[test]
+    fn channel_only_changes_its_component() {
+        let color = Color::new(0.2, 0.4, 0.6);
+        let c = Channel::Green.map(color, |x| x * 2.0);
+        assert!(c == Color::new(0.2, 0.8, 0.6));
+        let c = Channel::All.map(color, |x| x * 2.0);
+        assert!(c == Color::new(0.4, 0.8, 1.2));
+    }
+}
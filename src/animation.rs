@@ -0,0 +1,281 @@
+use crate::color::Color;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::shape::Shape;
+use crate::transform;
+use crate::vector::Vector;
+
+// How a value moves from one keyframe to the next, as a function from the
+// fraction of the time between them to the fraction of the way between
+// their values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    Linear,
+    // Starting slowly.
+    EaseIn,
+    // Stopping slowly.
+    EaseOut,
+    EaseInOut,
+    // Holding the value until the next keyframe.
+    Step,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Step => 0.0,
+        }
+    }
+}
+
+// Values that can be blended between keyframes.
+pub trait Interpolate: Clone {
+    // self at t = 0 to other at t = 1.
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Color, t: f32) -> Color {
+        self.lerp(other, t)
+    }
+}
+
+impl Interpolate for Point {
+    fn interpolate(&self, other: &Point, t: f32) -> Point {
+        self.lerp(other, t)
+    }
+}
+
+impl Interpolate for Vector {
+    fn interpolate(&self, other: &Vector, t: f32) -> Vector {
+        self.lerp(other, t)
+    }
+}
+
+// Through the transforms' decomposed parts, so rotations turn rather than
+// shrink.
+impl Interpolate for Matrix {
+    fn interpolate(&self, other: &Matrix, t: f32) -> Matrix {
+        transform::interpolate(self, other, t)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    // How the value moves on to the next keyframe's.
+    pub easing: Easing,
+}
+
+// A value over time, given at keyframes. Before the first keyframe and
+// after the last the value stays where they put it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new() -> Track<T> {
+        Track { keys: vec![] }
+    }
+
+    // Keys may be added in any order. A key at the same time as an existing
+    // one replaces it.
+    pub fn add_key(mut self, time: f32, value: T, easing: Easing) -> Self {
+        let key = Keyframe {
+            time,
+            value,
+            easing,
+        };
+        match self.keys.iter().position(|k| k.time >= time) {
+            Some(i) if self.keys[i].time == time => self.keys[i] = key,
+            Some(i) => self.keys.insert(i, key),
+            None => self.keys.push(key),
+        }
+        self
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    // None if there are no keys.
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keys.first()?;
+        let next = match self.keys.iter().position(|k| k.time > time) {
+            Some(0) => return Some(first.value.clone()),
+            Some(i) => i,
+            None => return self.keys.last().map(|k| k.value.clone()),
+        };
+        let (a, b) = (&self.keys[next - 1], &self.keys[next]);
+        let t = a.easing.apply((time - a.time) / (b.time - a.time));
+        Some(a.value.interpolate(&b.value, t))
+    }
+}
+
+impl<T: Interpolate> Default for Track<T> {
+    fn default() -> Track<T> {
+        Track::new()
+    }
+}
+
+// The numeric parts of a material that can be animated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MaterialProperty {
+    Ambient,
+    Diffuse,
+    Specular,
+    Shininess,
+    Reflective,
+    Transparency,
+    RefractiveIndex,
+}
+
+impl MaterialProperty {
+    fn field(self, material: &mut Material) -> &mut f32 {
+        match self {
+            MaterialProperty::Ambient => &mut material.ambient,
+            MaterialProperty::Diffuse => &mut material.diffuse,
+            MaterialProperty::Specular => &mut material.specular,
+            MaterialProperty::Shininess => &mut material.shininess,
+            MaterialProperty::Reflective => &mut material.reflective,
+            MaterialProperty::Transparency => &mut material.transparency,
+            MaterialProperty::RefractiveIndex => &mut material.refractive_index,
+        }
+    }
+}
+
+// Everything keyframed about one object. The transform track moves the
+// object from where it was placed: its value at each time is applied on
+// top of the object's own transform.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectAnimation {
+    transform: Option<Track<Matrix>>,
+    color: Option<Track<Color>>,
+    properties: Vec<(MaterialProperty, Track<f32>)>,
+}
+
+impl ObjectAnimation {
+    pub fn new() -> ObjectAnimation {
+        ObjectAnimation::default()
+    }
+
+    pub fn set_transform(mut self, track: Track<Matrix>) -> Self {
+        self.transform = Some(track);
+        self
+    }
+
+    pub fn set_color(mut self, track: Track<Color>) -> Self {
+        self.color = Some(track);
+        self
+    }
+
+    pub fn set_property(mut self, property: MaterialProperty, track: Track<f32>) -> Self {
+        self.properties.retain(|(p, _)| *p != property);
+        self.properties.push((property, track));
+        self
+    }
+
+    // Puts object as it is at time.
+    pub fn apply(&self, object: &mut dyn Shape, time: f32) {
+        if let Some(m) = self.transform.as_ref().and_then(|t| t.sample(time)) {
+            object.apply_parent_transform(&m);
+        }
+        let material = object.material_mut();
+        if let Some(color) = self.color.as_ref().and_then(|t| t.sample(time)) {
+            material.color = color;
+        }
+        for (property, track) in &self.properties {
+            if let Some(value) = track.sample(time) {
+                *property.field(material) = value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn easing_curves() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::Step.apply(0.9), 0.0);
+    }
+
+    #[test]
+    fn sampling_a_track() {
+        let track = Track::new()
+            .add_key(2.0, 10.0, Easing::Linear)
+            .add_key(0.0, 0.0, Easing::EaseIn)
+            .add_key(3.0, 0.0, Easing::Linear);
+        assert_eq!(track.keys().len(), 3);
+        assert_eq!(track.sample(-1.0), Some(0.0));
+        assert_eq!(track.sample(1.0), Some(2.5));
+        assert_eq!(track.sample(2.0), Some(10.0));
+        assert_eq!(track.sample(2.5), Some(5.0));
+        assert_eq!(track.sample(9.0), Some(0.0));
+        assert_eq!(Track::<f32>::new().sample(1.0), None);
+        let replaced = track.add_key(2.0, 4.0, Easing::Step);
+        assert_eq!(replaced.keys().len(), 3);
+        assert_eq!(replaced.sample(2.9), Some(4.0));
+    }
+
+    #[test]
+    fn animating_an_object() {
+        let animation = ObjectAnimation::new()
+            .set_transform(
+                Track::new()
+                    .add_key(0.0, Matrix::identity(), Easing::Linear)
+                    .add_key(1.0, Matrix::translation(4.0, 0.0, 0.0), Easing::Linear),
+            )
+            .set_color(
+                Track::new()
+                    .add_key(0.0, Color::new(1.0, 0.0, 0.0), Easing::Linear)
+                    .add_key(1.0, Color::new(0.0, 0.0, 1.0), Easing::Linear),
+            )
+            .set_property(
+                MaterialProperty::Reflective,
+                Track::new().add_key(0.0, 0.8, Easing::Linear),
+            );
+        let mut sphere = Sphere::default().set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        animation.apply(&mut sphere, 0.5);
+        assert_eq!(
+            sphere.transform(),
+            &(Matrix::translation(2.0, 0.0, 0.0) * Matrix::scaling(2.0, 2.0, 2.0))
+        );
+        assert_eq!(sphere.material().color, Color::new(0.5, 0.0, 0.5));
+        assert_eq!(sphere.material().reflective, 0.8);
+    }
+}
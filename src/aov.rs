@@ -0,0 +1,108 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::vector::Vector;
+use crate::world::ObjectId;
+
+// What a camera ray first hits, for the auxiliary images.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SurfaceSample {
+    // Distance from the ray's origin to the hit.
+    pub depth: f32,
+    // In world space, turned to face the ray.
+    pub normal: Vector,
+    // The surface color before any lighting.
+    pub albedo: Color,
+    pub object: ObjectId,
+}
+
+// A render along with the auxiliary images denoisers and compositors use,
+// all the same size. The auxiliary images come from one ray through the
+// middle of each pixel. Where that ray misses everything, depth is infinite,
+// normal and albedo are black and there is no object id.
+#[derive(Clone, Debug)]
+pub struct Aovs {
+    // The exposed render.
+    pub beauty: Canvas,
+    // Depth in all three channels, for writing as PFM or HDR.
+    pub depth: Canvas,
+    // The normal's x, y and z as red, green and blue, each from -1 to 1.
+    pub normal: Canvas,
+    pub albedo: Canvas,
+    object_ids: Vec<Option<ObjectId>>,
+}
+
+impl Aovs {
+    pub(crate) fn new(beauty: Canvas) -> Aovs {
+        let (width, height) = (beauty.width, beauty.height);
+        Aovs {
+            beauty,
+            depth: Canvas::new(width, height),
+            normal: Canvas::new(width, height),
+            albedo: Canvas::new(width, height),
+            object_ids: vec![None; (width * height) as usize],
+        }
+    }
+
+    pub(crate) fn write(&mut self, x: usize, y: usize, sample: Option<SurfaceSample>) {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let (depth, normal, albedo, id) = match sample {
+            Some(s) => {
+                let n = s.normal;
+                let depth = Color::new(s.depth, s.depth, s.depth);
+                (depth, Color::new(n.x, n.y, n.z), s.albedo, Some(s.object))
+            }
+            None => {
+                let inf = f32::INFINITY;
+                (Color::new(inf, inf, inf), black, black, None)
+            }
+        };
+        self.depth.write_pixel(x, y, depth);
+        self.normal.write_pixel(x, y, normal);
+        self.albedo.write_pixel(x, y, albedo);
+        self.object_ids[y * self.beauty.width as usize + x] = id;
+    }
+
+    pub fn object_id_at(&self, x: usize, y: usize) -> Option<ObjectId> {
+        self.object_ids[y * self.beauty.width as usize + x]
+    }
+
+    // White where the object was seen and black elsewhere, for masking it
+    // out in a compositor.
+    pub fn matte(&self, id: ObjectId) -> Canvas {
+        let mut image = Canvas::new(self.beauty.width, self.beauty.height);
+        for y in 0..self.beauty.height as usize {
+            for x in 0..self.beauty.width as usize {
+                if self.object_id_at(x, y) == Some(id) {
+                    image.write_pixel(x, y, Color::new(1.0, 1.0, 1.0));
+                }
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::World;
+
+    #[test]
+    fn mattes_from_object_ids() {
+        let ids = World::default().object_ids().to_vec();
+        let mut aovs = Aovs::new(Canvas::new(2, 1));
+        let sample = SurfaceSample {
+            depth: 2.0,
+            normal: Vector::new(0.0, 1.0, 0.0),
+            albedo: Color::new(0.5, 0.5, 0.5),
+            object: ids[1],
+        };
+        aovs.write(1, 0, Some(sample));
+        aovs.write(0, 0, None);
+        assert_eq!(aovs.object_id_at(1, 0), Some(ids[1]));
+        assert_eq!(aovs.normal.pixel_at(1, 0), Color::new(0.0, 1.0, 0.0));
+        let matte = aovs.matte(ids[1]);
+        assert_eq!(matte.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(matte.pixel_at(1, 0), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(aovs.matte(ids[0]).pixel_at(1, 0), Color::new(0.0, 0.0, 0.0));
+    }
+}
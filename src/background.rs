@@ -0,0 +1,124 @@
+use crate::color::Color;
+use crate::point::Point;
+use crate::sky::SunSky;
+use crate::texture::ImageTexture;
+use crate::uv::{cubic_map, CubeFace, UvPattern};
+use crate::vector::Vector;
+
+// What a ray that misses every object sees.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Background {
+    Solid(Color),
+    // Blends from bottom straight down to top straight up.
+    Gradient { bottom: Color, top: Color },
+    // One image per cube face, in CubeFace order: left, right, front, back,
+    // up, down. Each is mapped as cubic_map lays out its face.
+    Skybox(Box<[ImageTexture; 6]>),
+    Sky(SunSky),
+}
+
+impl Background {
+    pub fn color_in_direction(&self, direction: Vector) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.normalize().y + 1.0);
+                *bottom + (*top - *bottom) * t
+            }
+            Background::Skybox(faces) => {
+                let d = direction;
+                let largest = d.x.abs().max(d.y.abs()).max(d.z.abs());
+                let p = Point::new(d.x / largest, d.y / largest, d.z / largest);
+                let face = match CubeFace::from_point(p) {
+                    CubeFace::Left => 0,
+                    CubeFace::Right => 1,
+                    CubeFace::Front => 2,
+                    CubeFace::Back => 3,
+                    CubeFace::Up => 4,
+                    CubeFace::Down => 5,
+                };
+                let (u, v) = cubic_map(p);
+                faces[face].uv_pattern_at(u, v)
+            }
+            Background::Sky(sky) => sky.color_in_direction(direction),
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background::Solid(Color::new(0.0, 0.0, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+
+    fn solid_texture(color: Color) -> ImageTexture {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, color);
+        ImageTexture::new(c)
+    }
+
+    #[test]
+    fn default_background_is_black() {
+        assert_eq!(
+            Background::default().color_in_direction(Vector::new(0.0, 0.0, 1.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn gradient_blends_from_bottom_to_top() {
+        let bg = Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.0, 0.0, 1.0),
+        };
+        assert_eq!(
+            bg.color_in_direction(Vector::new(0.0, -1.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            bg.color_in_direction(Vector::new(0.0, 2.0, 0.0)),
+            Color::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            bg.color_in_direction(Vector::new(1.0, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 1.0)
+        );
+    }
+
+    #[test]
+    fn skybox_picks_the_face_a_direction_points_at() {
+        let colors = [
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 0.0),
+            Color::new(0.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 1.0),
+        ];
+        let bg = Background::Skybox(Box::new([
+            solid_texture(colors[0]),
+            solid_texture(colors[1]),
+            solid_texture(colors[2]),
+            solid_texture(colors[3]),
+            solid_texture(colors[4]),
+            solid_texture(colors[5]),
+        ]));
+        let directions = [
+            Vector::new(-3.0, 1.0, 0.5),
+            Vector::new(2.0, -0.5, 1.0),
+            Vector::new(0.1, 0.2, 0.9),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.5, 5.0, 0.5),
+            Vector::new(0.2, -0.7, 0.1),
+        ];
+        for (d, c) in directions.iter().zip(colors.iter()) {
+            assert_eq!(bg.color_in_direction(*d), *c);
+        }
+    }
+}
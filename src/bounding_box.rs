@@ -0,0 +1,244 @@
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::Ray;
+
+// Axis-aligned box. The default box is empty (min above max) so that adding
+// the first point or box makes it exactly that point or box.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingBox {
+    min: Point,
+    max: Point,
+}
+
+impl Default for BoundingBox {
+    fn default() -> BoundingBox {
+        BoundingBox {
+            min: Point::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Point::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    pub fn add_point(&mut self, p: Point) {
+        self.min = Point::new(
+            self.min.x.min(p.x),
+            self.min.y.min(p.y),
+            self.min.z.min(p.z),
+        );
+        self.max = Point::new(
+            self.max.x.max(p.x),
+            self.max.y.max(p.y),
+            self.max.z.max(p.z),
+        );
+    }
+
+    pub fn merge(mut self, other: &BoundingBox) -> Self {
+        self.add_point(other.min);
+        self.add_point(other.max);
+        self
+    }
+
+    pub fn contains_point(&self, p: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x)
+            && (self.min.y..=self.max.y).contains(&p.y)
+            && (self.min.z..=self.max.z).contains(&p.z)
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    // The axis-aligned box enclosing this one after transformation, found
+    // from all eight transformed corners.
+    pub fn transform(&self, m: &Matrix) -> BoundingBox {
+        let (lo, hi) = (self.min, self.max);
+        let corners = [
+            Point::new(lo.x, lo.y, lo.z),
+            Point::new(lo.x, lo.y, hi.z),
+            Point::new(lo.x, hi.y, lo.z),
+            Point::new(lo.x, hi.y, hi.z),
+            Point::new(hi.x, lo.y, lo.z),
+            Point::new(hi.x, lo.y, hi.z),
+            Point::new(hi.x, hi.y, lo.z),
+            Point::new(hi.x, hi.y, hi.z),
+        ];
+        let mut out = BoundingBox::default();
+        for corner in corners {
+            out.add_point(m * &corner);
+        }
+        out
+    }
+
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        let (o, d) = (ray.origin(), ray.direction());
+        let (xtmin, xtmax) = check_axis(o.x, d.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(o.y, d.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(o.z, d.z, self.min.z, self.max.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        tmin <= tmax
+    }
+}
+
+// Distances at which a ray crosses the two slab planes at min and max on one
+// axis, nearest first. Shared with Cube, whose slabs are at -1 and 1.
+pub(crate) fn check_axis(origin: f32, direction: f32, min: f32, max: f32) -> (f32, f32) {
+    let tmin_numerator = min - origin;
+    let tmax_numerator = max - origin;
+
+    let (tmin, tmax) = if direction.abs() >= f32::EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f32::INFINITY,
+            tmax_numerator * f32::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Vector;
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_4, SQRT_2};
+
+    #[test]
+    fn creating_an_empty_bounding_box() {
+        let b = BoundingBox::default();
+        assert_eq!(b.min().x, f32::INFINITY);
+        assert_eq!(b.max().x, f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let mut b = BoundingBox::default();
+        b.add_point(Point::new(-5.0, 2.0, 0.0));
+        b.add_point(Point::new(7.0, 0.0, -3.0));
+        assert_eq!(b.min(), Point::new(-5.0, 0.0, -3.0));
+        assert_eq!(b.max(), Point::new(7.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn merging_one_bounding_box_into_another() {
+        let b1 = BoundingBox::new(Point::new(-5.0, -2.0, 0.0), Point::new(7.0, 4.0, 4.0));
+        let b2 = BoundingBox::new(Point::new(8.0, -7.0, -2.0), Point::new(14.0, 2.0, 8.0));
+        let b = b1.merge(&b2);
+        assert_eq!(b.min(), Point::new(-5.0, -7.0, -2.0));
+        assert_eq!(b.max(), Point::new(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn checking_if_box_contains_a_point() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        assert!(b.contains_point(Point::new(5.0, -2.0, 0.0)));
+        assert!(b.contains_point(Point::new(8.0, 1.0, 3.0)));
+        assert!(!b.contains_point(Point::new(3.0, 0.0, 3.0)));
+        assert!(!b.contains_point(Point::new(8.0, -4.0, 3.0)));
+        assert!(!b.contains_point(Point::new(8.0, 1.0, 8.0)));
+    }
+
+    #[test]
+    fn checking_if_box_contains_a_box() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let inside = BoundingBox::new(Point::new(6.0, -1.0, 1.0), Point::new(10.0, 3.0, 6.0));
+        let overlapping = BoundingBox::new(Point::new(4.0, -3.0, -1.0), Point::new(10.0, 3.0, 6.0));
+        assert!(b.contains_box(&inside));
+        assert!(!b.contains_box(&overlapping));
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let m = Matrix::rotation_x(FRAC_PI_4) * Matrix::rotation_y(FRAC_PI_4);
+        let t = b.transform(&m);
+        let yz = 1.0 + FRAC_1_SQRT_2;
+        assert!((t.min().x + SQRT_2).abs() < 0.0001);
+        assert!((t.min().y + yz).abs() < 0.0001);
+        assert!((t.min().z + yz).abs() < 0.0001);
+        assert!((t.max().x - SQRT_2).abs() < 0.0001);
+        assert!((t.max().y - yz).abs() < 0.0001);
+        assert!((t.max().z - yz).abs() < 0.0001);
+    }
+
+    #[test]
+    fn intersecting_ray_with_bounding_box() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let cases = [
+            (
+                Point::new(15.0, 1.0, 2.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                true,
+            ),
+            (
+                Point::new(-5.0, -1.0, 4.0),
+                Vector::new(1.0, 0.0, 0.0),
+                true,
+            ),
+            (Point::new(7.0, 6.0, 5.0), Vector::new(0.0, -1.0, 0.0), true),
+            (Point::new(9.0, -5.0, 6.0), Vector::new(0.0, 1.0, 0.0), true),
+            (
+                Point::new(8.0, 2.0, 12.0),
+                Vector::new(0.0, 0.0, -1.0),
+                true,
+            ),
+            (Point::new(6.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), true),
+            (Point::new(8.0, 1.0, 3.5), Vector::new(0.0, 0.0, 1.0), true),
+            (
+                Point::new(9.0, -1.0, -8.0),
+                Vector::new(2.0, 4.0, 6.0),
+                false,
+            ),
+            (
+                Point::new(8.0, 3.0, -4.0),
+                Vector::new(6.0, 2.0, 4.0),
+                false,
+            ),
+            (
+                Point::new(9.0, -1.0, -2.0),
+                Vector::new(4.0, 6.0, 2.0),
+                false,
+            ),
+            (
+                Point::new(4.0, 0.0, 9.0),
+                Vector::new(0.0, 0.0, -1.0),
+                false,
+            ),
+            (
+                Point::new(8.0, 6.0, -1.0),
+                Vector::new(0.0, -1.0, 0.0),
+                false,
+            ),
+            (
+                Point::new(12.0, 5.0, 4.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                false,
+            ),
+        ];
+        for (origin, direction, expected) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), expected);
+        }
+    }
+}
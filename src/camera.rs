@@ -1,21 +1,124 @@
-use crate::canvas::Canvas;
+use crate::aov::Aovs;
+use crate::canvas::{Canvas, Metering, Transfer};
+use crate::checkpoint::Checkpoint;
+use crate::color::Color;
+use crate::debug_view::DebugView;
 use crate::matrix::Matrix;
+use crate::physical_camera::{exposure_from_ev100, PhysicalCamera};
 use crate::point::Point;
+use crate::progress::{CancelToken, Progress};
+use crate::quality::Quality;
 use crate::ray::Ray;
-use crate::world::World;
+use crate::rng::Rng;
+use crate::stats::{self, RenderStats, Stopwatch};
+use crate::tile::{tiles, Tile};
+use crate::transform::Transform;
+use crate::utils::EPSILON;
+use crate::vector::Vector;
+use crate::world::{ObjectId, World};
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 
+// Longest side of the preview that frame_exposure meters.
+const METERING_PREVIEW: u32 = 64;
+
+// How the color seen along each camera ray is worked out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Renderer {
+    // Classic recursive ray tracing: direct lights, mirrors and glass.
+    Whitted,
+    // Monte Carlo global illumination with World::trace_path. Needs many
+    // samples per pixel to converge.
+    PathTraced,
+}
+
+// How directions around the camera are laid out on the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Projection {
+    // A flat image plane, as through an ordinary lens.
+    Perspective,
+    // Equidistant fisheye: the angle off the view axis grows evenly with
+    // distance from the image center, reaching half the field of view at
+    // the middle of the wider edge.
+    Fisheye,
+    // The whole sphere around the camera, longitude across and latitude up
+    // the image, ignoring the field of view. Use a 2:1 image.
+    Equirectangular,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Camera {
     hsize: u32,
     vsize: u32,
     field_of_view: f32,
-    transform: Matrix,
+    // Kept with its inverse, which moves every ray out into world space.
+    transform: Transform,
     pixel_size: f32,
     half_width: f32,
     half_height: f32,
+    shift_x: f32,
+    shift_y: f32,
+    tilt_x: f32,
+    tilt_y: f32,
+    aperture: f32,
+    focal_distance: f32,
+    lens_samples: u32,
+    exposure: f32,
+    distortion: (f32, f32),
+    metering: Option<Metering>,
+    samples: u32,
+    seed: u64,
+    renderer: Renderer,
+    debug_view: Option<DebugView>,
+    shutter: (f32, f32),
+    projection: Projection,
+    threads: usize,
+    tile_size: u32,
+    exposure_stops: f32,
+    transfer: Transfer,
 }
 
 impl Camera {
     pub fn new(hsize: u32, vsize: u32, field_of_view: f32) -> Camera {
+        let (pixel_size, half_width, half_height) = Camera::frame(hsize, vsize, field_of_view);
+
+        Camera {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: Transform::default(),
+            pixel_size,
+            half_width,
+            half_height,
+            shift_x: 0.0,
+            shift_y: 0.0,
+            tilt_x: 0.0,
+            tilt_y: 0.0,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            lens_samples: 1,
+            exposure: 1.0,
+            distortion: (0.0, 0.0),
+            metering: None,
+            samples: 1,
+            seed: 0,
+            renderer: Renderer::Whitted,
+            debug_view: None,
+            shutter: (0.0, 0.0),
+            projection: Projection::Perspective,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            tile_size: 16,
+            exposure_stops: 0.0,
+            transfer: Transfer::Srgb,
+        }
+    }
+
+    fn frame(hsize: u32, vsize: u32, field_of_view: f32) -> (f32, f32, f32) {
         let half_view = (field_of_view / 2.0).tan();
         let aspect = hsize as f32 / vsize as f32;
 
@@ -26,43 +129,591 @@ impl Camera {
         };
 
         let pixel_size = (half_width * 2.0) / hsize as f32;
+        (pixel_size, half_width, half_height)
+    }
 
-        Camera {
-            hsize,
-            vsize,
-            field_of_view,
-            transform: Matrix::identity(),
-            pixel_size,
-            half_width,
-            half_height,
-        }
+    pub fn from_physical(hsize: u32, vsize: u32, physical: &PhysicalCamera) -> Camera {
+        Camera::new(hsize, vsize, physical.field_of_view())
+            .set_aperture(physical.aperture_radius())
+            .set_focal_distance(physical.focus_distance)
+            .set_exposure(physical.exposure())
     }
 
     pub fn ray_for_pixel(&self, px: u32, py: u32) -> Ray {
-        let xoffset = (px as f32 + 0.5) * self.pixel_size;
-        let yoffset = (py as f32 + 0.5) * self.pixel_size;
+        self.ray_through_lens(px, py, 0.0, 0.0)
+    }
+
+    // lens_x and lens_y are a point on the unit disk, scaled by the aperture.
+    // Every ray for a pixel passes through the same point on the focal plane.
+    pub fn ray_through_lens(&self, px: u32, py: u32, lens_x: f32, lens_y: f32) -> Ray {
+        self.ray_at(px as f32 + 0.5, py as f32 + 0.5, lens_x, lens_y)
+    }
+
+    fn ray_at(&self, px: f32, py: f32, lens_x: f32, lens_y: f32) -> Ray {
+        if self.projection != Projection::Perspective {
+            let inverse = self.transform.inverse();
+            let origin = inverse * &Point::new(0.0, 0.0, 0.0);
+            let direction = inverse * &self.panoramic_direction(px, py);
+            return Ray::new(origin, direction.normalize());
+        }
 
-        let world_x = self.half_width - xoffset;
-        let world_y = self.half_height - yoffset;
+        let (world_x, world_y) = self.image_plane_point(px, py);
+        let (world_x, world_y) = self.distort(world_x, world_y);
 
-        let pixel = self.transform().inverse() * Point::new(world_x, world_y, -1.0);
-        let origin = self.transform().inverse() * Point::new(0.0, 0.0, 0.0);
-        let direction = (pixel - origin).normalize();
+        let inverse = self.transform.inverse();
+        let depth = self.focus_depth(world_x, world_y);
+        let focus = inverse * &Point::new(world_x * depth, world_y * depth, -depth);
+        let origin = inverse * &Point::new(lens_x * self.aperture, lens_y * self.aperture, 0.0);
+        let direction = (focus - origin).normalize();
 
         Ray::new(origin, direction)
     }
 
+    // How far in front of the lens the plane of focus crosses the pinhole ray
+    // through (x, y) on the image plane. Untilted that's the focal distance
+    // everywhere; a plane tilted too far to be reached is taken as very far
+    // away.
+    fn focus_depth(&self, x: f32, y: f32) -> f32 {
+        let lean = 1.0 + x * self.tilt_y.tan() - y * self.tilt_x.tan();
+        self.focal_distance / lean.max(EPSILON)
+    }
+
+    // Camera space direction through a pixel for the projections without an
+    // image plane. Like image_plane_point, x grows to the left of the image.
+    fn panoramic_direction(&self, px: f32, py: f32) -> Vector {
+        let u = self.hsize as f32 / 2.0 - px;
+        let v = self.vsize as f32 / 2.0 - py;
+        if self.projection == Projection::Equirectangular {
+            let longitude = u / self.hsize as f32 * 2.0 * PI;
+            let latitude = v / self.vsize as f32 * PI;
+            return Vector::new(
+                latitude.cos() * longitude.sin(),
+                latitude.sin(),
+                -latitude.cos() * longitude.cos(),
+            );
+        }
+        let half_size = self.hsize.max(self.vsize) as f32 / 2.0;
+        let off_axis = ((u * u + v * v).sqrt() / half_size * self.field_of_view / 2.0).min(PI);
+        let around = v.atan2(u);
+        Vector::new(
+            off_axis.sin() * around.cos(),
+            off_axis.sin() * around.sin(),
+            -off_axis.cos(),
+        )
+    }
+
+    // Position on the z = -1 image plane, relative to the optical axis, of a
+    // point given in (fractional) pixel coordinates.
+    fn image_plane_point(&self, px: f32, py: f32) -> (f32, f32) {
+        let xoffset = px * self.pixel_size;
+        let yoffset = py * self.pixel_size;
+
+        let world_x = self.half_width - xoffset - self.shift_x * self.half_width * 2.0;
+        let world_y = self.half_height - yoffset + self.shift_y * self.half_height * 2.0;
+        (world_x, world_y)
+    }
+
+    fn distort(&self, x: f32, y: f32) -> (f32, f32) {
+        let (k1, k2) = self.distortion;
+        let r2 = x * x + y * y;
+        let scale = 1.0 + k1 * r2 + k2 * r2 * r2;
+        (x * scale, y * scale)
+    }
+
+    fn undistort_point(&self, x: f32, y: f32) -> (f32, f32) {
+        let (k1, k2) = self.distortion;
+        let (mut ux, mut uy) = (x, y);
+        for _ in 0..20 {
+            let r2 = ux * ux + uy * uy;
+            let scale = 1.0 + k1 * r2 + k2 * r2 * r2;
+            ux = x / scale;
+            uy = y / scale;
+        }
+        (ux, uy)
+    }
+
+    // Resamples an image rendered through this camera's distortion back to
+    // what an undistorted pinhole would have seen. Pixels that fall outside
+    // the source image are left black.
+    pub fn undistort(&self, image: &Canvas) -> Canvas {
+        let mut out = Canvas::new(image.width, image.height);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let (wx, wy) = self.image_plane_point(x as f32 + 0.5, y as f32 + 0.5);
+                let (dx, dy) = self.undistort_point(wx, wy);
+                let sx =
+                    (self.half_width - self.shift_x * self.half_width * 2.0 - dx) / self.pixel_size;
+                let sy = (self.half_height + self.shift_y * self.half_height * 2.0 - dy)
+                    / self.pixel_size;
+                if sx >= 0.0 && sy >= 0.0 && sx < image.width as f32 && sy < image.height as f32 {
+                    let color = image.pixel_at(sx as usize, sy as usize);
+                    out.write_pixel(x as usize, y as usize, color);
+                }
+            }
+        }
+        out
+    }
+
+    // With one sample and a pinhole this is a single ray through the pixel
+    // center. Otherwise samples are jittered over the pixel and, for each of
+    // them, over an n x n stratified grid on the lens. Each ray is cast at a
+    // random time while the shutter is open.
+    fn color_for_pixel(&self, world: &World, px: u32, py: u32) -> Color {
+        if let Some(view) = self.debug_view {
+            return view.color_at(world, &self.ray_for_pixel(px, py));
+        }
+        let path_traced = self.renderer == Renderer::PathTraced;
+        let (open, close) = self.shutter;
+        let mut rng = Rng::for_pixel(self.seed, px, py);
+        if self.samples <= 1 && self.lens_grid() == 1 && !path_traced && close <= open {
+            let ray = self.ray_for_pixel(px, py).set_seed(rng.next_u64());
+            return world.color_at(&ray);
+        }
+        let samples = self.samples.max(1);
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            total += self.pixel_sample(world, px, py, &mut rng, samples > 1);
+        }
+        total * (1.0 / samples as f32)
+    }
+
+    fn lens_grid(&self) -> u32 {
+        if self.aperture > 0.0 {
+            self.lens_samples.max(1)
+        } else {
+            1
+        }
+    }
+
+    // One sample of a pixel, at a random point in it when jittered and at
+    // its center otherwise, averaged over the lens grid.
+    fn pixel_sample(&self, world: &World, px: u32, py: u32, rng: &mut Rng, jitter: bool) -> Color {
+        let lens_n = self.lens_grid();
+        let path_traced = self.renderer == Renderer::PathTraced;
+        let (open, close) = self.shutter;
+        let blurred = close > open;
+        let (ox, oy) = if jitter {
+            (rng.next_f32(), rng.next_f32())
+        } else {
+            (0.5, 0.5)
+        };
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for i in 0..lens_n {
+            for j in 0..lens_n {
+                let (lens_x, lens_y) = if lens_n > 1 {
+                    concentric_disk(
+                        (i as f32 + rng.next_f32()) / lens_n as f32,
+                        (j as f32 + rng.next_f32()) / lens_n as f32,
+                    )
+                } else {
+                    (0.0, 0.0)
+                };
+                let time = if blurred {
+                    open + (close - open) * rng.next_f32()
+                } else {
+                    open
+                };
+                let ray = self
+                    .ray_at(px as f32 + ox, py as f32 + oy, lens_x, lens_y)
+                    .set_time(time)
+                    .set_seed(rng.next_u64());
+                total += if path_traced {
+                    world.trace_path(&ray, rng)
+                } else {
+                    world.color_at(&ray)
+                };
+            }
+        }
+        total * (1.0 / (lens_n * lens_n) as f32)
+    }
+
     pub fn render(&self, world: World) -> Canvas {
-        let mut image = Canvas::new(self.hsize, self.vsize);
-        for y in 0..(self.vsize - 1) {
-            for x in 0..(self.hsize - 1) {
-                let ray = self.ray_for_pixel(x, y);
-                image.write_pixel(x as usize, y as usize, world.color_at(&ray));
+        let mut image = self.render_unexposed(&world);
+        self.expose(&mut image);
+        image
+    }
+
+    // As render, also counting the work done and timing it.
+    pub fn render_with_stats(&self, world: World) -> (Canvas, RenderStats) {
+        let stopwatch = Stopwatch::start();
+        let (mut image, mut stats) = self
+            .render_pixels_with(
+                self.frame_tile(),
+                |x, y| self.color_for_pixel(&world, x, y),
+                &mut |_| {},
+                &CancelToken::new(),
+            )
+            .unwrap();
+        self.expose(&mut image);
+        stats.elapsed = stopwatch.elapsed();
+        (image, stats)
+    }
+
+    // As render, along with depth, normal, albedo and object id images. The
+    // auxiliary images take one ray per pixel, on this thread.
+    pub fn render_aovs(&self, world: World) -> Aovs {
+        let mut aovs = Aovs::new(self.render(world.clone()));
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let sample = world.surface_sample(&self.ray_for_pixel(x, y));
+                aovs.write(x as usize, y as usize, sample);
+            }
+        }
+        aovs
+    }
+
+    fn render_unexposed(&self, world: &World) -> Canvas {
+        self.render_pixels(self.frame_tile(), |x, y| self.color_for_pixel(world, x, y))
+    }
+
+    // Renders only the pixels from (x0, y0) up to but not including
+    // (x1, y1), into a canvas just big enough to hold them. The region is
+    // cut down to fit the image.
+    pub fn render_region(&self, world: World, x0: u32, y0: u32, x1: u32, y1: u32) -> Canvas {
+        let mut image = self.render_region_unexposed(&world, x0, y0, x1, y1);
+        self.expose(&mut image);
+        image
+    }
+
+    // As render_region, but developed with the given exposure rather than
+    // one metered from the region alone, so regions of one frame rendered
+    // separately match. See frame_exposure.
+    pub fn render_region_with_exposure(
+        &self,
+        world: &World,
+        x0: u32,
+        y0: u32,
+        x1: u32,
+        y1: u32,
+        exposure: f32,
+    ) -> Canvas {
+        let mut image = self.render_region_unexposed(world, x0, y0, x1, y1);
+        self.develop(&mut image, exposure);
+        image
+    }
+
+    fn render_region_unexposed(&self, world: &World, x0: u32, y0: u32, x1: u32, y1: u32) -> Canvas {
+        let (x1, y1) = (x1.min(self.hsize), y1.min(self.vsize));
+        let (x0, y0) = (x0.min(x1), y0.min(y1));
+        let region = Tile::new(x0, y0, x1 - x0, y1 - y0);
+        self.render_pixels(region, |x, y| self.color_for_pixel(world, x, y))
+    }
+
+    // The exposure for a whole frame: the manual one, or with metering on,
+    // one metered from a small preview at a sample per pixel.
+    pub fn frame_exposure(&self, world: &World) -> f32 {
+        let metering = match self.metering {
+            Some(metering) => metering,
+            None => return self.exposure,
+        };
+        let scale = (METERING_PREVIEW as f32 / self.hsize.max(self.vsize) as f32).min(1.0);
+        let hsize = ((self.hsize as f32 * scale).round() as u32).max(1);
+        let vsize = ((self.vsize as f32 * scale).round() as u32).max(1);
+        let preview = self.clone().set_size(hsize, vsize).set_samples(1);
+        preview.render_unexposed(world).metered_exposure(metering)
+    }
+
+    // Renders a scanline at a time, top to bottom, as the iterator is
+    // advanced. Auto exposure needs the whole image, so rows only get the
+    // manual exposure.
+    pub fn render_rows(&self, world: World) -> impl Iterator<Item = Vec<Color>> + '_ {
+        (0..self.vsize).map(move |y| {
+            let row = Tile::new(0, y, self.hsize, 1);
+            let mut image = self.render_pixels(row, |x, y| self.color_for_pixel(&world, x, y));
+            self.develop(&mut image, self.exposure);
+            image.grid.swap_remove(0)
+        })
+    }
+
+    fn frame_tile(&self) -> Tile {
+        Tile::new(0, 0, self.hsize, self.vsize)
+    }
+
+    // Renders one sample per pixel per pass, as many passes as the camera
+    // has samples, calling on_pass after each with the average so far and
+    // the number of passes in it. Returns the image from the last pass.
+    pub fn render_progressive<F: FnMut(&Canvas, u32)>(
+        &self,
+        world: World,
+        mut on_pass: F,
+    ) -> Canvas {
+        let passes = self.samples.max(1);
+        let mut sum = Canvas::new(self.hsize, self.vsize);
+        let mut image = sum.clone();
+        for pass in 0..passes {
+            let layer = self.render_pixels(self.frame_tile(), |x, y| {
+                let mut rng = Rng::for_pass(self.seed, pass, x, y);
+                self.pixel_sample(&world, x, y, &mut rng, passes > 1)
+            });
+            let pixels = sum.grid.iter_mut().flatten();
+            for (total, color) in pixels.zip(layer.grid.iter().flatten()) {
+                *total += *color;
             }
+            image = sum.clone();
+            image.apply_exposure(1.0 / (pass + 1) as f32);
+            self.expose(&mut image);
+            on_pass(&image, pass + 1);
         }
         image
     }
 
+    // As render, calling on_progress after every finished tile. The callback
+    // runs on the render threads, one call at a time. Returns None if the
+    // token is cancelled before the render finishes; tiles already started
+    // are finished first.
+    pub fn render_with_progress<F>(
+        &self,
+        world: World,
+        cancel: &CancelToken,
+        mut on_progress: F,
+    ) -> Option<Canvas>
+    where
+        F: FnMut(Progress) + Send,
+    {
+        let mut image = self
+            .render_pixels_with(
+                self.frame_tile(),
+                |x, y| self.color_for_pixel(&world, x, y),
+                &mut on_progress,
+                cancel,
+            )?
+            .0;
+        self.expose(&mut image);
+        Some(image)
+    }
+
+    fn render_pixels<F>(&self, region: Tile, color_at: F) -> Canvas
+    where
+        F: Fn(u32, u32) -> Color + Sync,
+    {
+        self.render_pixels_with(region, color_at, &mut |_| {}, &CancelToken::new())
+            .unwrap()
+            .0
+    }
+
+    // Renders the region's tiles into an image the size of the region.
+    fn render_pixels_with<F>(
+        &self,
+        region: Tile,
+        color_at: F,
+        on_progress: &mut (dyn FnMut(Progress) + Send),
+        cancel: &CancelToken,
+    ) -> Option<(Canvas, RenderStats)>
+    where
+        F: Fn(u32, u32) -> Color + Sync,
+    {
+        let tiles: Vec<Tile> = tiles(region.width, region.height, self.tile_size)
+            .into_iter()
+            .map(|t| Tile::new(t.x + region.x, t.y + region.y, t.width, t.height))
+            .collect();
+        let mut image = Canvas::new(region.width, region.height);
+        let mut write_tile = |index: usize, colors: Vec<Color>, progress| {
+            for ((x, y), color) in tiles[index].pixels().zip(colors) {
+                let (x, y) = (x - region.x, y - region.y);
+                image.write_pixel(x as usize, y as usize, color);
+            }
+            on_progress(progress);
+        };
+        let stats = self.render_tiles(&tiles, color_at, &mut write_tile, cancel)?;
+        Some((image, stats))
+    }
+
+    // Worker threads take tiles in order until none are left, handing each
+    // finished tile's index and colors to on_tile, one call at a time. The
+    // stats count the work done on the worker threads, without timing it.
+    // With one thread the work is done on the calling thread, which is all
+    // wasm32-unknown-unknown can do.
+    fn render_tiles<F>(
+        &self,
+        tiles: &[Tile],
+        color_at: F,
+        on_tile: &mut (dyn FnMut(usize, Vec<Color>, Progress) + Send),
+        cancel: &CancelToken,
+    ) -> Option<RenderStats>
+    where
+        F: Fn(u32, u32) -> Color + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let claim = || {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            tiles.get(index).map(|&tile| (index, tile))
+        };
+        let progress = Progress {
+            tiles_done: 0,
+            tiles_total: tiles.len(),
+            pixels_done: 0,
+            pixels_total: tiles.iter().map(|t| t.pixel_count() as u64).sum(),
+        };
+        let state = Mutex::new((progress, on_tile, RenderStats::default()));
+        let work = || {
+            while let Some((index, tile)) = claim() {
+                if cancel.is_cancelled() {
+                    break;
+                }
+                let before = stats::thread_counts();
+                let colors: Vec<Color> = tile.pixels().map(|(x, y)| color_at(x, y)).collect();
+                let counted = stats::thread_counts() - before;
+                let mut state = state.lock().unwrap();
+                let (progress, on_tile, stats) = &mut *state;
+                *stats = *stats + counted;
+                progress.tiles_done += 1;
+                progress.pixels_done += tile.pixel_count() as u64;
+                on_tile(index, colors, *progress);
+            }
+        };
+        let threads = self.threads.min(tiles.len());
+        if threads <= 1 {
+            work();
+        } else {
+            thread::scope(|s| {
+                for _ in 0..threads {
+                    s.spawn(work);
+                }
+            });
+        }
+        if cancel.is_cancelled() {
+            return None;
+        }
+        Some(state.into_inner().unwrap().2)
+    }
+
+    // As render, rendering only the tiles the checkpoint doesn't have yet
+    // and adding each to it as it finishes. on_tile is then called, on the
+    // render threads and one call at a time, e.g. to save the checkpoint
+    // now and again. The checkpoint must be for this camera's image size and
+    // tile size.
+    pub fn render_resumable<F>(
+        &self,
+        world: World,
+        checkpoint: &mut Checkpoint,
+        mut on_tile: F,
+    ) -> Canvas
+    where
+        F: FnMut(&Checkpoint) + Send,
+    {
+        assert!(
+            checkpoint.fits(self.hsize, self.vsize, self.tile_size),
+            "checkpoint is for a different image"
+        );
+        let (indices, todo): (Vec<usize>, Vec<Tile>) = checkpoint
+            .tiles()
+            .into_iter()
+            .enumerate()
+            .filter(|&(index, _)| !checkpoint.is_done(index))
+            .unzip();
+        let mut finish_tile = |index: usize, colors: Vec<Color>, _| {
+            checkpoint.finish_tile(indices[index], &colors);
+            on_tile(checkpoint);
+        };
+        self.render_tiles(
+            &todo,
+            |x, y| self.color_for_pixel(&world, x, y),
+            &mut finish_tile,
+            &CancelToken::new(),
+        );
+        let mut image = checkpoint.pixels().clone();
+        self.expose(&mut image);
+        image
+    }
+
+    // The tiles render splits the image into.
+    pub fn tiles(&self) -> Vec<Tile> {
+        tiles(self.hsize, self.vsize, self.tile_size)
+    }
+
+    // Colors for every pixel in the tile, a row at a time and unexposed.
+    pub fn render_tile(&self, world: &World, tile: Tile) -> Vec<Color> {
+        tile.pixels()
+            .map(|(x, y)| self.color_for_pixel(world, x, y))
+            .collect()
+    }
+
+    fn expose(&self, image: &mut Canvas) {
+        let exposure = match self.metering {
+            Some(metering) => image.metered_exposure(metering),
+            None => self.exposure,
+        };
+        self.develop(image, exposure);
+    }
+
+    // Applies exposure, then the exposure compensation, and tags the image
+    // with the camera's transfer for its 8-bit outputs. The colors stay
+    // linear.
+    fn develop(&self, image: &mut Canvas, exposure: f32) {
+        image.transfer = self.transfer;
+        if self.debug_view.is_some() {
+            return;
+        }
+        image.apply_exposure(exposure * self.exposure_stops.exp2());
+    }
+
+    // Left and right eye cameras, interocular apart and looking in parallel
+    // either side of this one.
+    pub fn stereo_eyes(&self, interocular: f32) -> (Camera, Camera) {
+        // Camera space x points to the left of the image.
+        let half = interocular / 2.0;
+        let left = self
+            .clone()
+            .set_transform(Matrix::translation(-half, 0.0, 0.0) * self.transform().clone());
+        let right = self
+            .clone()
+            .set_transform(Matrix::translation(half, 0.0, 0.0) * self.transform().clone());
+        (left, right)
+    }
+
+    // Renders both eyes into one canvas twice as wide, left eye on the left,
+    // for side-by-side stereo viewers. Both halves share one exposure.
+    pub fn render_stereo(&self, world: World, interocular: f32) -> Canvas {
+        let (left, right) = self.stereo_eyes(interocular);
+        let left = left.render_unexposed(&world);
+        let right = right.render_unexposed(&world);
+        let mut image = Canvas::new(self.hsize * 2, self.vsize);
+        for y in 0..self.vsize as usize {
+            for x in 0..self.hsize as usize {
+                image.write_pixel(x, y, left.pixel_at(x, y));
+                image.write_pixel(x + self.hsize as usize, y, right.pixel_at(x, y));
+            }
+        }
+        self.expose(&mut image);
+        image
+    }
+
+    // The camera transform for a turntable frame: looking at target from
+    // radius away and elevation radians above it, turned angle radians
+    // around it from in front, on the -z side.
+    pub fn turntable_transform(target: Point, radius: f32, elevation: f32, angle: f32) -> Matrix {
+        let (sin_e, cos_e) = elevation.sin_cos();
+        let (sin_a, cos_a) = angle.sin_cos();
+        let offset = Vector::new(sin_a * cos_e, sin_e, -cos_a * cos_e) * radius;
+        Matrix::view_transform(target + offset, target, Vector::new(0.0, 1.0, 0.0))
+    }
+
+    // frames images orbiting once around target, evenly spaced and starting
+    // from in front, for showing a model from every side. Feed them to
+    // write_gif for an animation that loops.
+    pub fn render_turntable(
+        &self,
+        world: World,
+        target: Point,
+        radius: f32,
+        elevation: f32,
+        frames: u32,
+    ) -> Vec<Canvas> {
+        (0..frames)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f32 / frames as f32;
+                let transform = Camera::turntable_transform(target, radius, elevation, angle);
+                self.clone().set_transform(transform).render(world.clone())
+            })
+            .collect()
+    }
+
+    // The object seen through the middle of pixel px, py, and the point on it
+    // in world space, for clicking on objects in an editor.
+    pub fn pick(&self, world: &World, px: u32, py: u32) -> Option<(ObjectId, Point)> {
+        world.pick(&self.ray_for_pixel(px, py))
+    }
+
     pub fn hsize(&self) -> u32 {
         self.hsize
     }
@@ -76,24 +727,330 @@ impl Camera {
     }
 
     pub fn transform(&self) -> &Matrix {
-        &self.transform
+        self.transform.matrix()
     }
 
     pub fn pixel_size(&self) -> f32 {
         self.pixel_size
     }
 
+    pub fn shift(&self) -> (f32, f32) {
+        (self.shift_x, self.shift_y)
+    }
+
+    pub fn tilt(&self) -> (f32, f32) {
+        (self.tilt_x, self.tilt_y)
+    }
+
+    pub fn aperture(&self) -> f32 {
+        self.aperture
+    }
+
+    pub fn focal_distance(&self) -> f32 {
+        self.focal_distance
+    }
+
+    pub fn lens_samples(&self) -> u32 {
+        self.lens_samples
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    pub fn distortion(&self) -> (f32, f32) {
+        self.distortion
+    }
+
+    pub fn metering(&self) -> Option<Metering> {
+        self.metering
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn renderer(&self) -> Renderer {
+        self.renderer
+    }
+
+    pub fn debug_view(&self) -> Option<DebugView> {
+        self.debug_view
+    }
+
+    pub fn shutter(&self) -> (f32, f32) {
+        self.shutter
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    pub fn exposure_stops(&self) -> f32 {
+        self.exposure_stops
+    }
+
+    pub fn transfer(&self) -> Transfer {
+        self.transfer
+    }
+
+    // Changes the output resolution, keeping the field of view and every
+    // other setting.
+    pub fn set_size(mut self, hsize: u32, vsize: u32) -> Self {
+        let (pixel_size, half_width, half_height) = Camera::frame(hsize, vsize, self.field_of_view);
+        self.hsize = hsize;
+        self.vsize = vsize;
+        self.pixel_size = pixel_size;
+        self.half_width = half_width;
+        self.half_height = half_height;
+        self
+    }
+
+    // Applies the profile's sample count and scales the current resolution.
+    pub fn set_quality(self, quality: &Quality) -> Self {
+        let hsize = ((self.hsize as f32 * quality.resolution_scale).round() as u32).max(1);
+        let vsize = ((self.vsize as f32 * quality.resolution_scale).round() as u32).max(1);
+        self.set_size(hsize, vsize).set_samples(quality.samples)
+    }
+
     pub fn set_transform(mut self, new: Matrix) -> Self {
-        self.transform = new;
+        self.transform = Transform::new(new);
+        self
+    }
+
+    // Places the camera at from, looking towards to, with up roughly
+    // upwards in the image.
+    pub fn look_at(self, from: Point, to: Point, up: Vector) -> Self {
+        self.set_transform(Matrix::view_transform(from, to, up))
+    }
+
+    // Lens shift moves the image plane instead of rotating the camera, so
+    // verticals stay parallel. Given as a fraction of the frame width/height,
+    // positive values shift the frame right and up.
+    pub fn set_shift(mut self, x: f32, y: f32) -> Self {
+        self.shift_x = x;
+        self.shift_y = y;
+        self
+    }
+
+    // Lens tilt leans the plane of focus instead of keeping it square to the
+    // view, so with an aperture a receding floor or wall can be sharp from
+    // front to back. The plane still crosses the view axis at the focal
+    // distance; angles in radians, positive x leans its top away from the
+    // camera and positive y its right side.
+    pub fn set_tilt(mut self, x: f32, y: f32) -> Self {
+        self.tilt_x = x;
+        self.tilt_y = y;
+        self
+    }
+
+    pub fn set_aperture(mut self, radius: f32) -> Self {
+        self.aperture = radius;
+        self
+    }
+
+    pub fn set_focal_distance(mut self, distance: f32) -> Self {
+        self.focal_distance = distance;
+        self
+    }
+
+    // Antialiasing samples per pixel, each at a random point in the pixel.
+    pub fn set_samples(mut self, n: u32) -> Self {
+        self.samples = n;
+        self
+    }
+
+    // Seeds every random choice made while rendering, so stochastic renders
+    // can be reproduced exactly.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // The times the shutter opens and closes. Moving shapes are blurred over
+    // the interval; with open equal to close every ray is cast at that time.
+    pub fn set_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter = (open, close);
+        self
+    }
+
+    // Depth of field, lens shift and distortion only apply to the
+    // perspective projection.
+    pub fn set_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    // Defaults to one thread per available core.
+    pub fn set_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // Tiles are size x size pixels, smaller at the right and bottom edges.
+    pub fn set_tile_size(mut self, size: u32) -> Self {
+        self.tile_size = size.max(1);
+        self
+    }
+
+    pub fn set_renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    // Renders a false-color picture in place of the usual image, or the
+    // usual image again for None.
+    pub fn set_debug_view(mut self, view: Option<DebugView>) -> Self {
+        self.debug_view = view;
+        self
+    }
+
+    // Lens samples are taken on an n x n jittered grid over the aperture.
+    pub fn set_lens_samples(mut self, n: u32) -> Self {
+        self.lens_samples = n;
+        self
+    }
+
+    pub fn set_exposure(mut self, exposure: f32) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    // When set, render meters the finished image and picks the exposure
+    // itself, ignoring the manual exposure.
+    pub fn set_auto_exposure(mut self, metering: Option<Metering>) -> Self {
+        self.metering = metering;
+        self
+    }
+
+    // Exposure compensation on top of the manual or metered exposure. Each
+    // stop doubles the brightness.
+    pub fn set_exposure_stops(mut self, stops: f32) -> Self {
+        self.exposure_stops = stops;
+        self
+    }
+
+    // How rendered images are encoded when written as 8-bit output; the
+    // default is sRGB. Float outputs such as PFM and HDR stay linear.
+    pub fn set_transfer(mut self, transfer: Transfer) -> Self {
+        self.transfer = transfer;
+        self
+    }
+
+    // A plain 1 / gamma power in place of the sRGB curve. 2.2 suits most
+    // screens; 1.0 leaves the output linear.
+    pub fn set_gamma(self, gamma: f32) -> Self {
+        self.set_transfer(Transfer::Gamma(gamma))
+    }
+
+    pub fn set_ev100(mut self, ev100: f32) -> Self {
+        self.exposure = exposure_from_ev100(ev100);
+        self
+    }
+
+    // Radial lens distortion, r' = r * (1 + k1 * r^2 + k2 * r^4), measured on
+    // the image plane. Positive coefficients give barrel distortion, negative
+    // ones pincushion.
+    pub fn set_distortion(mut self, k1: f32, k2: f32) -> Self {
+        self.distortion = (k1, k2);
+        self
+    }
+}
+
+// Collects a camera's basic setup and checks it before building, so a bad
+// size or view is reported instead of rendering NaNs.
+pub struct CameraBuilder {
+    hsize: u32,
+    vsize: u32,
+    field_of_view: f32,
+    from: Point,
+    to: Point,
+    up: Vector,
+}
+
+impl CameraBuilder {
+    // A 60 degree camera at the origin looking down -z.
+    pub fn new(hsize: u32, vsize: u32) -> CameraBuilder {
+        CameraBuilder {
+            hsize,
+            vsize,
+            field_of_view: PI / 3.0,
+            from: Point::new(0.0, 0.0, 0.0),
+            to: Point::new(0.0, 0.0, -1.0),
+            up: Vector::new(0.0, 1.0, 0.0),
+        }
+    }
+
+    pub fn field_of_view(mut self, radians: f32) -> Self {
+        self.field_of_view = radians;
+        self
+    }
+
+    pub fn field_of_view_degrees(self, degrees: f32) -> Self {
+        self.field_of_view(degrees.to_radians())
+    }
+
+    pub fn look_at(mut self, from: Point, to: Point, up: Vector) -> Self {
+        self.from = from;
+        self.to = to;
+        self.up = up;
         self
     }
+
+    pub fn build(self) -> Result<Camera, String> {
+        if self.hsize == 0 || self.vsize == 0 {
+            return Err(format!("image size {}x{} is empty", self.hsize, self.vsize));
+        }
+        if !(self.field_of_view > 0.0 && self.field_of_view < PI) {
+            return Err(format!(
+                "field of view {} degrees is not between 0 and 180",
+                self.field_of_view.to_degrees()
+            ));
+        }
+        let forward = self.to - self.from;
+        if forward.magnitude() == 0.0 {
+            return Err("camera looks at its own position".to_string());
+        }
+        if forward.normalize().cross(&self.up).magnitude() < 0.0001 {
+            return Err("up vector is parallel to the view direction".to_string());
+        }
+        Ok(Camera::new(self.hsize, self.vsize, self.field_of_view)
+            .look_at(self.from, self.to, self.up))
+    }
+}
+
+// Maps the unit square onto the unit disk while keeping strata evenly sized.
+fn concentric_disk(u: f32, v: f32) -> (f32, f32) {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, FRAC_PI_2 - FRAC_PI_4 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::color::Color;
-    use crate::vector::Vector;
+    use crate::utils::fp_equal;
 
     #[test]
     fn constructing_a_camera() {
@@ -101,7 +1058,7 @@ mod tests {
         let vsize = 120;
         let field_of_view = std::f32::consts::PI / 2.0;
         let c = Camera::new(hsize, vsize, field_of_view);
-        assert_eq!(c.hsize(), 160 as u32);
+        assert_eq!(c.hsize(), 160);
         assert_eq!(c.vsize(), 120);
         assert_eq!(c.field_of_view(), std::f32::consts::PI / 2.0);
         assert_eq!(c.transform(), &Matrix::identity());
@@ -146,6 +1103,283 @@ mod tests {
         assert_eq!(r.direction(), Vector::new(trt, 0.0, -trt));
     }
 
+    #[test]
+    fn camera_has_no_lens_shift_by_default() {
+        let c = Camera::new(201, 101, std::f32::consts::PI / 2.0);
+        assert_eq!(c.shift(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn vertical_lens_shift_moves_frame_up() {
+        let c = Camera::new(101, 101, std::f32::consts::PI / 2.0).set_shift(0.0, 0.25);
+        let r = c.ray_for_pixel(50, 50);
+        assert_eq!(r.origin(), Point::new(0.0, 0.0, 0.0));
+        assert_eq!(r.direction(), Vector::new(0.0, 0.44721, -0.89443));
+    }
+
+    #[test]
+    fn horizontal_lens_shift_moves_frame_right() {
+        let c = Camera::new(201, 101, std::f32::consts::PI / 2.0).set_shift(0.25, 0.0);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.direction(), Vector::new(-0.44721, 0.0, -0.89443));
+    }
+
+    #[test]
+    fn lens_shift_keeps_image_plane_parallel() {
+        let c = Camera::new(201, 101, std::f32::consts::PI / 2.0).set_shift(0.0, 0.5);
+        let top = c.ray_for_pixel(100, 0).direction();
+        let bottom = c.ray_for_pixel(100, 100).direction();
+        assert_eq!(top.x, 0.0);
+        assert_eq!(bottom.x, 0.0);
+        assert!(top.y > 0.0 && bottom.y > 0.0);
+    }
+
+    #[test]
+    fn camera_from_physical_settings() {
+        let p = PhysicalCamera::default()
+            .set_sensor_width(36.0)
+            .set_focal_length(18.0)
+            .set_f_stop(2.0)
+            .set_focus_distance(3.0);
+        let c = Camera::from_physical(200, 125, &p);
+        assert_eq!(c.field_of_view(), std::f32::consts::PI / 2.0);
+        assert_eq!(c.pixel_size(), 0.01);
+        assert_eq!(c.aperture(), p.aperture_radius());
+        assert_eq!(c.focal_distance(), 3.0);
+        assert_eq!(c.exposure(), p.exposure());
+    }
+
+    #[test]
+    fn pinhole_camera_ignores_focal_distance() {
+        let c = Camera::new(201, 101, std::f32::consts::PI / 2.0).set_focal_distance(7.0);
+        let r = c.ray_for_pixel(0, 0);
+        assert_eq!(r.origin(), Point::new(0.0, 0.0, 0.0));
+        assert_eq!(r.direction(), Vector::new(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn lens_rays_converge_on_focal_plane() {
+        let c = Camera::new(101, 101, std::f32::consts::PI / 2.0)
+            .set_aperture(0.5)
+            .set_focal_distance(2.0);
+        let r = c.ray_through_lens(50, 50, 1.0, 0.0);
+        assert_eq!(r.origin(), Point::new(0.5, 0.0, 0.0));
+        assert_eq!(r.direction(), Vector::new(-0.24254, 0.0, -0.97014));
+    }
+
+    #[test]
+    fn tilted_focal_plane_leans_away_at_the_top() {
+        let c = Camera::new(101, 101, std::f32::consts::PI / 2.0)
+            .set_aperture(0.5)
+            .set_focal_distance(2.0)
+            .set_tilt(std::f32::consts::PI / 4.0, 0.0);
+        assert_eq!(c.tilt(), (std::f32::consts::PI / 4.0, 0.0));
+        // The centre is still in focus at the focal distance.
+        let r = c.ray_through_lens(50, 50, 1.0, 0.0);
+        assert_eq!(r.direction(), Vector::new(-0.24254, 0.0, -0.97014));
+        // Higher up the frame the plane of focus is farther away, at
+        // z = -2 - y. The ray from the edge of the lens meets the pinhole ray
+        // there, where x is 0.
+        let r = c.ray_through_lens(50, 25, 1.0, 0.0);
+        let focus = r.position(0.5 / -r.direction().x);
+        assert!(focus.z < -3.9);
+        assert!(fp_equal(focus.z, -2.0 - focus.y));
+    }
+
+    #[test]
+    fn concentric_disk_samples_stay_on_unit_disk() {
+        for i in 0..=10 {
+            for j in 0..=10 {
+                let (x, y) = concentric_disk(i as f32 / 10.0, j as f32 / 10.0);
+                assert!(x * x + y * y <= 1.0 + 0.00001);
+            }
+        }
+        assert_eq!(concentric_disk(0.5, 0.5), (0.0, 0.0));
+    }
+
+    #[test]
+    fn distortion_leaves_center_ray_alone() {
+        let c = Camera::new(201, 101, std::f32::consts::PI / 2.0).set_distortion(0.3, 0.1);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.direction(), Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn barrel_distortion_widens_corner_rays() {
+        let plain = Camera::new(201, 101, std::f32::consts::PI / 2.0);
+        let barrel = Camera::new(201, 101, std::f32::consts::PI / 2.0).set_distortion(0.2, 0.0);
+        let pincushion =
+            Camera::new(201, 101, std::f32::consts::PI / 2.0).set_distortion(-0.2, 0.0);
+        let d = plain.ray_for_pixel(0, 0).direction();
+        let db = barrel.ray_for_pixel(0, 0).direction();
+        let dp = pincushion.ray_for_pixel(0, 0).direction();
+        assert!(db.x > d.x && db.y > d.y);
+        assert!(dp.x < d.x && dp.y < d.y);
+    }
+
+    #[test]
+    fn undistort_point_inverts_distort() {
+        let c = Camera::new(100, 100, std::f32::consts::PI / 2.0).set_distortion(0.1, 0.05);
+        let (x, y) = c.distort(0.6, -0.3);
+        let (ux, uy) = c.undistort_point(x, y);
+        assert!(crate::utils::fp_equal(ux, 0.6));
+        assert!(crate::utils::fp_equal(uy, -0.3));
+    }
+
+    #[test]
+    fn undistort_without_distortion_is_identity() {
+        let c = Camera::new(4, 3, std::f32::consts::PI / 2.0);
+        let mut image = Canvas::new(4, 3);
+        image.write_pixel(1, 2, Color::new(1.0, 0.5, 0.25));
+        image.write_pixel(3, 0, Color::new(0.0, 1.0, 0.0));
+        let out = c.undistort(&image);
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(out.pixel_at(x, y), image.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn exposure_from_ev() {
+        let c = Camera::new(11, 11, std::f32::consts::PI / 2.0).set_ev100(0.0);
+        assert_eq!(c.exposure(), 1.0 / 1.2);
+        let c = c.set_ev100(1.0);
+        assert_eq!(c.exposure(), 1.0 / 2.4);
+    }
+
+    #[test]
+    fn rendering_applies_exposure() {
+        let w = World::default();
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let c = Camera::new(11, 11, std::f32::consts::PI / 2.0)
+            .set_transform(Matrix::view_transform(from, to, up))
+            .set_exposure(0.5);
+        let image = c.render(w);
+        assert_eq!(
+            image.pixel_at(5, 5),
+            Color::new(0.38066, 0.47583, 0.2855) * 0.5
+        );
+    }
+
+    #[test]
+    fn auto_exposure_overrides_manual_exposure() {
+        let w = World::default();
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let c = Camera::new(11, 11, std::f32::consts::PI / 2.0)
+            .set_transform(Matrix::view_transform(from, to, up));
+        let raw = c.render(World::default());
+        let exposure = raw.metered_exposure(Metering::Average);
+        let c = c
+            .set_exposure(100.0)
+            .set_auto_exposure(Some(Metering::Average));
+        let image = c.render(w);
+        assert_eq!(image.pixel_at(5, 5), raw.pixel_at(5, 5) * exposure);
+    }
+
+    fn jittered_camera(seed: u64) -> Camera {
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        Camera::new(11, 11, std::f32::consts::PI / 2.0)
+            .set_transform(Matrix::view_transform(from, to, up))
+            .set_aperture(0.05)
+            .set_focal_distance(4.0)
+            .set_lens_samples(2)
+            .set_samples(2)
+            .set_seed(seed)
+    }
+
+    #[test]
+    fn camera_defaults_to_one_sample() {
+        let c = Camera::new(11, 11, std::f32::consts::PI / 2.0);
+        assert_eq!(c.samples(), 1);
+        assert_eq!(c.seed(), 0);
+    }
+
+    #[test]
+    fn rendering_counts_its_work() {
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let from = Point::new(0.0, 0.0, -5.0);
+        // Looking away, every ray misses both spheres.
+        let away = Matrix::view_transform(from, Point::new(0.0, 0.0, -10.0), up);
+        let c = Camera::new(3, 3, std::f32::consts::PI / 2.0)
+            .set_transform(away)
+            .set_threads(2);
+        let (_, stats) = c.render_with_stats(World::default());
+        assert_eq!(stats.rays_cast, 9);
+        assert_eq!(stats.intersection_tests, 18);
+        assert_eq!(stats.shading_calls, 0);
+
+        // A hit is shaded once and casts a shadow ray.
+        let towards = Matrix::view_transform(from, Point::new(0.0, 0.0, 0.0), up);
+        let c = Camera::new(1, 1, std::f32::consts::PI / 2.0).set_transform(towards);
+        let (image, stats) = c.render_with_stats(World::default());
+        assert_eq!(
+            image.pixel_at(0, 0),
+            c.render(World::default()).pixel_at(0, 0)
+        );
+        assert_eq!(stats.rays_cast, 2);
+        assert_eq!(stats.intersection_tests, 4);
+        assert_eq!(stats.shading_calls, 1);
+    }
+
+    #[test]
+    fn resuming_renders_only_the_missing_tiles() {
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let c = Camera::new(4, 4, std::f32::consts::PI / 2.0)
+            .set_transform(Matrix::view_transform(from, to, up))
+            .set_tile_size(2);
+        let marker = Color::new(0.0, 1.0, 0.0);
+        let mut checkpoint = Checkpoint::new(4, 4, 2);
+        checkpoint.finish_tile(0, &[marker; 4]);
+
+        let mut saves = 0;
+        let image = c.render_resumable(World::default(), &mut checkpoint, |_| saves += 1);
+        assert_eq!(saves, 3);
+        assert!(checkpoint.is_complete());
+        let full = c.render(World::default());
+        assert_eq!(image.pixel_at(1, 1), marker);
+        assert_eq!(image.pixel_at(2, 1), full.pixel_at(2, 1));
+        assert_eq!(image.pixel_at(1, 2), full.pixel_at(1, 2));
+    }
+
+    #[test]
+    fn renders_with_same_seed_are_identical() {
+        let a = jittered_camera(3).render(World::default());
+        let b = jittered_camera(3).render(World::default());
+        assert!(a.grid == b.grid);
+    }
+
+    #[test]
+    fn renders_with_different_seeds_differ() {
+        let a = jittered_camera(3).render(World::default());
+        let b = jittered_camera(4).render(World::default());
+        assert!(a.grid != b.grid);
+    }
+
+    #[test]
+    fn resizing_camera_keeps_field_of_view() {
+        let c = Camera::new(125, 200, std::f32::consts::PI / 2.0).set_size(200, 125);
+        assert_eq!(c.hsize(), 200);
+        assert_eq!(c.vsize(), 125);
+        assert_eq!(c.pixel_size(), 0.01);
+    }
+
+    #[test]
+    fn applying_quality_profile() {
+        let c = Camera::new(200, 100, std::f32::consts::PI / 2.0).set_quality(&Quality::draft());
+        assert_eq!(c.hsize(), 100);
+        assert_eq!(c.vsize(), 50);
+        assert_eq!(c.samples(), Quality::draft().samples);
+    }
+
     #[test]
     fn rendering_world_with_camera() {
         let w = World::default();
@@ -157,4 +1391,424 @@ mod tests {
         let image = c.render(w);
         assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
     }
+
+    #[test]
+    fn picking_an_object_under_a_pixel() {
+        let w = World::default();
+        let from = Point::new(0.0, 0.0, -5.0);
+        let c = Camera::new(11, 11, std::f32::consts::PI / 2.0).set_transform(
+            Matrix::view_transform(from, Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        );
+        let (id, point) = c.pick(&w, 5, 5).unwrap();
+        assert_eq!(id, w.pick(&c.ray_for_pixel(5, 5)).unwrap().0);
+        assert_eq!(point, Point::new(0.0, 0.0, -1.0));
+        assert!(c.pick(&w, 0, 0).is_none());
+    }
+
+    #[test]
+    fn rendering_auxiliary_images() {
+        let w = World::default();
+        let from = Point::new(0.0, 0.0, -5.0);
+        let c = Camera::new(11, 11, std::f32::consts::PI / 2.0).set_transform(
+            Matrix::view_transform(from, Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        );
+        let aovs = c.render_aovs(w.clone());
+        assert_eq!(aovs.beauty, c.render(w.clone()));
+        assert_eq!(aovs.depth.pixel_at(5, 5), Color::new(4.0, 4.0, 4.0));
+        assert_eq!(aovs.normal.pixel_at(5, 5), Color::new(0.0, 0.0, -1.0));
+        assert_eq!(aovs.albedo.pixel_at(5, 5), Color::new(0.8, 1.0, 0.6));
+        assert_eq!(aovs.object_id_at(5, 5), Some(w.object_ids()[0]));
+        assert_eq!(aovs.object_id_at(0, 0), None);
+        assert_eq!(aovs.albedo.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(aovs.depth.pixel_at(0, 0).red, f32::INFINITY);
+    }
+
+    #[test]
+    fn rendering_a_debug_view() {
+        let w = World::default();
+        let from = Point::new(0.0, 0.0, -5.0);
+        let c = Camera::new(11, 11, std::f32::consts::PI / 2.0)
+            .set_transform(Matrix::view_transform(
+                from,
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .set_exposure(4.0)
+            .set_debug_view(Some(DebugView::Normals));
+        let image = c.render(w.clone());
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.5, 0.5, 0.0));
+        assert_eq!(image.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        let usual = c.set_debug_view(None).set_exposure(1.0).render(w);
+        assert_eq!(usual.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn turntable_orbits_the_target() {
+        let target = Point::new(0.0, 1.0, 0.0);
+        let front = Camera::turntable_transform(target, 5.0, 0.0, 0.0);
+        let expected = Matrix::view_transform(
+            Point::new(0.0, 1.0, -5.0),
+            target,
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(front, expected);
+        let side = Camera::turntable_transform(target, 5.0, PI / 4.0, PI / 2.0);
+        let eye = side.inverse() * Point::new(0.0, 0.0, 0.0);
+        let h = 5.0 / 2.0_f32.sqrt();
+        assert_eq!(eye, Point::new(h, 1.0 + h, 0.0));
+
+        let c = Camera::new(11, 11, PI / 2.0);
+        let frames = c.render_turntable(World::default(), Point::new(0.0, 0.0, 0.0), 5.0, 0.0, 4);
+        assert_eq!(frames.len(), 4);
+        let first = c
+            .clone()
+            .set_transform(Camera::turntable_transform(
+                Point::new(0.0, 0.0, 0.0),
+                5.0,
+                0.0,
+                0.0,
+            ))
+            .render(World::default());
+        assert_eq!(frames[0], first);
+        assert_eq!(
+            frames[0].pixel_at(5, 5),
+            Color::new(0.38066, 0.47583, 0.2855)
+        );
+    }
+
+    #[test]
+    fn path_traced_render_is_reproducible() {
+        let from = Point::new(0.0, 0.0, -5.0);
+        let c = || {
+            Camera::new(5, 5, std::f32::consts::PI / 2.0)
+                .set_transform(Matrix::view_transform(
+                    from,
+                    Point::new(0.0, 0.0, 0.0),
+                    Vector::new(0.0, 1.0, 0.0),
+                ))
+                .set_renderer(Renderer::PathTraced)
+                .set_samples(4)
+                .set_seed(7)
+        };
+        assert_eq!(c().renderer(), Renderer::PathTraced);
+        let a = c().render(World::default());
+        let b = c().render(World::default());
+        assert!(a.grid == b.grid);
+        assert!(a.pixel_at(2, 2).green > 0.0);
+    }
+
+    #[test]
+    fn open_shutter_blurs_moving_shapes() {
+        use crate::moving::Moving;
+        use crate::point_light::PointLight;
+        use crate::sphere::Sphere;
+        let sphere = Moving::new(
+            Box::new(Sphere::default()),
+            Matrix::translation(-1.5, 0.0, 0.0),
+            Matrix::translation(1.5, 0.0, 0.0),
+        );
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let world = || World::new(vec![Box::new(sphere.clone())], Box::new(light.clone()));
+        let c =
+            Camera::new(9, 3, std::f32::consts::PI / 2.0).set_transform(Matrix::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ));
+        // Still at time 0, the sphere covers the left of the frame only.
+        let still = c.render(world());
+        assert!(still.pixel_at(2, 1).red > 0.5);
+        assert_eq!(still.pixel_at(6, 1), Color::new(0.0, 0.0, 0.0));
+        let c = c.set_shutter(0.0, 1.0).set_samples(16);
+        assert_eq!(c.shutter(), (0.0, 1.0));
+        let blurred = c.render(world());
+        assert!(blurred.pixel_at(2, 1).red < still.pixel_at(2, 1).red);
+        assert!(blurred.pixel_at(6, 1).red > 0.0);
+    }
+
+    #[test]
+    fn equirectangular_camera_sees_all_around() {
+        let c = Camera::new(200, 100, PI / 2.0).set_projection(Projection::Equirectangular);
+        assert_eq!(c.projection(), Projection::Equirectangular);
+        let center = c.ray_at(100.0, 50.0, 0.0, 0.0);
+        assert_eq!(center.origin(), Point::new(0.0, 0.0, 0.0));
+        assert_eq!(center.direction(), Vector::new(0.0, 0.0, -1.0));
+        let left = c.ray_at(50.0, 50.0, 0.0, 0.0);
+        assert_eq!(left.direction(), Vector::new(1.0, 0.0, 0.0));
+        let behind = c.ray_at(0.0, 50.0, 0.0, 0.0);
+        assert_eq!(behind.direction(), Vector::new(0.0, 0.0, 1.0));
+        let up = c.ray_at(100.0, 0.0, 0.0, 0.0);
+        assert_eq!(up.direction(), Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn fisheye_angle_grows_evenly_from_center() {
+        let c = Camera::new(200, 100, PI)
+            .set_projection(Projection::Fisheye)
+            .set_transform(Matrix::rotation_y(PI / 2.0));
+        let center = c.ray_at(100.0, 50.0, 0.0, 0.0);
+        assert_eq!(center.direction(), Vector::new(1.0, 0.0, 0.0));
+        let c = c.set_transform(Matrix::identity());
+        let edge = c.ray_at(200.0, 50.0, 0.0, 0.0);
+        assert_eq!(edge.direction(), Vector::new(-1.0, 0.0, 0.0));
+        let halfway = c.ray_at(100.0, 0.0, 0.0, 0.0);
+        let h = (PI / 4.0).sin();
+        assert_eq!(halfway.direction(), Vector::new(0.0, h, -h));
+    }
+
+    #[test]
+    fn stereo_eyes_sit_either_side_of_camera() {
+        let c = Camera::new(11, 11, PI / 2.0);
+        let (left, right) = c.stereo_eyes(0.2);
+        assert_eq!(left.ray_for_pixel(5, 5).origin(), Point::new(0.1, 0.0, 0.0));
+        assert_eq!(
+            right.ray_for_pixel(5, 5).origin(),
+            Point::new(-0.1, 0.0, 0.0)
+        );
+        assert_eq!(
+            left.ray_for_pixel(5, 5).direction(),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn stereo_render_places_eyes_side_by_side() {
+        let c = Camera::new(11, 11, PI / 2.0).set_transform(Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = c.render_stereo(World::default(), 0.0);
+        assert_eq!(image.width, 22);
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(image.pixel_at(16, 5), Color::new(0.38066, 0.47583, 0.2855));
+        let image = c.render_stereo(World::default(), 1.0);
+        assert!(image.pixel_at(5, 5) != image.pixel_at(16, 5));
+    }
+
+    #[test]
+    fn thread_count_and_tile_size_do_not_change_the_image() {
+        let c = || {
+            Camera::new(23, 17, PI / 2.0)
+                .set_transform(Matrix::view_transform(
+                    Point::new(0.0, 0.0, -5.0),
+                    Point::new(0.0, 0.0, 0.0),
+                    Vector::new(0.0, 1.0, 0.0),
+                ))
+                .set_samples(2)
+        };
+        let single = c()
+            .set_threads(1)
+            .set_tile_size(100)
+            .render(World::default());
+        let tiled = c().set_threads(4).set_tile_size(5).render(World::default());
+        assert!(single.grid == tiled.grid);
+        assert_eq!(c().set_tile_size(5).tiles().len(), 20);
+    }
+
+    #[test]
+    fn render_covers_every_pixel() {
+        let w = World::new(vec![], World::default().light().box_clone()).set_background(
+            crate::background::Background::Solid(Color::new(0.5, 0.5, 0.5)),
+        );
+        let image = Camera::new(4, 3, PI / 2.0).render(w);
+        assert_eq!(image.pixel_at(3, 2), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn progressive_render_reports_every_pass() {
+        let c = Camera::new(11, 11, PI / 2.0)
+            .set_transform(Matrix::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .set_samples(16);
+        let mut passes = vec![];
+        let mut last = None;
+        let image = c.render_progressive(World::default(), |canvas, pass| {
+            passes.push(pass);
+            last = Some(canvas.clone());
+        });
+        assert_eq!(passes, (1..=16).collect::<Vec<_>>());
+        assert!(last.unwrap() == image);
+        let center = image.pixel_at(5, 5);
+        // Sixteen samples land near the pixel's average, about 0.463.
+        assert!((center.green - 0.463).abs() < 0.05);
+    }
+
+    #[test]
+    fn single_pass_progressive_render_matches_render() {
+        let c = Camera::new(11, 11, PI / 2.0).set_transform(Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = c.render_progressive(World::default(), |_, _| {});
+        assert!(image == c.render(World::default()));
+    }
+
+    #[test]
+    fn rendering_a_region_matches_the_full_render() {
+        let c = Camera::new(11, 11, PI / 2.0).set_transform(Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let full = c.render(World::default());
+        let region = c.render_region(World::default(), 4, 3, 8, 6);
+        assert_eq!((region.width, region.height), (4, 3));
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(region.pixel_at(x, y), full.pixel_at(x + 4, y + 3));
+            }
+        }
+        let clipped = c.render_region(World::default(), 9, 9, 20, 20);
+        assert_eq!((clipped.width, clipped.height), (2, 2));
+    }
+
+    #[test]
+    fn regions_share_the_frame_exposure() {
+        let c = Camera::new(11, 11, PI / 2.0)
+            .set_transform(Matrix::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .set_auto_exposure(Some(Metering::Average));
+        let w = World::default();
+        let full = c.render(w.clone());
+        // Smaller than the preview and at one sample, so metered exactly.
+        let exposure = c.frame_exposure(&w);
+        let region = c.render_region_with_exposure(&w, 0, 0, 11, 2, exposure);
+        assert!(region.grid[..] == full.grid[..2]);
+        assert_eq!(c.clone().set_auto_exposure(None).frame_exposure(&w), 1.0);
+    }
+
+    #[test]
+    fn rendering_row_by_row_matches_the_full_render() {
+        let c = Camera::new(11, 7, PI / 2.0).set_transform(Matrix::view_transform(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let full = c.render(World::default());
+        let rows: Vec<Vec<Color>> = c.render_rows(World::default()).collect();
+        assert_eq!(rows.len(), 7);
+        assert!(rows == full.grid);
+        let mut lazy = c.render_rows(World::default());
+        assert_eq!(lazy.next().map(|row| row.len()), Some(11));
+    }
+
+    #[test]
+    fn render_reports_progress_after_each_tile() {
+        let c = Camera::new(10, 10, PI / 2.0)
+            .set_tile_size(4)
+            .set_threads(3);
+        let mut reports = vec![];
+        let image =
+            c.render_with_progress(World::default(), &CancelToken::new(), |p| reports.push(p));
+        assert!(image.is_some());
+        assert_eq!(reports.len(), 9);
+        let last = reports.last().unwrap();
+        assert_eq!((last.tiles_done, last.tiles_total), (9, 9));
+        assert_eq!((last.pixels_done, last.pixels_total), (100, 100));
+        assert!(reports
+            .windows(2)
+            .all(|w| w[0].pixels_done < w[1].pixels_done));
+    }
+
+    #[test]
+    fn cancelled_render_stops_early() {
+        let c = Camera::new(10, 10, PI / 2.0)
+            .set_tile_size(2)
+            .set_threads(1);
+        let cancel = CancelToken::new();
+        let mut reports = 0;
+        let image = c.render_with_progress(World::default(), &cancel, |p| {
+            reports += 1;
+            if p.tiles_done == 3 {
+                cancel.cancel();
+            }
+        });
+        assert!(image.is_none());
+        assert_eq!(reports, 3);
+    }
+
+    #[test]
+    fn builder_sets_up_a_look_at_camera() {
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let c = CameraBuilder::new(11, 11)
+            .field_of_view_degrees(90.0)
+            .look_at(from, to, up)
+            .build()
+            .unwrap();
+        assert!(fp_equal(c.field_of_view(), PI / 2.0));
+        assert_eq!(c.transform(), &Matrix::view_transform(from, to, up));
+        let image = c.render(World::default());
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn builder_rejects_bad_setups() {
+        let origin = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert!(CameraBuilder::new(0, 10).build().is_err());
+        assert!(CameraBuilder::new(10, 10)
+            .field_of_view_degrees(180.0)
+            .build()
+            .is_err());
+        assert!(CameraBuilder::new(10, 10)
+            .look_at(origin, origin, up)
+            .build()
+            .is_err());
+        assert!(CameraBuilder::new(10, 10)
+            .look_at(origin, Point::new(0.0, 5.0, 0.0), up)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn exposure_stops_and_gamma_apply_to_render() {
+        let w = || {
+            World::new(vec![], World::default().light().box_clone()).set_background(
+                crate::background::Background::Solid(Color::new(0.25, 0.25, 0.25)),
+            )
+        };
+        let c = Camera::new(2, 2, PI / 2.0).set_exposure_stops(1.0);
+        assert_eq!(c.render(w()).pixel_at(0, 0), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(c.transfer(), Transfer::Srgb);
+        let c = c.set_exposure_stops(0.0).set_gamma(2.0);
+        assert_eq!(c.transfer(), Transfer::Gamma(2.0));
+        // Gamma only encodes the 8-bit output; the colors stay linear.
+        let image = c.render(w());
+        assert_eq!(image.pixel_at(1, 1), Color::new(0.25, 0.25, 0.25));
+        assert_eq!(image.transfer(), Transfer::Gamma(2.0));
+        assert_eq!(&image.to_rgba()[..4], &[128, 128, 128, 255]);
+        let row = c.render_rows(w()).next().unwrap();
+        assert_eq!(row[0], Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn camera_round_trips_through_serde() {
+        let c = Camera::new(160, 90, 1.0)
+            .look_at(
+                Point::new(1.0, 2.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            )
+            .set_samples(4)
+            .set_renderer(Renderer::PathTraced)
+            .set_auto_exposure(Some(Metering::Average));
+        let json = serde_json::to_string(&c).unwrap();
+        let back: Camera = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.samples(), 4);
+        let (a, b) = (c.ray_for_pixel(10, 20), back.ray_for_pixel(10, 20));
+        assert_eq!(a.origin(), b.origin());
+        assert_eq!(a.direction(), b.direction());
+    }
 }
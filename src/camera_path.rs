@@ -0,0 +1,171 @@
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::vector::Vector;
+
+// How a camera path runs between its points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Spline {
+    // Through every point, turning smoothly at each.
+    CatmullRom,
+    // One curve from the first point to the last, pulled towards the points
+    // between without passing through them.
+    Bezier,
+}
+
+// A smooth flight for the camera, for fly-through animations. Each point
+// on the path is a place for the eye and a place for it to look at, and
+// both follow the spline. The path runs from t = 0 at the first point to
+// t = 1 at the last.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraPath {
+    spline: Spline,
+    eyes: Vec<Point>,
+    targets: Vec<Point>,
+    up: Vector,
+}
+
+impl CameraPath {
+    pub fn new(spline: Spline) -> CameraPath {
+        CameraPath {
+            spline,
+            eyes: vec![],
+            targets: vec![],
+            up: Vector::new(0.0, 1.0, 0.0),
+        }
+    }
+
+    pub fn add_point(mut self, eye: Point, target: Point) -> Self {
+        self.eyes.push(eye);
+        self.targets.push(target);
+        self
+    }
+
+    pub fn set_up(mut self, up: Vector) -> Self {
+        self.up = up;
+        self
+    }
+
+    pub fn spline(&self) -> Spline {
+        self.spline
+    }
+
+    pub fn len(&self) -> usize {
+        self.eyes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.eyes.is_empty()
+    }
+
+    // None for an empty path.
+    pub fn eye_at(&self, t: f32) -> Option<Point> {
+        self.point_at(&self.eyes, t)
+    }
+
+    pub fn target_at(&self, t: f32) -> Option<Point> {
+        self.point_at(&self.targets, t)
+    }
+
+    // The camera transform at t, for Camera::set_transform. The identity for
+    // an empty path.
+    pub fn sample(&self, t: f32) -> Matrix {
+        match (self.eye_at(t), self.target_at(t)) {
+            (Some(eye), Some(target)) => Matrix::view_transform(eye, target, self.up),
+            _ => Matrix::identity(),
+        }
+    }
+
+    fn point_at(&self, points: &[Point], t: f32) -> Option<Point> {
+        let t = t.clamp(0.0, 1.0);
+        match (self.spline, points.len()) {
+            (_, 0) => None,
+            (_, 1) => Some(points[0]),
+            (Spline::CatmullRom, n) => {
+                let along = t * (n - 1) as f32;
+                let i = (along.floor() as usize).min(n - 2);
+                // The ends are repeated to give the first and last segments
+                // their outer neighbours.
+                let p = |k: isize| points[(i as isize + k).clamp(0, n as isize - 1) as usize];
+                Some(catmull_rom(p(-1), p(0), p(1), p(2), along - i as f32))
+            }
+            (Spline::Bezier, _) => Some(de_casteljau(points, t)),
+        }
+    }
+}
+
+// The segment from p1 at u = 0 to p2 at u = 1.
+fn catmull_rom(p0: Point, p1: Point, p2: Point, p3: Point, u: f32) -> Point {
+    let (u2, u3) = (u * u, u * u * u);
+    let blend = |a: f32, b: f32, c: f32, d: f32| {
+        0.5 * (2.0 * b
+            + (c - a) * u
+            + (2.0 * a - 5.0 * b + 4.0 * c - d) * u2
+            + (3.0 * b - a - 3.0 * c + d) * u3)
+    };
+    Point::new(
+        blend(p0.x, p1.x, p2.x, p3.x),
+        blend(p0.y, p1.y, p2.y, p3.y),
+        blend(p0.z, p1.z, p2.z, p3.z),
+    )
+}
+
+// Repeated blending of neighbouring points until only one is left.
+fn de_casteljau(points: &[Point], t: f32) -> Point {
+    let mut points = points.to_vec();
+    while points.len() > 1 {
+        points = points.windows(2).map(|w| w[0].lerp(&w[1], t)).collect();
+    }
+    points[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zigzag(spline: Spline) -> CameraPath {
+        let target = Point::new(0.0, 0.0, 0.0);
+        CameraPath::new(spline)
+            .add_point(Point::new(-4.0, 0.0, -5.0), target)
+            .add_point(Point::new(0.0, 2.0, -5.0), target)
+            .add_point(Point::new(4.0, 0.0, -5.0), target)
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_every_point() {
+        let path = zigzag(Spline::CatmullRom);
+        assert_eq!(path.eye_at(0.0), Some(Point::new(-4.0, 0.0, -5.0)));
+        assert_eq!(path.eye_at(0.5), Some(Point::new(0.0, 2.0, -5.0)));
+        assert_eq!(path.eye_at(1.0), Some(Point::new(4.0, 0.0, -5.0)));
+        // Smooth through the middle point rather than coming to a corner.
+        let before = path.eye_at(0.49).unwrap();
+        let after = path.eye_at(0.51).unwrap();
+        assert!((before.y - after.y).abs() < 0.001);
+        assert!(before.y > 1.99);
+    }
+
+    #[test]
+    fn bezier_is_pulled_towards_the_middle_points() {
+        let path = zigzag(Spline::Bezier);
+        assert_eq!(path.eye_at(0.0), Some(Point::new(-4.0, 0.0, -5.0)));
+        assert_eq!(path.eye_at(0.5), Some(Point::new(0.0, 1.0, -5.0)));
+        assert_eq!(path.eye_at(1.0), Some(Point::new(4.0, 0.0, -5.0)));
+    }
+
+    #[test]
+    fn sampling_camera_transforms() {
+        let path = zigzag(Spline::CatmullRom);
+        let view = Matrix::view_transform(
+            Point::new(0.0, 2.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(path.sample(0.5), view);
+        assert_eq!(path.sample(7.0), path.sample(1.0));
+        assert_eq!(
+            CameraPath::new(Spline::Bezier).sample(0.5),
+            Matrix::identity()
+        );
+    }
+}
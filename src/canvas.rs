@@ -1,10 +1,162 @@
+use crate::adjustment::{Channel, Curve, Levels};
 use crate::color::*;
 use std::convert::TryInto;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
 
+// Scene luminance that auto exposure maps to middle grey.
+const MIDDLE_GREY: f32 = 0.18;
+
+// Color temperature that white balance treats as neutral.
+const REFERENCE_WHITE: f32 = 6500.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Metering {
+    // Geometric mean of every pixel's luminance.
+    Average,
+    // Geometric mean of the pixels between two luminance percentiles (0-1),
+    // so a few very dark or very bright pixels don't skew the result.
+    Histogram { low: f32, high: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageStatistics {
+    pub min_luminance: f32,
+    pub max_luminance: f32,
+    pub mean_luminance: f32,
+    // Percentage of pixels with at least one channel above 1.0, which the
+    // 8-bit output will clamp.
+    pub clipped_percentage: f32,
+}
+
+// How linear channel values are encoded when written as 8-bit output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Transfer {
+    // Straight 0-255 scaling, which looks too dark in the midtones.
+    Linear,
+    // The sRGB curve: a short linear toe, then a 1 / 2.4 power.
+    Srgb,
+    // A plain 1 / gamma power.
+    Gamma(f32),
+}
+
+impl Transfer {
+    // Maps a linear value in 0-1 to its encoded value in 0-1.
+    pub fn encode(&self, x: f32) -> f32 {
+        match self {
+            Transfer::Linear => x,
+            Transfer::Srgb => {
+                if x <= 0.003_130_8 {
+                    x * 12.92
+                } else {
+                    1.055 * x.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            Transfer::Gamma(gamma) => x.powf(1.0 / gamma),
+        }
+    }
+}
+
+// Bits per channel for the PPM and PNG outputs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+}
+
+impl BitDepth {
+    pub fn max_value(&self) -> u32 {
+        match self {
+            BitDepth::Eight => 255,
+            BitDepth::Sixteen => 65535,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    Box,
+    Lanczos3,
+}
+
+impl Filter {
+    fn radius(&self) -> f32 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Lanczos3 => 3.0,
+        }
+    }
+
+    fn weight(&self, t: f32) -> f32 {
+        match self {
+            Filter::Box => {
+                if t.abs() <= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Filter::Lanczos3 => {
+                if t.abs() < 3.0 {
+                    sinc(t) * sinc(t / 3.0)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 0.00001 {
+        1.0
+    } else {
+        let px = std::f32::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+// Source pixels and their normalized weights for every pixel along an axis
+// resampled from `from` to `to` pixels.
+fn resample_weights(from: u32, to: u32, filter: Filter) -> Vec<Vec<(usize, f32)>> {
+    let scale = from as f32 / to as f32;
+    let stretch = scale.max(1.0);
+    let support = filter.radius() * stretch;
+    (0..to)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale - 0.5;
+            let start = (center - support).floor().max(0.0) as usize;
+            let end = ((center + support).ceil() as usize).min(from as usize - 1);
+            let mut weights: Vec<(usize, f32)> = (start..=end)
+                .map(|j| (j, filter.weight((j as f32 - center) / stretch)))
+                .filter(|(_, w)| *w != 0.0)
+                .collect();
+            let total: f32 = weights.iter().map(|(_, w)| w).sum();
+            if total == 0.0 {
+                let nearest = (center.round().max(0.0) as usize).min(from as usize - 1);
+                weights = vec![(nearest, 1.0)];
+            } else {
+                for (_, w) in weights.iter_mut() {
+                    *w /= total;
+                }
+            }
+            weights
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Canvas {
     pub width: u32,
     pub height: u32,
     pub grid: Vec<Vec<Color>>,
+    pub(crate) transfer: Transfer,
+    bit_depth: BitDepth,
 }
 
 impl Canvas {
@@ -16,9 +168,33 @@ impl Canvas {
                 vec![Color::new(0.0, 0.0, 0.0); width.try_into().unwrap()];
                 height.try_into().unwrap()
             ],
+            transfer: Transfer::Linear,
+            bit_depth: BitDepth::Eight,
         }
     }
 
+    // Used by the integer outputs: PPM, PNG, BMP, JPEG and GIF. The float
+    // formats are always written linear.
+    pub fn set_transfer(mut self, transfer: Transfer) -> Self {
+        self.transfer = transfer;
+        self
+    }
+
+    pub fn transfer(&self) -> Transfer {
+        self.transfer
+    }
+
+    // Sixteen bits keep smooth gradients in dark areas from banding. Only
+    // PPM and PNG output can use it; the other formats are always 8-bit.
+    pub fn set_bit_depth(mut self, bit_depth: BitDepth) -> Self {
+        self.bit_depth = bit_depth;
+        self
+    }
+
+    pub fn bit_depth(&self) -> BitDepth {
+        self.bit_depth
+    }
+
     pub fn pixel_at(&self, x: usize, y: usize) -> Color {
         self.grid[y][x]
     }
@@ -27,50 +203,747 @@ impl Canvas {
         self.grid[y][x] = color;
     }
 
-    fn convert(x: f32) -> u32 {
-        let mut val = x * 255.0;
-        if val < 0.0 {
-            val = 0.0
-        };
-        if val > 255.0 {
-            val = 255.0
+    // The absolute difference of every channel, so identical images give
+    // black. Both canvases must be the same size.
+    pub fn diff(&self, other: &Canvas) -> Canvas {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "can't diff a {}x{} canvas with a {}x{} one",
+            self.width,
+            self.height,
+            other.width,
+            other.height
+        );
+        let mut out = Canvas::new(self.width, self.height);
+        let pairs = self.grid.iter().flatten().zip(other.grid.iter().flatten());
+        for (pixel, (a, b)) in out.grid.iter_mut().flatten().zip(pairs) {
+            *pixel = Channel::All.map(*a - *b, f32::abs);
+        }
+        out
+    }
+
+    // True when the canvases are the same size and no channel of any pixel
+    // differs by more than tolerance.
+    pub fn approx_eq(&self, other: &Canvas, tolerance: f32) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self
+                .diff(other)
+                .grid
+                .iter()
+                .flatten()
+                .all(|p| p.red <= tolerance && p.green <= tolerance && p.blue <= tolerance)
+    }
+
+    fn log_luminances(&self) -> Vec<f32> {
+        self.grid
+            .iter()
+            .flatten()
+            .map(|pixel| (pixel.luminance().max(0.0) + 0.0001).ln())
+            .collect()
+    }
+
+    // Pixel counts over `bins` equal luminance ranges covering 0 to 1; values
+    // outside that range land in the first or last bin.
+    pub fn histogram(&self, bins: usize) -> Vec<usize> {
+        let mut counts = vec![0; bins];
+        if bins == 0 {
+            return counts;
+        }
+        for pixel in self.grid.iter().flatten() {
+            let bin = (pixel.luminance() * bins as f32).floor();
+            let bin = (bin.max(0.0) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+        counts
+    }
+
+    pub fn statistics(&self) -> ImageStatistics {
+        let mut min_luminance = f32::MAX;
+        let mut max_luminance = f32::MIN;
+        let mut total = 0.0;
+        let mut clipped = 0;
+        let mut count = 0;
+        for pixel in self.grid.iter().flatten() {
+            let luminance = pixel.luminance();
+            min_luminance = min_luminance.min(luminance);
+            max_luminance = max_luminance.max(luminance);
+            total += luminance;
+            if pixel.red > 1.0 || pixel.green > 1.0 || pixel.blue > 1.0 {
+                clipped += 1;
+            }
+            count += 1;
+        }
+        if count == 0 {
+            return ImageStatistics {
+                min_luminance: 0.0,
+                max_luminance: 0.0,
+                mean_luminance: 0.0,
+                clipped_percentage: 0.0,
+            };
+        }
+        ImageStatistics {
+            min_luminance,
+            max_luminance,
+            mean_luminance: total / count as f32,
+            clipped_percentage: clipped as f32 * 100.0 / count as f32,
+        }
+    }
+
+    // Exposure multiplier that brings the metered luminance to middle grey.
+    pub fn metered_exposure(&self, metering: Metering) -> f32 {
+        let mut logs = self.log_luminances();
+        if logs.is_empty() {
+            return 1.0;
+        }
+        let selected = match metering {
+            Metering::Average => &logs[..],
+            Metering::Histogram { low, high } => {
+                logs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let last = logs.len() - 1;
+                let start = (low.clamp(0.0, 1.0) * last as f32).round() as usize;
+                let end = (high.clamp(0.0, 1.0) * last as f32).round() as usize;
+                &logs[start..=end.max(start)]
+            }
         };
+        let mean = selected.iter().sum::<f32>() / selected.len() as f32;
+        MIDDLE_GREY / mean.exp()
+    }
+
+    pub fn apply_exposure(&mut self, exposure: f32) {
+        for pixel in self.grid.iter_mut().flatten() {
+            *pixel *= exposure;
+        }
+    }
+
+    // Raises every channel to 1 / gamma. Negative channels become black.
+    pub fn apply_gamma(&mut self, gamma: f32) {
+        let inverse = 1.0 / gamma;
+        for pixel in self.grid.iter_mut().flatten() {
+            *pixel = Channel::All.map(*pixel, |x| x.max(0.0).powf(inverse));
+        }
+    }
+
+    // Neutralizes light of the given color temperature (Kelvin) so it renders
+    // as 6500K white, keeping the overall brightness. Tint shifts between
+    // green (negative) and magenta (positive).
+    pub fn white_balance(&mut self, temperature: f32, tint: f32) {
+        let source = Color::from_temperature(temperature);
+        let target = Color::from_temperature(REFERENCE_WHITE);
+        let gains = Color::new(
+            target.red / source.red.max(0.0001),
+            target.green / source.green.max(0.0001) * (1.0 - tint),
+            target.blue / source.blue.max(0.0001),
+        );
+        let gains = gains * (1.0 / gains.luminance());
+        for pixel in self.grid.iter_mut().flatten() {
+            *pixel *= gains;
+        }
+    }
+
+    // Resamples the image with a separable filter, horizontally then
+    // vertically. Downscaling widens the filter so every source pixel counts.
+    pub fn resize(&self, width: u32, height: u32, filter: Filter) -> Canvas {
+        let mut out = Canvas::new(width, height)
+            .set_transfer(self.transfer)
+            .set_bit_depth(self.bit_depth);
+        if width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+            return out;
+        }
+        let columns = resample_weights(self.width, width, filter);
+        let rows = resample_weights(self.height, height, filter);
+
+        let horizontal: Vec<Vec<Color>> = self
+            .grid
+            .iter()
+            .map(|line| {
+                columns
+                    .iter()
+                    .map(|weights| weights.iter().map(|(j, w)| line[*j] * *w).sum())
+                    .collect()
+            })
+            .collect();
+
+        for (line, weights) in out.grid.iter_mut().zip(rows.iter()) {
+            for (x, pixel) in line.iter_mut().enumerate() {
+                *pixel = weights.iter().map(|(j, w)| horizontal[*j][x] * *w).sum();
+            }
+        }
+        out
+    }
+
+    // Averages factor x factor blocks, for rendering at a multiple of the
+    // final size and shrinking afterwards.
+    pub fn downscale(&self, factor: u32) -> Canvas {
+        let factor = factor.max(1);
+        self.resize(
+            (self.width / factor).max(1),
+            (self.height / factor).max(1),
+            Filter::Box,
+        )
+    }
+
+    pub fn apply_levels(&mut self, channel: Channel, levels: &Levels) {
+        for pixel in self.grid.iter_mut().flatten() {
+            *pixel = channel.map(*pixel, |x| levels.apply(x));
+        }
+    }
+
+    pub fn apply_curve(&mut self, channel: Channel, curve: &Curve) {
+        for pixel in self.grid.iter_mut().flatten() {
+            *pixel = channel.map(*pixel, |x| curve.apply(x));
+        }
+    }
+
+    fn convert(&self, x: f32) -> u32 {
+        self.quantize(x, 255)
+    }
+
+    fn quantize(&self, x: f32, max: u32) -> u32 {
+        let val = self.transfer.encode(x.clamp(0.0, 1.0)) * max as f32;
         val.round() as u32
     }
 
-    pub fn to_ppm(self) -> String {
-        let mut str = format!("P3\n{} {}\n255\n", self.width, self.height);
-        for line in self.grid {
-            let mut newline = String::from("");
-            for pixel in line {
-                let new = format!(
-                    "{} {} {} ",
-                    Self::convert(pixel.red),
-                    Self::convert(pixel.green),
-                    Self::convert(pixel.blue)
+    // Reads a plain (P3) or binary (P6) PPM. Channels are scaled by the
+    // file's maximum value so they land in 0-1.
+    pub fn from_ppm<R: Read>(mut reader: R) -> Result<Canvas, String> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        let bytes = &bytes[..];
+        let mut pos = 0;
+        let magic = ppm_token(bytes, &mut pos).ok_or("missing PPM header")?;
+        let mut header = [0; 3];
+        for h in header.iter_mut() {
+            *h = ppm_token(bytes, &mut pos)
+                .and_then(|t| t.parse::<u32>().ok())
+                .ok_or("malformed PPM header")?;
+        }
+        let [width, height, max] = header;
+        if max == 0 || max > 65535 {
+            return Err(format!("unsupported PPM maximum value {}", max));
+        }
+        let count = width as usize * height as usize * 3;
+        let values: Vec<u32> = match magic.as_str() {
+            "P3" => (0..count)
+                .map(|_| ppm_token(bytes, &mut pos).and_then(|t| t.parse().ok()))
+                .collect::<Option<_>>()
+                .ok_or("malformed PPM pixel data")?,
+            "P6" => {
+                // A single whitespace byte separates the header from the data.
+                let data = &bytes[(pos + 1).min(bytes.len())..];
+                let size = if max < 256 { 1 } else { 2 };
+                if data.len() < count * size {
+                    return Err(String::from("truncated PPM pixel data"));
+                }
+                data.chunks(size)
+                    .take(count)
+                    .map(|c| c.iter().fold(0, |v, b| v << 8 | *b as u32))
+                    .collect()
+            }
+            _ => return Err(format!("unsupported PPM type {}", magic)),
+        };
+        let mut canvas = Canvas::new(width, height);
+        for (i, rgb) in values.chunks(3).enumerate() {
+            let channel = |v: u32| v as f32 / max as f32;
+            canvas.write_pixel(
+                i % width as usize,
+                i / width as usize,
+                Color::new(channel(rgb[0]), channel(rgb[1]), channel(rgb[2])),
+            );
+        }
+        Ok(canvas)
+    }
+
+    // Reads a Radiance .hdr file with the usual top-to-bottom, left-to-right
+    // orientation. Scanlines may be flat or run-length encoded.
+    pub fn from_hdr(bytes: &[u8]) -> Result<Canvas, String> {
+        let mut pos = 0;
+        let mut line = || -> Option<String> {
+            let start = pos;
+            let end = start + bytes[start..].iter().position(|&b| b == b'\n')?;
+            pos = end + 1;
+            Some(String::from_utf8_lossy(&bytes[start..end]).into_owned())
+        };
+        match line() {
+            Some(magic) if magic.starts_with("#?") => {}
+            _ => return Err(String::from("missing Radiance header")),
+        }
+        loop {
+            match line() {
+                Some(l) if l.trim().is_empty() => break,
+                Some(l) if l.starts_with("FORMAT=") && l.trim() != "FORMAT=32-bit_rle_rgbe" => {
+                    return Err(format!("unsupported HDR format {}", &l[7..]))
+                }
+                Some(_) => {}
+                None => return Err(String::from("truncated HDR header")),
+            }
+        }
+        let size = line().ok_or("missing HDR resolution")?;
+        let (height, width) = match size.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", h, "+X", w] => (
+                h.parse::<u32>().map_err(|e| e.to_string())?,
+                w.parse::<u32>().map_err(|e| e.to_string())?,
+            ),
+            _ => return Err(format!("unsupported HDR orientation {}", size)),
+        };
+        let mut data = &bytes[pos..];
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height as usize {
+            let (scanline, rest) = hdr_scanline(data, width as usize)?;
+            data = rest;
+            for (x, rgbe) in scanline.chunks(4).enumerate() {
+                let scale = if rgbe[3] == 0 {
+                    0.0
+                } else {
+                    2.0_f32.powi(rgbe[3] as i32 - 136)
+                };
+                let channel = |v: u8| v as f32 * scale;
+                canvas.write_pixel(
+                    x,
+                    y,
+                    Color::new(channel(rgbe[0]), channel(rgbe[1]), channel(rgbe[2])),
                 );
-                newline.push_str(&new);
-            }
-            if newline.len() > 70 {
-                let mut start = 0;
-                for _ in 0..(newline.len() / 70) {
-                    let pos = newline[start..(start + 71)].rfind(' ').unwrap();
-                    newline.replace_range((start + pos)..(start + pos + 1), "\n");
-                    start += pos;
+            }
+        }
+        Ok(canvas)
+    }
+
+    // 8 and 16 bit PNGs, with or without alpha (which is dropped). Needs the
+    // `image` feature.
+    #[cfg(feature = "image")]
+    pub fn from_png<P: AsRef<Path>>(path: P) -> Result<Canvas, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        if !bytes.starts_with(b"\x89PNG") {
+            return Err(String::from("not a PNG file"));
+        }
+        Self::decode_image(&bytes)
+    }
+
+    #[cfg(not(feature = "image"))]
+    pub fn from_png<P: AsRef<Path>>(_path: P) -> Result<Canvas, String> {
+        Err(String::from("PNG input needs the `image` feature"))
+    }
+
+    // Any format the image crate recognises from the bytes themselves.
+    #[cfg(feature = "image")]
+    pub(crate) fn decode_image(bytes: &[u8]) -> Result<Canvas, String> {
+        let image = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
+        Ok(Canvas::from(image.into_rgb32f()))
+    }
+
+    // Writes the file in the format its extension names: ppm, bmp, pfm or
+    // hdr, or with the `image` feature png, jpg or gif.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let extension = Self::check_format(path)?;
+        let writer = || fs::File::create(path).map(io::BufWriter::new);
+        let result = match extension.as_str() {
+            "ppm" => self.write_ppm_binary(writer()?),
+            "bmp" => self.write_bmp(writer()?),
+            "pfm" => self.write_pfm(writer()?),
+            "hdr" => self.write_hdr(writer()?),
+            "png" => self.write_png(writer()?),
+            "jpg" | "jpeg" => self.write_jpeg(writer()?, 90),
+            "gif" => crate::gif::write_gif(writer()?, std::slice::from_ref(self), 0),
+            _ => unreachable!(),
+        };
+        // Don't leave a partly written file behind.
+        if result.is_err() && path.exists() {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    // Whether save can write the format path's extension names in this
+    // build, so a long render can be refused before it starts. Returns the
+    // extension in lower case.
+    pub fn check_format<P: AsRef<Path>>(path: P) -> io::Result<String> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" | "bmp" | "pfm" | "hdr" => Ok(extension),
+            "png" | "jpg" | "jpeg" | "gif" if cfg!(feature = "image") => Ok(extension),
+            "png" | "jpg" | "jpeg" | "gif" => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "{} output needs the `image` feature",
+                    extension.to_uppercase()
+                ),
+            )),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("can't tell the image format of {}", path.display()),
+            )),
+        }
+    }
+
+    pub fn to_ppm(&self) -> String {
+        let mut bytes = vec![];
+        // Writing into a Vec can't fail.
+        self.write_ppm(&mut bytes).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    // Plain (P3) PPM, written a row at a time so the whole file is never in
+    // memory. Lines are wrapped to at most 70 characters.
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let max = self.bit_depth.max_value();
+        write!(writer, "P3\n{} {}\n{}\n", self.width, self.height, max)?;
+        let mut text = String::new();
+        for line in &self.grid {
+            text.clear();
+            let mut length = 0;
+            for pixel in line {
+                for channel in [pixel.red, pixel.green, pixel.blue].iter() {
+                    let value = self.quantize(*channel, max).to_string();
+                    if length > 0 && length + 1 + value.len() > 70 {
+                        text.push('\n');
+                        length = 0;
+                    } else if length > 0 {
+                        text.push(' ');
+                        length += 1;
+                    }
+                    text.push_str(&value);
+                    length += value.len();
                 }
             }
+            text.push('\n');
+            writer.write_all(text.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    // Binary (P6) PPM: the same header, then three bytes per pixel, or six
+    // big-endian ones at 16 bits. Much smaller and quicker to write than
+    // to_ppm's text.
+    pub fn to_ppm_binary(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_ppm_binary(&mut bytes).unwrap();
+        bytes
+    }
+
+    pub fn write_ppm_binary<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let max = self.bit_depth.max_value();
+        write!(writer, "P6\n{} {}\n{}\n", self.width, self.height, max)?;
+        for line in &self.grid {
+            match self.bit_depth {
+                BitDepth::Eight => writer.write_all(&self.rgb_bytes(line))?,
+                BitDepth::Sixteen => {
+                    let bytes: Vec<u8> = self
+                        .rgb16(line)
+                        .iter()
+                        .flat_map(|v| v.to_be_bytes().to_vec())
+                        .collect();
+                    writer.write_all(&bytes)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rgb16(&self, pixels: &[Color]) -> Vec<u16> {
+        pixels
+            .iter()
+            .flat_map(|p| vec![p.red, p.green, p.blue])
+            .map(|x| self.quantize(x, 65535) as u16)
+            .collect()
+    }
+
+    // PNG at the canvas's bit depth. Needs the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        use image::codecs::png::PngEncoder;
+        use image::{ColorType, ImageEncoder};
+        let pixels = self.grid.concat();
+        let (bytes, color) = match self.bit_depth {
+            BitDepth::Eight => (self.rgb_bytes(&pixels), ColorType::Rgb8),
+            BitDepth::Sixteen => (
+                self.rgb16(&pixels)
+                    .iter()
+                    .flat_map(|v| v.to_ne_bytes().to_vec())
+                    .collect(),
+                ColorType::Rgb16,
+            ),
+        };
+        PngEncoder::new(writer)
+            .write_image(&bytes, self.width, self.height, color)
+            .map_err(io::Error::other)
+    }
+
+    #[cfg(not(feature = "image"))]
+    pub fn write_png<W: Write>(&self, _writer: W) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "PNG output needs the `image` feature",
+        ))
+    }
+
+    // Eight bits per channel.
+    pub(crate) fn rgb_bytes(&self, pixels: &[Color]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(pixels.len() * 3);
+        for pixel in pixels {
+            bytes.push(self.convert(pixel.red) as u8);
+            bytes.push(self.convert(pixel.green) as u8);
+            bytes.push(self.convert(pixel.blue) as u8);
+        }
+        bytes
+    }
+
+    // Eight bits per channel with opaque alpha, a row at a time, as an HTML
+    // canvas's ImageData holds them. Uses the canvas's transfer function.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.grid.len() * self.width as usize * 4);
+        for rgb in self.rgb_bytes(&self.grid.concat()).chunks(3) {
+            bytes.extend_from_slice(rgb);
+            bytes.push(255);
+        }
+        bytes
+    }
+
+    // Uncompressed 24-bit Windows bitmap.
+    pub fn write_bmp<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        // Rows are stored bottom up, in BGR order, padded to four bytes.
+        let row_size = (self.width * 3).div_ceil(4) * 4;
+        let image_size = row_size * self.height;
+        let mut header = Vec::with_capacity(54);
+        header.extend_from_slice(b"BM");
+        header.extend_from_slice(&(54 + image_size).to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&54u32.to_le_bytes());
+        header.extend_from_slice(&40u32.to_le_bytes());
+        header.extend_from_slice(&(self.width as i32).to_le_bytes());
+        header.extend_from_slice(&(self.height as i32).to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&24u16.to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&image_size.to_le_bytes());
+        // 72 dpi, and no palette.
+        header.extend_from_slice(&2835u32.to_le_bytes());
+        header.extend_from_slice(&2835u32.to_le_bytes());
+        header.extend_from_slice(&[0; 8]);
+        writer.write_all(&header)?;
+
+        let mut row = Vec::with_capacity(row_size as usize);
+        for line in self.grid.iter().rev() {
+            row.clear();
+            for pixel in line {
+                row.push(self.convert(pixel.blue) as u8);
+                row.push(self.convert(pixel.green) as u8);
+                row.push(self.convert(pixel.red) as u8);
+            }
+            row.resize(row_size as usize, 0);
+            writer.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    // Portable float map: 32-bit float RGB, so values above 1.0 survive for
+    // tone mapping or compositing later. Rows run bottom to top.
+    pub fn write_pfm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        // A negative scale marks the data as little-endian.
+        write!(writer, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        let mut row = Vec::with_capacity(self.width as usize * 12);
+        for line in self.grid.iter().rev() {
+            row.clear();
+            for pixel in line {
+                row.extend_from_slice(&pixel.red.to_le_bytes());
+                row.extend_from_slice(&pixel.green.to_le_bytes());
+                row.extend_from_slice(&pixel.blue.to_le_bytes());
+            }
+            writer.write_all(&row)?;
+        }
+        Ok(())
+    }
 
-            str.push_str(&newline);
-            str.pop();
-            str.push('\n')
+    // Radiance RGBE, the format from_hdr reads, with uncompressed
+    // scanlines. Keeps values above 1.0 at about 1% precision.
+    pub fn write_hdr<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(
+            writer,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )?;
+        let mut row = Vec::with_capacity(self.width as usize * 4);
+        for line in &self.grid {
+            row.clear();
+            for pixel in line {
+                row.extend_from_slice(&rgbe(*pixel));
+            }
+            writer.write_all(&row)?;
         }
-        str
+        Ok(())
+    }
+
+    // Baseline JPEG at quality 1 (smallest) to 100 (best). Needs the `image`
+    // feature.
+    #[cfg(feature = "image")]
+    pub fn write_jpeg<W: Write>(&self, mut writer: W, quality: u8) -> io::Result<()> {
+        use image::codecs::jpeg::JpegEncoder;
+        JpegEncoder::new_with_quality(&mut writer, quality.clamp(1, 100))
+            .encode(
+                &self.rgb_bytes(&self.grid.concat()),
+                self.width,
+                self.height,
+                image::ColorType::Rgb8,
+            )
+            .map_err(io::Error::other)
+    }
+
+    #[cfg(not(feature = "image"))]
+    pub fn write_jpeg<W: Write>(&self, _writer: W, _quality: u8) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "JPEG output needs the `image` feature",
+        ))
+    }
+}
+
+// Conversions to and from the image crate's buffers, for using its filters
+// and encoders on renders. Needs the `image` feature.
+
+// Eight bits per channel, through the canvas's transfer function as PNG
+// output is.
+#[cfg(feature = "image")]
+impl From<Canvas> for image::RgbImage {
+    fn from(canvas: Canvas) -> image::RgbImage {
+        let bytes = canvas.rgb_bytes(&canvas.grid.concat());
+        image::RgbImage::from_raw(canvas.width, canvas.height, bytes).unwrap()
+    }
+}
+
+// The bytes are scaled to 0 to 1 as they are, without undoing any sRGB
+// encoding, as from_png does.
+#[cfg(feature = "image")]
+impl From<image::RgbImage> for Canvas {
+    fn from(image: image::RgbImage) -> Canvas {
+        Canvas::from(image::DynamicImage::ImageRgb8(image).into_rgb32f())
+    }
+}
+
+// The colors as they are, neither clamped nor encoded.
+#[cfg(feature = "image")]
+impl From<Canvas> for image::Rgb32FImage {
+    fn from(canvas: Canvas) -> image::Rgb32FImage {
+        let floats = canvas
+            .grid
+            .iter()
+            .flatten()
+            .flat_map(|p| [p.red, p.green, p.blue])
+            .collect();
+        image::Rgb32FImage::from_raw(canvas.width, canvas.height, floats).unwrap()
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<image::Rgb32FImage> for Canvas {
+    fn from(image: image::Rgb32FImage) -> Canvas {
+        let mut canvas = Canvas::new(image.width(), image.height());
+        for (x, y, p) in image.enumerate_pixels() {
+            canvas.write_pixel(x as usize, y as usize, Color::new(p[0], p[1], p[2]));
+        }
+        canvas
+    }
+}
+
+// A shared exponent byte and three mantissas scaled to it. Channels below
+// zero are stored as zero.
+fn rgbe(color: Color) -> [u8; 4] {
+    let (r, g, b) = (
+        color.red.max(0.0),
+        color.green.max(0.0),
+        color.blue.max(0.0),
+    );
+    let largest = r.max(g).max(b);
+    if largest < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // largest = mantissa * 2^exponent with mantissa in [0.5, 1).
+    let exponent = largest.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f32.powi(exponent);
+    let byte = |x: f32| (x * scale).min(255.0) as u8;
+    [
+        byte(r),
+        byte(g),
+        byte(b),
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+// One scanline of RGBE pixels and the bytes after it. Run-length encoded
+// lines start with 2, 2 and the width, then hold each channel in turn as runs
+// (count over 128) or literal spans.
+fn hdr_scanline(data: &[u8], width: usize) -> Result<(Vec<u8>, &[u8]), String> {
+    let truncated = || String::from("truncated HDR pixel data");
+    let encoded = (8..0x8000).contains(&width)
+        && data.len() >= 4
+        && data[0] == 2
+        && data[1] == 2
+        && ((data[2] as usize) << 8 | data[3] as usize) == width;
+    if !encoded {
+        if data.len() < width * 4 {
+            return Err(truncated());
+        }
+        return Ok((data[..width * 4].to_vec(), &data[width * 4..]));
+    }
+    let mut pixels = vec![0; width * 4];
+    let mut pos = 4;
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *data.get(pos).ok_or_else(truncated)? as usize;
+            pos += 1;
+            let (run, literal) = if count > 128 {
+                (count - 128, false)
+            } else {
+                (count, true)
+            };
+            if run == 0 || x + run > width {
+                return Err(String::from("bad HDR run length"));
+            }
+            for i in 0..run {
+                let at = if literal { pos + i } else { pos };
+                pixels[(x + i) * 4 + channel] = *data.get(at).ok_or_else(truncated)?;
+            }
+            pos += if literal { run } else { 1 };
+            x += run;
+        }
+    }
+    Ok((pixels, &data[pos..]))
+}
+
+// The next whitespace-separated word of a PPM, skipping `#` comments.
+fn ppm_token(bytes: &[u8], pos: &mut usize) -> Option<String> {
+    loop {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if *pos < bytes.len() && bytes[*pos] == b'#' {
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let start = *pos;
+    while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+    if start == *pos {
+        None
+    } else {
+        Some(String::from_utf8_lossy(&bytes[start..*pos]).into_owned())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::fp_equal;
 
     #[test]
     fn creating_a_canvas() {
@@ -93,6 +966,171 @@ mod tests {
         assert!(Color::equal(c.pixel_at(2, 3), red))
     }
 
+    fn filled(width: u32, height: u32, color: Color) -> Canvas {
+        let mut c = Canvas::new(width, height);
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                c.write_pixel(x, y, color);
+            }
+        }
+        c
+    }
+
+    #[test]
+    fn average_metering_of_uniform_canvas() {
+        let c = filled(4, 4, Color::new(0.36, 0.36, 0.36));
+        let exposure = c.metered_exposure(Metering::Average);
+        assert!(f32::abs(exposure - 0.5) < 0.001);
+    }
+
+    #[test]
+    fn histogram_metering_ignores_highlights() {
+        let mut c = filled(10, 10, Color::new(0.18, 0.18, 0.18));
+        for x in 0..5 {
+            c.write_pixel(x, 0, Color::new(1000.0, 1000.0, 1000.0));
+        }
+        let average = c.metered_exposure(Metering::Average);
+        let histogram = c.metered_exposure(Metering::Histogram {
+            low: 0.0,
+            high: 0.9,
+        });
+        assert!(average < histogram);
+        assert!(f32::abs(histogram - 1.0) < 0.001);
+    }
+
+    #[test]
+    fn applying_exposure_scales_every_pixel() {
+        let mut c = filled(2, 2, Color::new(0.2, 0.4, 0.8));
+        c.apply_exposure(0.5);
+        assert!(Color::equal(c.pixel_at(1, 1), Color::new(0.1, 0.2, 0.4)));
+    }
+
+    #[test]
+    fn applying_gamma_brightens_midtones() {
+        let mut c = filled(1, 1, Color::new(0.25, 1.0, -0.5));
+        c.apply_gamma(2.0);
+        assert!(Color::equal(c.pixel_at(0, 0), Color::new(0.5, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn white_balance_at_reference_is_identity() {
+        let color = Color::new(0.2, 0.5, 0.7);
+        let mut c = filled(2, 2, color);
+        c.white_balance(6500.0, 0.0);
+        assert!(Color::equal(c.pixel_at(0, 0), color));
+    }
+
+    #[test]
+    fn white_balance_neutralizes_tungsten_light() {
+        let tungsten = Color::from_temperature(3200.0);
+        let mut c = filled(2, 2, tungsten);
+        c.white_balance(3200.0, 0.0);
+        let balanced = c.pixel_at(1, 1);
+        let white = Color::from_temperature(6500.0);
+        let scale = balanced.red / white.red;
+        assert!(Color::equal(balanced, white * scale));
+    }
+
+    #[test]
+    fn positive_tint_removes_green() {
+        let grey = Color::new(0.5, 0.5, 0.5);
+        let mut c = filled(1, 1, grey);
+        c.white_balance(6500.0, 0.2);
+        let p = c.pixel_at(0, 0);
+        assert!(p.green < p.red && p.green < p.blue);
+    }
+
+    #[test]
+    fn histogram_counts_pixels_by_luminance() {
+        let mut c = filled(4, 1, Color::new(0.0, 0.0, 0.0));
+        c.write_pixel(1, 0, Color::new(0.3, 0.3, 0.3));
+        c.write_pixel(2, 0, Color::new(0.9, 0.9, 0.9));
+        c.write_pixel(3, 0, Color::new(5.0, 5.0, 5.0));
+        assert_eq!(c.histogram(4), vec![1, 1, 0, 2]);
+    }
+
+    #[test]
+    fn histogram_bins_sum_to_pixel_count() {
+        let c = filled(7, 3, Color::new(0.5, 0.2, 0.1));
+        assert_eq!(c.histogram(16).iter().sum::<usize>(), 21);
+    }
+
+    #[test]
+    fn statistics_of_a_canvas() {
+        let mut c = filled(2, 2, Color::new(0.0, 0.0, 0.0));
+        c.write_pixel(0, 0, Color::new(1.0, 1.0, 1.0));
+        c.write_pixel(1, 0, Color::new(2.0, 2.0, 2.0));
+        c.write_pixel(0, 1, Color::new(0.5, 1.5, 0.5));
+        let stats = c.statistics();
+        assert!(fp_equal(stats.min_luminance, 0.0));
+        assert!(fp_equal(stats.max_luminance, 2.0));
+        assert!(fp_equal(
+            stats.mean_luminance,
+            (1.0 + 2.0 + Color::new(0.5, 1.5, 0.5).luminance()) / 4.0
+        ));
+        assert!(fp_equal(stats.clipped_percentage, 50.0));
+    }
+
+    #[test]
+    fn applying_levels_to_one_channel() {
+        let mut c = filled(2, 2, Color::new(0.4, 0.4, 0.4));
+        c.apply_levels(Channel::Red, &Levels::default().set_input(0.2, 0.6));
+        assert!(Color::equal(c.pixel_at(1, 1), Color::new(0.5, 0.4, 0.4)));
+    }
+
+    #[test]
+    fn applying_curve_to_all_channels() {
+        let mut c = filled(2, 2, Color::new(0.25, 0.5, 0.75));
+        let curve = Curve::new(&[(0.0, 0.0), (0.5, 0.25), (1.0, 1.0)]);
+        c.apply_curve(Channel::All, &curve);
+        assert!(Color::equal(
+            c.pixel_at(0, 1),
+            Color::new(0.125, 0.25, 0.625)
+        ));
+    }
+
+    #[test]
+    fn resizing_changes_dimensions() {
+        let c = Canvas::new(10, 6);
+        let r = c.resize(4, 3, Filter::Lanczos3);
+        assert_eq!((r.width, r.height), (4, 3));
+        assert_eq!(r.grid.len(), 3);
+        assert_eq!(r.grid[0].len(), 4);
+    }
+
+    #[test]
+    fn downscaling_averages_blocks() {
+        let mut c = Canvas::new(4, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(3, 1, Color::new(0.0, 0.0, 1.0));
+        c.write_pixel(2, 0, Color::new(0.0, 1.0, 0.0));
+        let d = c.downscale(2);
+        assert_eq!((d.width, d.height), (2, 1));
+        assert!(Color::equal(d.pixel_at(0, 0), Color::new(0.25, 0.0, 0.0)));
+        assert!(Color::equal(d.pixel_at(1, 0), Color::new(0.0, 0.25, 0.25)));
+    }
+
+    #[test]
+    fn resizing_preserves_flat_color() {
+        let color = Color::new(0.3, 0.6, 0.9);
+        let c = filled(9, 7, color);
+        for filter in [Filter::Box, Filter::Lanczos3] {
+            let down = c.resize(4, 3, filter);
+            let up = c.resize(20, 15, filter);
+            assert!(Color::equal(down.pixel_at(2, 1), color));
+            assert!(Color::equal(up.pixel_at(19, 14), color));
+        }
+    }
+
+    #[test]
+    fn resizing_to_same_size_is_identity() {
+        let mut c = Canvas::new(3, 3);
+        c.write_pixel(1, 1, Color::new(1.0, 1.0, 1.0));
+        let r = c.resize(3, 3, Filter::Lanczos3);
+        assert!(Color::equal(r.pixel_at(1, 1), Color::new(1.0, 1.0, 1.0)));
+        assert!(Color::equal(r.pixel_at(0, 1), Color::new(0.0, 0.0, 0.0)));
+    }
+
     #[test]
     fn constructing_the_ppm_header() {
         let c = Canvas::new(5, 3);
@@ -142,10 +1180,328 @@ mod tests {
         );
     }
 
+    #[test]
+    fn writing_ppm_matches_to_ppm_and_keeps_lines_short() {
+        let mut c = Canvas::new(40, 2);
+        for pixel in c.grid.iter_mut().flatten() {
+            *pixel = Color::new(1.0, 0.8, 0.6);
+        }
+        let mut bytes = vec![];
+        c.write_ppm(&mut bytes).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        assert_eq!(text, c.to_ppm());
+        assert!(text.lines().all(|l| l.len() <= 70));
+        assert_eq!(text.split_whitespace().count(), 4 + 40 * 2 * 3);
+    }
+
+    #[test]
+    fn srgb_transfer_brightens_midtones() {
+        assert_eq!(Transfer::Srgb.encode(0.0), 0.0);
+        assert!(fp_equal(Transfer::Srgb.encode(0.002), 0.02584));
+        assert!(fp_equal(Transfer::Srgb.encode(0.5), 0.735_357));
+        assert!(fp_equal(Transfer::Srgb.encode(1.0), 1.0));
+        assert!(fp_equal(Transfer::Gamma(2.0).encode(0.25), 0.5));
+    }
+
+    #[test]
+    fn ppm_output_uses_the_canvas_transfer() {
+        let c = filled(1, 1, Color::new(0.5, 0.0, 1.5));
+        assert!(c.to_ppm().ends_with("\n128 0 255\n"));
+        let c = c.set_transfer(Transfer::Srgb);
+        assert_eq!(c.transfer(), Transfer::Srgb);
+        assert!(c.to_ppm().ends_with("\n188 0 255\n"));
+        assert_eq!(c.to_ppm_binary()[11..], [188, 0, 255]);
+    }
+
     #[test]
     fn ppm_files_are_terminated_by_a_newline_character() {
         let c = Canvas::new(5, 3);
         let ppm = c.to_ppm();
         assert!(ppm.ends_with('\n'));
     }
+
+    #[test]
+    fn diffing_canvases() {
+        let a = filled(2, 1, Color::new(0.5, 0.5, 0.5));
+        let mut b = a.clone();
+        b.write_pixel(1, 0, Color::new(0.25, 0.75, 0.5));
+        let d = a.diff(&b);
+        assert_eq!(d.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(d.pixel_at(1, 0), Color::new(0.25, 0.25, 0.0));
+    }
+
+    #[test]
+    fn comparing_canvases_within_a_tolerance() {
+        let a = filled(2, 2, Color::new(0.5, 0.5, 0.5));
+        let b = filled(2, 2, Color::new(0.5, 0.52, 0.5));
+        assert!(a.approx_eq(&b, 0.03));
+        assert!(!a.approx_eq(&b, 0.01));
+        assert!(!a.approx_eq(&filled(2, 1, Color::new(0.5, 0.5, 0.5)), 1.0));
+    }
+
+    #[test]
+    fn reading_a_file_with_the_wrong_magic_number() {
+        assert!(Canvas::from_ppm(&b"P32\n1 1\n255\n0 0 0\n"[..]).is_err());
+    }
+
+    #[test]
+    fn reading_pixel_data_from_a_ppm_file() {
+        let ppm = b"P3\n# a comment\n4 3\n255\n255 127 0  0 127 255  127 255 0  255 255 255\n\
+                    0 0 0  255 0 0  0 255 0  0 0 255\n\
+                    255 255 0  0 255 255  255 0 255  127 127 127\n";
+        let c = Canvas::from_ppm(&ppm[..]).unwrap();
+        assert!(c.width == 4 && c.height == 3);
+        assert_eq!(c.pixel_at(0, 0), Color::new(1.0, 127.0 / 255.0, 0.0));
+        assert_eq!(c.pixel_at(3, 0), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(c.pixel_at(1, 1), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(c.pixel_at(2, 2), Color::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn ppm_pixel_data_is_scaled_by_the_maximum_value() {
+        let c = Canvas::from_ppm(&b"P3\n2 1\n100\n100 100 100  50 50 50\n"[..]).unwrap();
+        assert_eq!(c.pixel_at(1, 0), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn reading_a_binary_ppm_file() {
+        let mut ppm = b"P6\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[255, 0, 0, 0, 51, 255]);
+        let c = Canvas::from_ppm(&ppm[..]).unwrap();
+        assert_eq!(c.pixel_at(0, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(c.pixel_at(1, 0), Color::new(0.0, 0.2, 1.0));
+        assert!(Canvas::from_ppm(&ppm[..ppm.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn ppm_round_trips_through_canvas() {
+        let c = filled(3, 2, Color::new(0.2, 0.4, 0.6));
+        let back = Canvas::from_ppm(c.clone().to_ppm().as_bytes()).unwrap();
+        assert!(fp_equal(back.pixel_at(2, 1).green, 0.4));
+    }
+
+    #[test]
+    fn writing_a_binary_ppm_file() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, -0.5));
+        c.write_pixel(1, 0, Color::new(0.0, 0.2, 1.0));
+        let ppm = c.to_ppm_binary();
+        assert!(ppm.starts_with(b"P6\n2 1\n255\n"));
+        assert_eq!(&ppm[ppm.len() - 6..], &[255, 0, 0, 0, 51, 255]);
+        assert!(Canvas::from_ppm(&ppm[..]).unwrap().pixel_at(1, 0) == c.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn reading_a_ppm_file_from_disk() {
+        let path = std::env::temp_dir().join("raytracer_read_ppm_test.ppm");
+        let c = filled(2, 2, Color::new(0.2, 0.4, 0.6));
+        c.write_ppm(fs::File::create(&path).unwrap()).unwrap();
+        let back = Canvas::from_ppm(fs::File::open(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(fp_equal(back.pixel_at(1, 1).blue, 0.6));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn reading_a_png_file() {
+        let path = std::env::temp_dir().join("raytracer_read_png_test.png");
+        image::RgbImage::from_raw(2, 1, vec![255, 0, 0, 0, 51, 255])
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        let c = Canvas::from_png(&path);
+        fs::remove_file(&path).unwrap();
+        let c = c.unwrap();
+        assert!(c.width == 2 && c.height == 1);
+        assert_eq!(c.pixel_at(1, 0), Color::new(0.0, 0.2, 1.0));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn converting_to_and_from_image_buffers() {
+        let c = filled(2, 1, Color::new(0.2, 0.5, 1.5)).set_transfer(Transfer::Srgb);
+        let rgb = image::RgbImage::from(c.clone());
+        assert_eq!(rgb.get_pixel(1, 0).0, [124, 188, 255]);
+        let back = Canvas::from(rgb);
+        assert_eq!(
+            back.pixel_at(0, 0),
+            Color::new(124.0 / 255.0, 188.0 / 255.0, 1.0)
+        );
+
+        let floats = image::Rgb32FImage::from(c);
+        assert_eq!(floats.get_pixel(0, 0).0, [0.2, 0.5, 1.5]);
+        let back = Canvas::from(floats);
+        assert_eq!(back.pixel_at(1, 0), Color::new(0.2, 0.5, 1.5));
+        assert_eq!((back.width, back.height), (2, 1));
+    }
+
+    #[test]
+    fn reading_a_png_file_that_is_not_there() {
+        assert!(Canvas::from_png("no/such/file.png").is_err());
+    }
+
+    #[test]
+    fn writing_a_16_bit_ppm_file() {
+        let c = filled(2, 1, Color::new(0.5, 0.0, 1.0)).set_bit_depth(BitDepth::Sixteen);
+        assert_eq!(c.bit_depth(), BitDepth::Sixteen);
+        assert!(c.to_ppm().starts_with("P3\n2 1\n65535\n32768 0 65535 "));
+        let ppm = c.to_ppm_binary();
+        assert!(ppm.starts_with(b"P6\n2 1\n65535\n"));
+        assert_eq!(&ppm[ppm.len() - 6..], &[128, 0, 0, 0, 255, 255]);
+        let back = Canvas::from_ppm(&ppm[..]).unwrap();
+        assert!(fp_equal(back.pixel_at(1, 0).red, 32768.0 / 65535.0));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn writing_a_16_bit_png_file() {
+        let c = filled(2, 1, Color::new(0.001, 0.5, 1.0)).set_bit_depth(BitDepth::Sixteen);
+        let mut png = vec![];
+        c.write_png(&mut png).unwrap();
+        let back = image::load_from_memory(&png).unwrap().into_rgb16();
+        assert_eq!(back.get_pixel(1, 0).0, [66, 32768, 65535]);
+    }
+
+    #[cfg(not(feature = "image"))]
+    #[test]
+    fn writing_a_png_needs_the_image_feature() {
+        assert!(Canvas::new(1, 1).write_png(vec![]).is_err());
+    }
+
+    #[test]
+    fn saving_picks_the_format_from_the_extension() {
+        let dir = std::env::temp_dir();
+        let c = filled(2, 1, Color::new(0.2, 0.4, 0.6));
+        let ppm = dir.join("raytracer_save_test.PPM");
+        c.save(&ppm).unwrap();
+        let back = Canvas::from_ppm(fs::File::open(&ppm).unwrap());
+        fs::remove_file(&ppm).unwrap();
+        assert!(fp_equal(back.unwrap().pixel_at(1, 0).green, 0.4));
+        assert!(c.save(dir.join("raytracer_save_test.tga")).is_err());
+    }
+
+    #[test]
+    fn checking_an_output_format() {
+        assert_eq!(Canvas::check_format("out.HDR").unwrap(), "hdr");
+        assert!(Canvas::check_format("out.tga").is_err());
+        assert!(Canvas::check_format("out").is_err());
+        assert_eq!(
+            Canvas::check_format("out.png").is_ok(),
+            cfg!(feature = "image")
+        );
+    }
+
+    #[test]
+    fn converting_to_rgba() {
+        let mut c = Canvas::new(2, 1).set_transfer(Transfer::Srgb);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, 0.5));
+        c.write_pixel(1, 0, Color::new(0.0, 1.0, 0.0));
+        assert_eq!(c.to_rgba(), vec![255, 0, 188, 255, 0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn writing_a_bmp_file() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(1, 1, Color::new(0.0, 0.0, 1.0));
+        let mut bmp = vec![];
+        c.write_bmp(&mut bmp).unwrap();
+        // Two rows of six bytes, each padded to eight.
+        assert_eq!(bmp.len(), 54 + 16);
+        assert_eq!(&bmp[0..2], b"BM");
+        assert_eq!(&bmp[2..6], &70u32.to_le_bytes());
+        assert_eq!(&bmp[18..26], &[2, 0, 0, 0, 2, 0, 0, 0]);
+        // The bottom row comes first.
+        assert_eq!(&bmp[54..62], &[0, 0, 0, 255, 0, 0, 0, 0]);
+        assert_eq!(&bmp[62..70], &[0, 0, 255, 0, 0, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn writing_a_jpeg_file() {
+        let c = filled(8, 8, Color::new(1.0, 0.0, 0.0));
+        let mut jpeg = vec![];
+        c.write_jpeg(&mut jpeg, 90).unwrap();
+        assert_eq!(&jpeg[0..2], &[0xFF, 0xD8]);
+        let back = image::load_from_memory(&jpeg).unwrap().into_rgb8();
+        assert!(back.get_pixel(4, 4)[0] > 240);
+    }
+
+    #[cfg(not(feature = "image"))]
+    #[test]
+    fn writing_a_jpeg_needs_the_image_feature() {
+        assert!(Canvas::new(1, 1).write_jpeg(vec![], 90).is_err());
+    }
+
+    #[test]
+    fn writing_a_pfm_file_keeps_bright_values() {
+        let mut c = Canvas::new(1, 2);
+        c.write_pixel(0, 0, Color::new(4.5, 0.0, -1.0));
+        c.write_pixel(0, 1, Color::new(0.25, 0.5, 1.0));
+        let mut pfm = vec![];
+        c.write_pfm(&mut pfm).unwrap();
+        let header = b"PF\n1 2\n-1.0\n";
+        assert!(pfm.starts_with(header));
+        let floats: Vec<f32> = pfm[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(floats, vec![0.25, 0.5, 1.0, 4.5, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn hdr_round_trips_through_canvas() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(12.0, 3.0, 0.75));
+        c.write_pixel(1, 0, Color::new(0.0, 0.0, 0.0));
+        let mut hdr = vec![];
+        c.write_hdr(&mut hdr).unwrap();
+        let back = Canvas::from_hdr(&hdr).unwrap();
+        let p = back.pixel_at(0, 0);
+        assert!((p.red - 12.0).abs() < 0.1 && (p.green - 3.0).abs() < 0.1);
+        assert!((p.blue - 0.75).abs() < 0.1);
+        assert_eq!(back.pixel_at(1, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    fn hdr_header(width: u32, height: u32) -> Vec<u8> {
+        format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            height, width
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn reading_a_flat_hdr_file() {
+        let mut hdr = hdr_header(2, 1);
+        hdr.extend_from_slice(&[128, 64, 0, 129, 0, 0, 0, 0]);
+        let c = Canvas::from_hdr(&hdr).unwrap();
+        assert_eq!(c.pixel_at(0, 0), Color::new(1.0, 0.5, 0.0));
+        assert_eq!(c.pixel_at(1, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn reading_a_run_length_encoded_hdr_file() {
+        let mut hdr = hdr_header(8, 1);
+        hdr.extend_from_slice(&[2, 2, 0, 8]);
+        // Red: a run of eight 128s. Green: eight literal values. Blue: runs
+        // of four 0s and four 255s. Exponent: a run of eight 130s.
+        hdr.extend_from_slice(&[136, 128]);
+        hdr.extend_from_slice(&[8, 0, 32, 64, 96, 128, 160, 192, 224]);
+        hdr.extend_from_slice(&[132, 0, 132, 255]);
+        hdr.extend_from_slice(&[136, 130]);
+        let c = Canvas::from_hdr(&hdr).unwrap();
+        assert!(c.width == 8 && c.height == 1);
+        assert_eq!(c.pixel_at(0, 0), Color::new(2.0, 0.0, 0.0));
+        assert_eq!(c.pixel_at(4, 0), Color::new(2.0, 2.0, 255.0 / 64.0));
+        assert!(Canvas::from_hdr(&hdr[..hdr.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn hdr_files_need_a_radiance_header() {
+        assert!(Canvas::from_hdr(b"P3\n1 1\n255\n0 0 0\n").is_err());
+        let mut hdr = b"#?RADIANCE\n\n+Y 1 +X 1\n".to_vec();
+        hdr.extend_from_slice(&[0, 0, 0, 0]);
+        assert!(Canvas::from_hdr(&hdr).is_err());
+    }
 }
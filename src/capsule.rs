@@ -0,0 +1,244 @@
+use crate::bounding_box::BoundingBox;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::utils::EPSILON;
+use crate::vector::Vector;
+use std::any::Any;
+
+// Cylinder of the given radius around the y axis, running from -length/2 to
+// length/2, closed off by a hemisphere at each end.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capsule {
+    transform: Transform,
+    pub material: Material,
+    radius: f32,
+    length: f32,
+}
+
+impl Default for Capsule {
+    fn default() -> Capsule {
+        Capsule {
+            transform: Transform::default(),
+            material: Material::default(),
+            radius: 1.0,
+            length: 2.0,
+        }
+    }
+}
+
+impl Capsule {
+    pub fn new(radius: f32, length: f32) -> Capsule {
+        Capsule {
+            radius,
+            length,
+            ..Capsule::default()
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    pub fn set_material(mut self, m: Material) -> Self {
+        self.material = m;
+        self
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
+    }
+
+    pub fn set_radius(mut self, new: f32) -> Self {
+        self.radius = new;
+        self
+    }
+
+    pub fn set_length(mut self, new: f32) -> Self {
+        self.length = new;
+        self
+    }
+
+    // Roots of the ray against the sphere of the capsule's radius centred at
+    // (0, y, 0).
+    fn cap_roots(&self, ray: &Ray, y: f32) -> Option<(f32, f32)> {
+        let oc = ray.origin() - Point::new(0.0, y, 0.0);
+        let d = ray.direction();
+        let a = d.dot(&d);
+        let b = 2.0 * d.dot(&oc);
+        let c = oc.dot(&oc) - self.radius * self.radius;
+        quadratic(a, b, c)
+    }
+}
+
+fn quadratic(a: f32, b: f32, c: f32) -> Option<(f32, f32)> {
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return None;
+    }
+    let t0 = (-b - disc.sqrt()) / (2.0 * a);
+    let t1 = (-b + disc.sqrt()) / (2.0 * a);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Capsule {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let half = self.length / 2.0;
+        let (o, d) = (ray.origin(), ray.direction());
+        let mut ts = vec![];
+
+        let a = d.x * d.x + d.z * d.z;
+        if a >= EPSILON {
+            let b = 2.0 * (o.x * d.x + o.z * d.z);
+            let c = o.x * o.x + o.z * o.z - self.radius * self.radius;
+            if let Some((t0, t1)) = quadratic(a, b, c) {
+                for t in [t0, t1] {
+                    let y = o.y + t * d.y;
+                    if -half < y && y < half {
+                        ts.push(t);
+                    }
+                }
+            }
+        }
+
+        // Each hemisphere only counts beyond its end of the body.
+        for (centre, outside) in [(half, 1.0), (-half, -1.0)] {
+            if let Some((t0, t1)) = self.cap_roots(ray, centre) {
+                for t in [t0, t1] {
+                    let y = o.y + t * d.y;
+                    if (y - centre) * outside >= 0.0 {
+                        ts.push(t);
+                    }
+                }
+            }
+        }
+
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.into_iter().map(|t| Intersection::new(t, self)).collect()
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let half = self.length / 2.0;
+        let centre = point.y.clamp(-half, half);
+        (point - Point::new(0.0, centre, 0.0)) / self.radius
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let r = self.radius;
+        let h = self.length / 2.0 + r;
+        BoundingBox::new(Point::new(-r, -h, -r), Point::new(r, h, r))
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Capsule>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn ray_strikes_the_body_of_a_capsule() {
+        let c = Capsule::default();
+        let r = Ray::new(Point::new(0.0, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.local_intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!(fp_equal(xs[0].t(), 4.0));
+        assert!(fp_equal(xs[1].t(), 6.0));
+    }
+
+    #[test]
+    fn ray_along_the_axis_strikes_both_caps() {
+        let c = Capsule::default();
+        let r = Ray::new(Point::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let xs = c.local_intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!(fp_equal(xs[0].t(), 3.0));
+        assert!(fp_equal(xs[1].t(), 7.0));
+    }
+
+    #[test]
+    fn ray_through_a_cap_and_the_body() {
+        let c = Capsule::default();
+        let r = Ray::new(Point::new(0.0, 1.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.local_intersect(&r);
+        let z = 0.75_f32.sqrt();
+        assert_eq!(xs.len(), 2);
+        assert!(fp_equal(xs[0].t(), 5.0 - z));
+        assert!(fp_equal(xs[1].t(), 5.0 + z));
+    }
+
+    #[test]
+    fn ray_misses_a_capsule() {
+        let c = Capsule::default();
+        for (origin, direction) in [
+            (Point::new(0.0, 2.5, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(1.5, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(0.0, 3.0, 0.0), Vector::new(1.0, 0.0, 0.0)),
+        ] {
+            assert!(c.local_intersect(&Ray::new(origin, direction)).is_empty());
+        }
+    }
+
+    #[test]
+    fn normal_on_a_capsule() {
+        let c = Capsule::default();
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let cases = [
+            (Point::new(1.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0)),
+            (Point::new(0.0, -0.9, -1.0), Vector::new(0.0, 0.0, -1.0)),
+            (Point::new(0.0, 2.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, -2.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(x, 1.0 + x, 0.0), Vector::new(x, x, 0.0)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(c.local_normal_at(point), normal);
+        }
+    }
+
+    #[test]
+    fn capsule_bounds_include_the_caps() {
+        let b = Capsule::new(0.5, 3.0).bounds();
+        assert_eq!(b.min(), Point::new(-0.5, -2.0, -0.5));
+        assert_eq!(b.max(), Point::new(0.5, 2.0, 0.5));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::tile::{tiles, Tile};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"RTCKPT1\n";
+
+// A partly finished render: which of the image's tiles are done, and their
+// unexposed colors. Saved every so often during a long render so that it can
+// pick up where it left off after a crash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    tile_size: u32,
+    done: Vec<bool>,
+    pixels: Canvas,
+}
+
+impl Checkpoint {
+    // Nothing done yet, for an image split into tile_size tiles.
+    pub fn new(width: u32, height: u32, tile_size: u32) -> Checkpoint {
+        Checkpoint {
+            tile_size,
+            done: vec![false; tiles(width, height, tile_size).len()],
+            pixels: Canvas::new(width, height),
+        }
+    }
+
+    // Whether this checkpoint is for an image of this size and tiling.
+    pub fn fits(&self, width: u32, height: u32, tile_size: u32) -> bool {
+        self.pixels.width == width && self.pixels.height == height && self.tile_size == tile_size
+    }
+
+    pub fn tiles(&self) -> Vec<Tile> {
+        tiles(self.pixels.width, self.pixels.height, self.tile_size)
+    }
+
+    pub fn is_done(&self, index: usize) -> bool {
+        self.done[index]
+    }
+
+    pub fn tiles_done(&self) -> usize {
+        self.done.iter().filter(|&&done| done).count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.done.iter().all(|&done| done)
+    }
+
+    // Records the colors of the tile at index, a row at a time.
+    pub fn finish_tile(&mut self, index: usize, colors: &[Color]) {
+        let tile = self.tiles()[index];
+        for ((x, y), &color) in tile.pixels().zip(colors) {
+            self.pixels.write_pixel(x as usize, y as usize, color);
+        }
+        self.done[index] = true;
+    }
+
+    // The image so far, with black where tiles are still to do.
+    pub fn pixels(&self) -> &Canvas {
+        &self.pixels
+    }
+
+    // Little-endian throughout: the magic, width, height and tile size, one
+    // byte per tile for whether it's done, then each pixel's red, green and
+    // blue as f32s, a row at a time.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        for n in [self.pixels.width, self.pixels.height, self.tile_size].iter() {
+            writer.write_all(&n.to_le_bytes())?;
+        }
+        let done: Vec<u8> = self.done.iter().map(|&done| done as u8).collect();
+        writer.write_all(&done)?;
+        for color in self.pixels.grid.iter().flatten() {
+            for channel in [color.red, color.green, color.blue].iter() {
+                writer.write_all(&channel.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Checkpoint> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a raytracer checkpoint"));
+        }
+        let mut word = [0; 4];
+        let mut next_u32 = |reader: &mut R| -> io::Result<u32> {
+            reader.read_exact(&mut word)?;
+            Ok(u32::from_le_bytes(word))
+        };
+        let width = next_u32(&mut reader)?;
+        let height = next_u32(&mut reader)?;
+        let tile_size = next_u32(&mut reader)?;
+        if width == 0 || height == 0 || tile_size == 0 {
+            return Err(invalid("checkpoint has an empty image or tile"));
+        }
+
+        let mut checkpoint = Checkpoint::new(width, height, tile_size);
+        let mut done = vec![0; checkpoint.done.len()];
+        reader.read_exact(&mut done)?;
+        checkpoint.done = done.iter().map(|&byte| byte != 0).collect();
+        let mut channels = [0.0; 3];
+        for color in checkpoint.pixels.grid.iter_mut().flatten() {
+            for channel in channels.iter_mut() {
+                reader.read_exact(&mut word)?;
+                *channel = f32::from_le_bytes(word);
+            }
+            *color = Color::new(channels[0], channels[1], channels[2]);
+        }
+        Ok(checkpoint)
+    }
+
+    // Writes to a temporary file next to path first, then renames it over
+    // path, so a crash while saving leaves the previous checkpoint intact.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        self.write(BufWriter::new(File::create(&temporary)?))?;
+        fs::rename(&temporary, path)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Checkpoint> {
+        Checkpoint::read(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finishing_tiles() {
+        let mut c = Checkpoint::new(5, 3, 2);
+        assert_eq!(c.tiles().len(), 6);
+        assert_eq!(c.tiles_done(), 0);
+        let red = Color::new(1.0, 0.0, 0.0);
+        c.finish_tile(2, &[red; 2]);
+        assert!(c.is_done(2));
+        assert!(!c.is_complete());
+        assert_eq!(c.pixels().pixel_at(4, 0), red);
+        assert_eq!(c.pixels().pixel_at(4, 1), red);
+        assert_eq!(c.pixels().pixel_at(3, 0), Color::new(0.0, 0.0, 0.0));
+        assert!(c.fits(5, 3, 2));
+        assert!(!c.fits(5, 3, 16));
+    }
+
+    #[test]
+    fn checkpoints_round_trip() {
+        let mut c = Checkpoint::new(3, 2, 2);
+        c.finish_tile(1, &[Color::new(0.25, 1.5, -0.5), Color::new(0.1, 0.2, 0.3)]);
+        let mut bytes = vec![];
+        c.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 8 + 12 + 2 + 6 * 12);
+        assert_eq!(Checkpoint::read(&bytes[..]).unwrap(), c);
+        assert!(Checkpoint::read(&bytes[..30]).is_err());
+        assert!(Checkpoint::read(&b"P3\n1 1\n255\n0 0 0\n"[..]).is_err());
+    }
+
+    #[test]
+    fn saving_replaces_the_previous_checkpoint() {
+        let path = std::env::temp_dir().join("raytracer_checkpoint_test.ckpt");
+        let mut c = Checkpoint::new(2, 2, 1);
+        c.save(&path).unwrap();
+        c.finish_tile(0, &[Color::new(1.0, 1.0, 1.0)]);
+        c.save(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), c);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
-use crate::utils::fp_equal;
+use crate::utils::{ApproxEq, Tolerance};
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub red: f32,
     pub green: f32,
@@ -16,28 +17,63 @@ impl Color {
         }
     }
 
+    // Approximate color of a black body at the given temperature in Kelvin,
+    // normalized so the brightest channel is 1. Fitted for 1000K-40000K.
+    pub fn from_temperature(kelvin: f32) -> Color {
+        let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+        let red = if t <= 66.0 {
+            255.0
+        } else {
+            329.69873 * (t - 60.0).powf(-0.13320476)
+        };
+        let green = if t <= 66.0 {
+            99.4708 * t.ln() - 161.11957
+        } else {
+            288.12217 * (t - 60.0).powf(-0.07551485)
+        };
+        let blue = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.51773 * (t - 10.0).ln() - 305.0448
+        };
+        Color::new(
+            red.clamp(0.0, 255.0) / 255.0,
+            green.clamp(0.0, 255.0) / 255.0,
+            blue.clamp(0.0, 255.0) / 255.0,
+        )
+    }
+
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        *self + (*other - *self) * t
+    }
+
+    // Relative luminance with Rec. 709 primaries.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
     pub fn equal(a: Color, b: Color) -> bool {
-        for (i, j) in [(a.red, b.red), (a.green, b.green), (a.blue, b.blue)] {
-            if !fp_equal(i, j) {
-                return false;
-            }
-        }
-        true
+        a.approx_eq(&b)
     }
 }
 
-impl PartialEq for Color {
-    fn eq(&self, other: &Color) -> bool {
-        for (i, j) in [
+impl ApproxEq for Color {
+    fn approx_eq_with(&self, other: &Color, tolerance: Tolerance) -> bool {
+        [
             (self.red, other.red),
             (self.green, other.green),
             (self.blue, other.blue),
-        ] {
-            if !fp_equal(i, j) {
-                return false;
-            }
-        }
-        true
+        ]
+        .iter()
+        .all(|&(i, j)| tolerance.equal(i, j))
+    }
+}
+
+impl PartialEq for Color {
+    fn eq(&self, other: &Color) -> bool {
+        self.approx_eq(other)
     }
 }
 
@@ -85,9 +121,40 @@ impl std::ops::Mul<Color> for Color {
     }
 }
 
+impl std::ops::AddAssign for Color {
+    fn add_assign(&mut self, other: Color) {
+        *self = *self + other;
+    }
+}
+
+impl std::ops::SubAssign for Color {
+    fn sub_assign(&mut self, other: Color) {
+        *self = *self - other;
+    }
+}
+
+impl std::ops::MulAssign<f32> for Color {
+    fn mul_assign(&mut self, other: f32) {
+        *self = *self * other;
+    }
+}
+
+impl std::ops::MulAssign<Color> for Color {
+    fn mul_assign(&mut self, other: Color) {
+        *self = *self * other;
+    }
+}
+
+impl std::iter::Sum for Color {
+    fn sum<I: Iterator<Item = Color>>(iter: I) -> Color {
+        iter.fold(Color::new(0.0, 0.0, 0.0), |acc, c| acc + c)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::fp_equal;
 
     #[test]
     fn colors_are_rgb_tuples() {
@@ -117,6 +184,65 @@ mod tests {
         assert!(Color::equal(c * 2.0, Color::new(0.4, 0.6, 0.8)));
     }
 
+    #[test]
+    fn updating_a_color_in_place() {
+        let mut c = Color::new(0.2, 0.3, 0.4);
+        c += Color::new(0.2, 0.3, 0.4);
+        assert!(Color::equal(c, Color::new(0.4, 0.6, 0.8)));
+        c -= Color::new(0.2, 0.2, 0.2);
+        assert!(Color::equal(c, Color::new(0.2, 0.4, 0.6)));
+        c *= 2.0;
+        assert!(Color::equal(c, Color::new(0.4, 0.8, 1.2)));
+        c *= Color::new(0.5, 0.0, 1.0);
+        assert!(Color::equal(c, Color::new(0.2, 0.0, 1.2)));
+    }
+
+    #[test]
+    fn interpolating_colors() {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let c = Color::new(1.0, 0.5, 0.2);
+        assert!(Color::equal(
+            black.lerp(&c, 0.5),
+            Color::new(0.5, 0.25, 0.1)
+        ));
+    }
+
+    #[test]
+    fn summing_colors() {
+        let colors = vec![Color::new(0.1, 0.2, 0.3), Color::new(0.4, 0.5, 0.6)];
+        let total: Color = colors.into_iter().sum();
+        assert!(Color::equal(total, Color::new(0.5, 0.7, 0.9)));
+        let none: Color = std::iter::empty().sum();
+        assert!(Color::equal(none, Color::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn luminance_of_colors() {
+        assert!(fp_equal(Color::new(1.0, 1.0, 1.0).luminance(), 1.0));
+        assert!(fp_equal(Color::new(0.0, 1.0, 0.0).luminance(), 0.7152));
+        assert!(fp_equal(Color::new(0.0, 0.0, 0.0).luminance(), 0.0));
+    }
+
+    #[test]
+    fn color_temperature_of_daylight_is_near_white() {
+        let c = Color::from_temperature(6600.0);
+        assert!(Color::equal(c, Color::new(1.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn low_color_temperature_is_warm() {
+        let c = Color::from_temperature(2700.0);
+        assert!(fp_equal(c.red, 1.0));
+        assert!(c.green < c.red && c.blue < c.green);
+    }
+
+    #[test]
+    fn high_color_temperature_is_cool() {
+        let c = Color::from_temperature(12000.0);
+        assert!(fp_equal(c.blue, 1.0));
+        assert!(c.red < c.blue);
+    }
+
     #[test]
     fn multiplying_colors() {
         let c1 = Color::new(1.0, 0.2, 0.4);
@@ -0,0 +1,216 @@
+use crate::bounding_box::{check_axis, BoundingBox};
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::vector::Vector;
+use std::any::Any;
+
+// Axis-aligned cube spanning -1 to 1 on every axis in object space.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cube {
+    transform: Transform,
+    pub material: Material,
+}
+
+impl Cube {
+    pub fn new(matrix: Matrix, material: Material) -> Cube {
+        Cube {
+            transform: Transform::new(matrix),
+            material,
+        }
+    }
+
+    pub fn set_material(mut self, m: Material) -> Self {
+        self.material = m;
+        self
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Cube {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let (xtmin, xtmax) = check_axis(ray.origin().x, ray.direction().x, -1.0, 1.0);
+        let (ytmin, ytmax) = check_axis(ray.origin().y, ray.direction().y, -1.0, 1.0);
+        let (ztmin, ztmax) = check_axis(ray.origin().z, ray.direction().z, -1.0, 1.0);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            return vec![];
+        }
+
+        vec![Intersection::new(tmin, self), Intersection::new(tmax, self)]
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let maxc = point.x.abs().max(point.y.abs()).max(point.z.abs());
+
+        if maxc == point.x.abs() {
+            Vector::new(point.x, 0.0, 0.0)
+        } else if maxc == point.y.abs() {
+            Vector::new(0.0, point.y, 0.0)
+        } else {
+            Vector::new(0.0, 0.0, point.z)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Cube>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn ray_intersects_a_cube() {
+        let c = Cube::default();
+        let cases = [
+            (
+                Point::new(5.0, 0.5, 0.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(-5.0, 0.5, 0.0),
+                Vector::new(1.0, 0.0, 0.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.5, 5.0, 0.0),
+                Vector::new(0.0, -1.0, 0.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.5, -5.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.5, 0.0, 5.0),
+                Vector::new(0.0, 0.0, -1.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.5, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.0, 0.5, 0.0),
+                Vector::new(0.0, 0.0, 1.0),
+                -1.0,
+                1.0,
+            ),
+        ];
+        for (origin, direction, t1, t2) in cases {
+            let r = Ray::new(origin, direction);
+            let xs = c.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert!(fp_equal(xs[0].t(), t1));
+            assert!(fp_equal(xs[1].t(), t2));
+        }
+    }
+
+    #[test]
+    fn ray_misses_a_cube() {
+        let c = Cube::default();
+        let cases = [
+            (
+                Point::new(-2.0, 0.0, 0.0),
+                Vector::new(0.2673, 0.5345, 0.8018),
+            ),
+            (
+                Point::new(0.0, -2.0, 0.0),
+                Vector::new(0.8018, 0.2673, 0.5345),
+            ),
+            (
+                Point::new(0.0, 0.0, -2.0),
+                Vector::new(0.5345, 0.8018, 0.2673),
+            ),
+            (Point::new(2.0, 0.0, 2.0), Vector::new(0.0, 0.0, -1.0)),
+            (Point::new(0.0, 2.0, 2.0), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(2.0, 2.0, 0.0), Vector::new(-1.0, 0.0, 0.0)),
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(origin, direction);
+            assert!(c.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn normal_on_surface_of_cube() {
+        let c = Cube::default();
+        let cases = [
+            (Point::new(1.0, 0.5, -0.8), Vector::new(1.0, 0.0, 0.0)),
+            (Point::new(-1.0, -0.2, 0.9), Vector::new(-1.0, 0.0, 0.0)),
+            (Point::new(-0.4, 1.0, -0.1), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.3, -1.0, -0.7), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(-0.6, 0.3, 1.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(0.4, 0.4, -1.0), Vector::new(0.0, 0.0, -1.0)),
+            (Point::new(1.0, 1.0, 1.0), Vector::new(1.0, 0.0, 0.0)),
+            (Point::new(-1.0, -1.0, -1.0), Vector::new(-1.0, 0.0, 0.0)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(c.local_normal_at(point), normal);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_transformed_cube() {
+        let c = Cube::default().set_transform(Matrix::scaling(2.0, 1.0, 1.0));
+        let r = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let xs = c.intersect(&r);
+        assert!(fp_equal(xs[0].t(), 3.0));
+        assert!(fp_equal(xs[1].t(), 7.0));
+    }
+}
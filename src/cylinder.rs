@@ -0,0 +1,321 @@
+use crate::bounding_box::BoundingBox;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::utils::EPSILON;
+use crate::vector::Vector;
+use std::any::Any;
+
+// Unit-radius cylinder around the y axis, truncated to minimum < y < maximum
+// and optionally capped at both ends.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cylinder {
+    transform: Transform,
+    pub material: Material,
+    minimum: f32,
+    maximum: f32,
+    closed: bool,
+}
+
+impl Default for Cylinder {
+    fn default() -> Cylinder {
+        Cylinder {
+            transform: Transform::default(),
+            material: Material::default(),
+            minimum: f32::NEG_INFINITY,
+            maximum: f32::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl Cylinder {
+    pub fn new(matrix: Matrix, material: Material) -> Cylinder {
+        Cylinder {
+            transform: Transform::new(matrix),
+            material,
+            ..Cylinder::default()
+        }
+    }
+
+    pub fn minimum(&self) -> f32 {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> f32 {
+        self.maximum
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn set_material(mut self, m: Material) -> Self {
+        self.material = m;
+        self
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
+    }
+
+    pub fn set_minimum(mut self, new: f32) -> Self {
+        self.minimum = new;
+        self
+    }
+
+    pub fn set_maximum(mut self, new: f32) -> Self {
+        self.maximum = new;
+        self
+    }
+
+    pub fn set_closed(mut self, new: bool) -> Self {
+        self.closed = new;
+        self
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction().y.abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin().y) / ray.direction().y;
+            if check_cap(ray, t) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+// Whether the ray at t falls within the unit radius of a cap.
+fn check_cap(ray: &Ray, t: f32) -> bool {
+    let x = ray.origin().x + t * ray.direction().x;
+    let z = ray.origin().z + t * ray.direction().z;
+    x * x + z * z <= 1.0 + EPSILON
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Cylinder {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = vec![];
+        let (o, d) = (ray.origin(), ray.direction());
+
+        let a = d.x * d.x + d.z * d.z;
+        if a.abs() >= EPSILON {
+            let b = 2.0 * o.x * d.x + 2.0 * o.z * d.z;
+            let c = o.x * o.x + o.z * o.z - 1.0;
+            let disc = b * b - 4.0 * a * c;
+            if disc < 0.0 {
+                return xs;
+            }
+
+            let t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let t1 = (-b + disc.sqrt()) / (2.0 * a);
+            let (t0, t1) = if t0 > t1 { (t1, t0) } else { (t0, t1) };
+
+            for t in [t0, t1] {
+                let y = o.y + t * d.y;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        xs
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let dist = point.x * point.x + point.z * point.z;
+
+        if dist < 1.0 && point.y >= self.maximum - EPSILON {
+            Vector::new(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && point.y <= self.minimum + EPSILON {
+            Vector::new(0.0, -1.0, 0.0)
+        } else {
+            Vector::new(point.x, 0.0, point.z)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-1.0, self.minimum, -1.0),
+            Point::new(1.0, self.maximum, 1.0),
+        )
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Cylinder>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn ray_misses_a_cylinder() {
+        let cyl = Cylinder::default();
+        let cases = [
+            (Point::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(1.0, 1.0, 1.0)),
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert!(cyl.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_cylinder() {
+        let cyl = Cylinder::default();
+        let cases = [
+            (
+                Point::new(1.0, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                5.0,
+                5.0,
+            ),
+            (
+                Point::new(0.0, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.5, 0.0, -5.0),
+                Vector::new(0.1, 1.0, 1.0),
+                6.80798,
+                7.08872,
+            ),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = cyl.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert!((xs[0].t() - t0).abs() < 0.0001);
+            assert!((xs[1].t() - t1).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_a_cylinder() {
+        let cyl = Cylinder::default();
+        let cases = [
+            (Point::new(1.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0)),
+            (Point::new(0.0, 5.0, -1.0), Vector::new(0.0, 0.0, -1.0)),
+            (Point::new(0.0, -2.0, 1.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(-1.0, 1.0, 0.0), Vector::new(-1.0, 0.0, 0.0)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(cyl.local_normal_at(point), normal);
+        }
+    }
+
+    #[test]
+    fn default_cylinder_is_infinite_and_open() {
+        let cyl = Cylinder::default();
+        assert_eq!(cyl.minimum(), f32::NEG_INFINITY);
+        assert_eq!(cyl.maximum(), f32::INFINITY);
+        assert!(!cyl.closed());
+    }
+
+    #[test]
+    fn intersecting_a_constrained_cylinder() {
+        let cyl = Cylinder::default().set_minimum(1.0).set_maximum(2.0);
+        let cases = [
+            (Point::new(0.0, 1.5, 0.0), Vector::new(0.1, 1.0, 0.0), 0),
+            (Point::new(0.0, 3.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 1.5, -2.0), Vector::new(0.0, 0.0, 1.0), 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn intersecting_the_caps_of_a_closed_cylinder() {
+        let cyl = Cylinder::default()
+            .set_minimum(1.0)
+            .set_maximum(2.0)
+            .set_closed(true);
+        let cases = [
+            (Point::new(0.0, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0), 2),
+            (Point::new(0.0, 3.0, -2.0), Vector::new(0.0, -1.0, 2.0), 2),
+            (Point::new(0.0, 4.0, -2.0), Vector::new(0.0, -1.0, 1.0), 2),
+            (Point::new(0.0, 0.0, -2.0), Vector::new(0.0, 1.0, 2.0), 2),
+            (Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 1.0), 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_cylinder_end_caps() {
+        let cyl = Cylinder::default()
+            .set_minimum(1.0)
+            .set_maximum(2.0)
+            .set_closed(true);
+        let cases = [
+            (Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(0.5, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(0.0, 1.0, 0.5), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(0.0, 2.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.5, 2.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 2.0, 0.5), Vector::new(0.0, 1.0, 0.0)),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(cyl.local_normal_at(point), normal);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_transformed_cylinder() {
+        let cyl = Cylinder::default().set_transform(Matrix::translation(0.0, 0.0, 2.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = cyl.intersect(&r);
+        assert!(fp_equal(xs[0].t(), 6.0));
+        assert!(fp_equal(xs[1].t(), 8.0));
+    }
+}
@@ -0,0 +1,111 @@
+use crate::color::Color;
+use crate::ray::Ray;
+use crate::stats;
+use crate::world::World;
+
+// False-color pictures of a scene, for seeing at a glance where it is wrong
+// or slow. A camera with a debug view renders one of these in place of the
+// usual image, with one ray through the middle of each pixel and no
+// exposure or gamma.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DebugView {
+    // World space normals facing the camera, with x, y and z from -1 to 1
+    // shown as red, green and blue from 0 to 1.
+    Normals,
+    // White at the camera fading to black at far and beyond.
+    Depth { far: f32 },
+    // Ray-shape tests made working out each pixel's color, shadow and
+    // secondary rays included, on the heat scale up to max.
+    IntersectionTests { max: u32 },
+    // Reflected and refracted rays followed from each pixel, on the heat
+    // scale up to max.
+    Bounces { max: u32 },
+}
+
+impl DebugView {
+    pub fn color_at(&self, world: &World, ray: &Ray) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        match *self {
+            DebugView::Normals => match world.surface_sample(ray) {
+                Some(s) => Color::new(s.normal.x + 1.0, s.normal.y + 1.0, s.normal.z + 1.0) * 0.5,
+                None => black,
+            },
+            DebugView::Depth { far } => match world.surface_sample(ray) {
+                Some(s) => {
+                    let v = (1.0 - s.depth / far).max(0.0);
+                    Color::new(v, v, v)
+                }
+                None => black,
+            },
+            DebugView::IntersectionTests { max } => {
+                let before = stats::thread_counts();
+                world.color_at(ray);
+                let tests = (stats::thread_counts() - before).intersection_tests;
+                heat(tests as f32 / max as f32)
+            }
+            DebugView::Bounces { max } => {
+                let before = stats::thread_counts();
+                world.color_at(ray);
+                let bounces = (stats::thread_counts() - before).bounces;
+                heat(bounces as f32 / max as f32)
+            }
+        }
+    }
+}
+
+// Blue through cyan, green and yellow to red as x goes from 0 to 1.
+pub fn heat(x: f32) -> Color {
+    let x = x.clamp(0.0, 1.0) * 4.0;
+    let ramp = |v: f32| v.clamp(0.0, 1.0);
+    Color::new(ramp(x - 2.0), ramp(x).min(ramp(4.0 - x)), ramp(2.0 - x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Material;
+    use crate::point::Point;
+    use crate::point_light::PointLight;
+    use crate::sphere::Sphere;
+    use crate::vector::Vector;
+
+    #[test]
+    fn heat_scale() {
+        assert_eq!(heat(0.0), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(heat(0.5), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(heat(1.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(heat(7.0), heat(1.0));
+    }
+
+    #[test]
+    fn normals_and_depth() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let normal = DebugView::Normals.color_at(&w, &r);
+        assert_eq!(normal, Color::new(0.5, 0.5, 0.0));
+        let depth = DebugView::Depth { far: 8.0 }.color_at(&w, &r);
+        assert_eq!(depth, Color::new(0.5, 0.5, 0.5));
+        let away = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(
+            DebugView::Normals.color_at(&w, &away),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn counting_tests_and_bounces() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        // Two spheres for the camera ray and two more for the shadow ray.
+        let tests = DebugView::IntersectionTests { max: 8 }.color_at(&w, &r);
+        assert_eq!(tests, heat(0.5));
+        assert_eq!(DebugView::Bounces { max: 4 }.color_at(&w, &r), heat(0.0));
+
+        // The mirror reflects the ray back out into empty space.
+        let mirror = Sphere::default().set_material(Material::default().set_reflective(1.0));
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let w = World::new(vec![Box::new(mirror)], Box::new(light));
+        assert_eq!(DebugView::Bounces { max: 4 }.color_at(&w, &r), heat(0.25));
+    }
+}
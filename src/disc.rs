@@ -0,0 +1,159 @@
+use crate::bounding_box::BoundingBox;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::utils::EPSILON;
+use crate::vector::Vector;
+use std::any::Any;
+
+// Flat disc in the xz plane, centred on the origin and facing +y.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Disc {
+    transform: Transform,
+    pub material: Material,
+    radius: f32,
+}
+
+impl Default for Disc {
+    fn default() -> Disc {
+        Disc {
+            transform: Transform::default(),
+            material: Material::default(),
+            radius: 1.0,
+        }
+    }
+}
+
+impl Disc {
+    pub fn new(radius: f32) -> Disc {
+        Disc {
+            radius,
+            ..Disc::default()
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn set_material(mut self, m: Material) -> Self {
+        self.material = m;
+        self
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
+    }
+
+    pub fn set_radius(mut self, new: f32) -> Self {
+        self.radius = new;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Disc {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if ray.direction().y.abs() < EPSILON {
+            return vec![];
+        }
+
+        let t = -ray.origin().y / ray.direction().y;
+        let p = ray.position(t);
+        if p.x * p.x + p.z * p.z > self.radius * self.radius {
+            return vec![];
+        }
+        vec![Intersection::new(t, self)]
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-self.radius, 0.0, -self.radius),
+            Point::new(self.radius, 0.0, self.radius),
+        )
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Disc>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn ray_strikes_a_disc() {
+        let d = Disc::new(2.0);
+        let r = Ray::new(Point::new(1.5, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let xs = d.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert!(fp_equal(xs[0].t(), 1.0));
+    }
+
+    #[test]
+    fn ray_outside_radius_misses_a_disc() {
+        let d = Disc::new(2.0);
+        let r = Ray::new(Point::new(1.5, 1.0, 1.5), Vector::new(0.0, -1.0, 0.0));
+        assert!(d.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_parallel_to_a_disc_misses() {
+        let d = Disc::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(d.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn normal_of_a_disc_is_constant() {
+        let d = Disc::default();
+        let n = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(d.local_normal_at(Point::new(0.0, 0.0, 0.0)), n);
+        assert_eq!(d.local_normal_at(Point::new(0.5, 0.0, -0.5)), n);
+    }
+
+    #[test]
+    fn disc_bounds_are_flat() {
+        let b = Disc::new(3.0).bounds();
+        assert_eq!(b.min(), Point::new(-3.0, 0.0, -3.0));
+        assert_eq!(b.max(), Point::new(3.0, 0.0, 3.0));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+
+// Glyphs are 3 x 5 pixels, with a pixel of space after each.
+const GLYPH_WIDTH: i32 = 3;
+const GLYPH_HEIGHT: i32 = 5;
+
+// Simple overlays for debugging and labelling renders. Coordinates may fall
+// outside the canvas; anything off the edge is clipped.
+impl Canvas {
+    fn plot(&mut self, x: i32, y: i32, color: Color) {
+        if x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height {
+            self.write_pixel(x as usize, y as usize, color);
+        }
+    }
+
+    // Bresenham's line, including both end points.
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+        loop {
+            self.plot(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += sx;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    // The outline of a width x height rectangle with its top-left at (x, y).
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (right, bottom) = (x + width as i32 - 1, y + height as i32 - 1);
+        self.draw_line(x, y, right, y, color);
+        self.draw_line(x, bottom, right, bottom, color);
+        self.draw_line(x, y, x, bottom, color);
+        self.draw_line(right, y, right, bottom, color);
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        for py in y..y + height as i32 {
+            for px in x..x + width as i32 {
+                self.plot(px, py, color);
+            }
+        }
+    }
+
+    // Midpoint circle outline.
+    pub fn draw_circle(&mut self, cx: i32, cy: i32, radius: u32, color: Color) {
+        let (mut x, mut y) = (radius as i32, 0);
+        let mut error = 1 - x;
+        while x >= y {
+            for (px, py) in [(x, y), (y, x), (-y, x), (-x, y)].iter() {
+                self.plot(cx + px, cy + py, color);
+                self.plot(cx - px, cy - py, color);
+            }
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    // Stamps text in a tiny built-in font with its top-left at (x, y), each
+    // font pixel drawn as a scale x scale block. Letters are shown in upper
+    // case; characters the font lacks are drawn as '?'.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, scale: u32, color: Color) {
+        let scale = scale.max(1) as i32;
+        let (mut pen_x, mut pen_y) = (x, y);
+        for c in text.chars() {
+            if c == '\n' {
+                pen_x = x;
+                pen_y += (GLYPH_HEIGHT + 1) * scale;
+                continue;
+            }
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (4 >> col) != 0 {
+                        let px = pen_x + col * scale;
+                        let py = pen_y + row as i32 * scale;
+                        self.fill_rect(px, py, scale as u32, scale as u32, color);
+                    }
+                }
+            }
+            pen_x += (GLYPH_WIDTH + 1) * scale;
+        }
+    }
+}
+
+// One row per entry, top first, with the leftmost pixel in the 4s bit.
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        '0' => [7, 5, 5, 5, 7],
+        '1' => [2, 6, 2, 2, 7],
+        '2' => [7, 1, 7, 4, 7],
+        '3' => [7, 1, 7, 1, 7],
+        '4' => [5, 5, 7, 1, 1],
+        '5' => [7, 4, 7, 1, 7],
+        '6' => [7, 4, 7, 5, 7],
+        '7' => [7, 1, 1, 1, 1],
+        '8' => [7, 5, 7, 5, 7],
+        '9' => [7, 5, 7, 1, 7],
+        'A' => [2, 5, 7, 5, 5],
+        'B' => [6, 5, 6, 5, 6],
+        'C' => [3, 4, 4, 4, 3],
+        'D' => [6, 5, 5, 5, 6],
+        'E' => [7, 4, 6, 4, 7],
+        'F' => [7, 4, 6, 4, 4],
+        'G' => [3, 4, 5, 5, 3],
+        'H' => [5, 5, 7, 5, 5],
+        'I' => [7, 2, 2, 2, 7],
+        'J' => [1, 1, 1, 5, 2],
+        'K' => [5, 5, 6, 5, 5],
+        'L' => [4, 4, 4, 4, 7],
+        'M' => [5, 7, 7, 5, 5],
+        'N' => [6, 5, 5, 5, 5],
+        'O' => [2, 5, 5, 5, 2],
+        'P' => [6, 5, 6, 4, 4],
+        'Q' => [2, 5, 5, 6, 3],
+        'R' => [6, 5, 6, 5, 5],
+        'S' => [3, 4, 2, 1, 6],
+        'T' => [7, 2, 2, 2, 2],
+        'U' => [5, 5, 5, 5, 7],
+        'V' => [5, 5, 5, 5, 2],
+        'W' => [5, 5, 7, 7, 5],
+        'X' => [5, 5, 2, 5, 5],
+        'Y' => [5, 5, 2, 2, 2],
+        'Z' => [7, 1, 2, 4, 7],
+        ' ' => [0, 0, 0, 0, 0],
+        '.' => [0, 0, 0, 0, 2],
+        ',' => [0, 0, 0, 2, 4],
+        '-' => [0, 0, 7, 0, 0],
+        '+' => [0, 2, 7, 2, 0],
+        ':' => [0, 2, 0, 2, 0],
+        '/' => [1, 1, 2, 4, 4],
+        '%' => [5, 1, 2, 4, 5],
+        '(' => [1, 2, 2, 2, 1],
+        ')' => [4, 2, 2, 2, 4],
+        _ => [7, 1, 2, 0, 2],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn lit(c: &Canvas) -> Vec<(usize, usize)> {
+        let mut pixels = vec![];
+        for y in 0..c.height as usize {
+            for x in 0..c.width as usize {
+                if c.pixel_at(x, y) != Color::new(0.0, 0.0, 0.0) {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn drawing_a_line_covers_both_end_points() {
+        let mut c = Canvas::new(7, 3);
+        c.draw_line(6, 2, 0, 0, white());
+        let pixels = lit(&c);
+        assert_eq!(pixels.len(), 7);
+        assert_eq!(pixels[..3], [(0, 0), (1, 0), (2, 1)]);
+        assert_eq!(pixels[6], (6, 2));
+    }
+
+    #[test]
+    fn drawing_is_clipped_to_the_canvas() {
+        let mut c = Canvas::new(4, 4);
+        c.draw_rect(-2, 1, 10, 2, white());
+        c.draw_circle(0, 0, 10, white());
+        c.fill_rect(3, 3, 5, 5, white());
+        // Two full rows of the rectangle and one corner of the square.
+        let pixels = lit(&c);
+        assert_eq!(pixels.len(), 9);
+        assert!(pixels[..8].iter().all(|&(_, y)| y == 1 || y == 2));
+        assert_eq!(pixels[8], (3, 3));
+    }
+
+    #[test]
+    fn drawing_a_circle_touches_its_radius() {
+        let mut c = Canvas::new(7, 7);
+        c.draw_circle(3, 3, 3, white());
+        let pixels = lit(&c);
+        for p in [(0, 3), (6, 3), (3, 0), (3, 6)].iter() {
+            assert!(pixels.contains(p));
+        }
+        assert!(!pixels.contains(&(3, 3)));
+    }
+
+    #[test]
+    fn drawing_scaled_text() {
+        let mut c = Canvas::new(16, 10);
+        c.draw_text(0, 0, "1-", 2, white());
+        // The top of the 1 is its middle column, two pixels wide at scale 2.
+        assert_eq!(lit(&c)[..2], [(2, 0), (3, 0)]);
+        // The dash starts one glyph and one space along.
+        assert_eq!(c.pixel_at(8, 4), white());
+        assert_eq!(c.pixel_at(13, 5), white());
+        assert_eq!(c.pixel_at(8, 2), Color::new(0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,211 @@
+use crate::color::Color;
+use crate::point::Point;
+use crate::rng::Rng;
+use crate::texture::ImageTexture;
+use crate::uv::{spherical_map, UvPattern};
+use crate::vector::Vector;
+use std::f32::consts::PI;
+use std::io;
+use std::path::Path;
+
+// A direction drawn from an environment map and the probability density (per
+// steradian) of having drawn it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvironmentSample {
+    pub direction: Vector,
+    pub pdf: f32,
+}
+
+// An equirectangular image surrounding the whole scene, seen by rays that
+// miss every object. It can also light diffuse surfaces, through directions
+// importance-sampled by brightness afresh at every shading point.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvironmentMap {
+    texture: ImageTexture,
+    intensity: f32,
+    diffuse_samples: usize,
+    // Each pixel's weight, luminance times the solid angle it covers, with
+    // the running totals along each row and down the rows' totals.
+    weights: Vec<Vec<f32>>,
+    row_cdfs: Vec<Vec<f32>>,
+    marginal: Vec<f32>,
+}
+
+impl EnvironmentMap {
+    pub fn new(texture: ImageTexture) -> EnvironmentMap {
+        EnvironmentMap {
+            texture,
+            intensity: 1.0,
+            diffuse_samples: 0,
+            weights: vec![],
+            row_cdfs: vec![],
+            marginal: vec![],
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<EnvironmentMap> {
+        Ok(EnvironmentMap::new(ImageTexture::load(path)?))
+    }
+
+    // Scales every color the map gives out.
+    pub fn set_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn color_in_direction(&self, direction: Vector) -> Color {
+        let d = direction.normalize();
+        let (u, v) = spherical_map(Point::new(d.x, d.y, d.z));
+        self.texture.uv_pattern_at(u, v) * self.intensity
+    }
+
+    // How many directions to draw from the map for each diffuse hit, with
+    // probability proportional to each pixel's luminance times the solid
+    // angle it covers. Zero turns diffuse lighting from the map off.
+    pub fn set_diffuse_samples(mut self, count: usize) -> Self {
+        let canvas = self.texture.canvas();
+        let height = canvas.height as usize;
+        self.weights = canvas
+            .grid
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let sin_phi = ((y as f32 + 0.5) / height as f32 * PI).sin();
+                // A small floor keeps black pixels reachable.
+                row.iter()
+                    .map(|c| (c.luminance().max(0.0) + 0.0001) * sin_phi)
+                    .collect()
+            })
+            .collect();
+        self.row_cdfs = self.weights.iter().map(|row| cumulative(row)).collect();
+        let row_totals: Vec<f32> = self.row_cdfs.iter().map(|cdf| cdf[cdf.len() - 1]).collect();
+        self.marginal = cumulative(&row_totals);
+        self.diffuse_samples = count;
+        self
+    }
+
+    pub fn diffuse_samples(&self) -> usize {
+        self.diffuse_samples
+    }
+
+    // Draws one direction for diffuse lighting. Needs set_diffuse_samples.
+    pub fn sample(&self, rng: &mut Rng) -> EnvironmentSample {
+        let (width, height) = (self.weights[0].len(), self.weights.len());
+        let total = self.marginal[height - 1];
+        loop {
+            let y = pick(&self.marginal, rng.next_f32() * total);
+            let row = &self.row_cdfs[y];
+            let x = pick(row, rng.next_f32() * row[width - 1]);
+            let u = (x as f32 + rng.next_f32()) / width as f32;
+            let v = 1.0 - (y as f32 + rng.next_f32()) / height as f32;
+            let sin_phi = ((1.0 - v) * PI).sin();
+            if sin_phi <= 0.0 {
+                continue;
+            }
+            let pixel_probability = self.weights[y][x] / total;
+            return EnvironmentSample {
+                direction: direction_from_uv(u, v),
+                pdf: pixel_probability * (width * height) as f32 / (2.0 * PI * PI * sin_phi),
+            };
+        }
+    }
+}
+
+fn cumulative(values: &[f32]) -> Vec<f32> {
+    values
+        .iter()
+        .scan(0.0, |sum, v| {
+            *sum += v;
+            Some(*sum)
+        })
+        .collect()
+}
+
+// The first index whose cumulative value passes target.
+fn pick(cdf: &[f32], target: f32) -> usize {
+    cdf.partition_point(|&c| c <= target).min(cdf.len() - 1)
+}
+
+// The inverse of spherical_map for points on the unit sphere.
+fn direction_from_uv(u: f32, v: f32) -> Vector {
+    let theta = (0.5 - u) * 2.0 * PI;
+    let phi = (1.0 - v) * PI;
+    Vector::new(phi.sin() * theta.sin(), phi.cos(), phi.sin() * theta.cos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::utils::fp_equal;
+
+    fn filled(width: u32, height: u32, color: Color) -> ImageTexture {
+        let mut c = Canvas::new(width, height);
+        for pixel in c.grid.iter_mut().flatten() {
+            *pixel = color;
+        }
+        ImageTexture::new(c)
+    }
+
+    #[test]
+    fn direction_from_uv_inverts_spherical_map() {
+        for &(u, v) in &[(0.1, 0.3), (0.5, 0.5), (0.8, 0.9), (0.3, 0.2)] {
+            let d = direction_from_uv(u, v);
+            let (u2, v2) = spherical_map(Point::new(d.x, d.y, d.z));
+            assert!(fp_equal(u, u2) && fp_equal(v, v2));
+        }
+    }
+
+    #[test]
+    fn environment_color_is_scaled_by_intensity() {
+        let env = EnvironmentMap::new(filled(4, 2, Color::new(0.5, 0.25, 1.0))).set_intensity(2.0);
+        assert_eq!(
+            env.color_in_direction(Vector::new(0.0, 1.0, 0.0)),
+            Color::new(1.0, 0.5, 2.0)
+        );
+    }
+
+    fn draw(env: &EnvironmentMap, count: usize, seed: u64) -> Vec<EnvironmentSample> {
+        let mut rng = Rng::new(seed);
+        (0..count).map(|_| env.sample(&mut rng)).collect()
+    }
+
+    #[test]
+    fn uniform_environment_samples_the_whole_sphere() {
+        let env =
+            EnvironmentMap::new(filled(8, 4, Color::new(1.0, 1.0, 1.0))).set_diffuse_samples(16);
+        assert_eq!(env.diffuse_samples(), 16);
+        let samples = draw(&env, 2000, 1);
+        let up = samples.iter().filter(|s| s.direction.y > 0.0).count();
+        assert!((900..1100).contains(&up));
+        // Averaging 1 / pdf estimates the area of the unit sphere.
+        let area = samples.iter().map(|s| 1.0 / s.pdf).sum::<f32>() / 2000.0;
+        assert!((area - 4.0 * PI).abs() < 0.5);
+    }
+
+    #[test]
+    fn samples_favour_bright_pixels() {
+        let mut c = Canvas::new(4, 2);
+        c.write_pixel(1, 0, Color::new(100.0, 100.0, 100.0));
+        let env = EnvironmentMap::new(ImageTexture::new(c)).set_diffuse_samples(100);
+        let bright = draw(&env, 100, 3)
+            .iter()
+            .filter(|s| env.color_in_direction(s.direction).red > 1.0)
+            .count();
+        assert!(bright > 95);
+    }
+
+    #[test]
+    fn different_streams_draw_different_directions() {
+        let env =
+            EnvironmentMap::new(filled(8, 4, Color::new(1.0, 1.0, 1.0))).set_diffuse_samples(4);
+        assert!(draw(&env, 4, 1) != draw(&env, 4, 2));
+        assert!(draw(&env, 4, 1) == draw(&env, 4, 1));
+    }
+}
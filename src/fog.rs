@@ -0,0 +1,127 @@
+use crate::color::Color;
+
+// Blends what a camera ray sees towards a flat color with distance. Rays that
+// miss everything are taken to be infinitely far away.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fog {
+    // No fog nearer than start, only fog past end.
+    Linear { color: Color, start: f32, end: f32 },
+    // The fraction of the original color left is e^(-density * distance).
+    Exponential { color: Color, density: f32 },
+}
+
+impl Fog {
+    // How much of the fog color to show at distance, from 0 to 1.
+    pub fn amount(&self, distance: f32) -> f32 {
+        match *self {
+            Fog::Linear { start, end, .. } => ((distance - start) / (end - start)).clamp(0.0, 1.0),
+            Fog::Exponential { density, .. } => 1.0 - (-density * distance).exp(),
+        }
+    }
+
+    pub fn apply(&self, color: Color, distance: f32) -> Color {
+        let fog_color = match *self {
+            Fog::Linear { color, .. } => color,
+            Fog::Exponential { color, .. } => color,
+        };
+        let amount = self.amount(distance);
+        color * (1.0 - amount) + fog_color * amount
+    }
+}
+
+// A uniform scattering volume filling the whole scene. Light from the world's
+// lights is scattered towards the eye wherever it is not shadowed, giving
+// visible light shafts, and everything behind is dimmed by absorption.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Medium {
+    density: f32,
+    color: Color,
+    steps: u32,
+}
+
+impl Medium {
+    // density is the fraction of light scattered or absorbed per unit
+    // distance; color tints the scattered light.
+    pub fn new(density: f32, color: Color) -> Medium {
+        Medium {
+            density,
+            color,
+            steps: 32,
+        }
+    }
+
+    // How many points along each ray are tested for light.
+    pub fn set_steps(mut self, steps: u32) -> Self {
+        self.steps = steps.max(1);
+        self
+    }
+
+    pub fn density(&self) -> f32 {
+        self.density
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    // The fraction of light that makes it through distance without being
+    // scattered or absorbed.
+    pub fn transmittance(&self, distance: f32) -> f32 {
+        (-self.density * distance).exp()
+    }
+
+    // Past this distance less than a thousandth of the light gets through,
+    // so marching further adds nothing visible.
+    pub fn reach(&self) -> f32 {
+        if self.density > 0.0 {
+            -(0.001f32.ln()) / self.density
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn linear_fog_between_start_and_end() {
+        let fog = Fog::Linear {
+            color: Color::new(1.0, 1.0, 1.0),
+            start: 10.0,
+            end: 20.0,
+        };
+        let black = Color::new(0.0, 0.0, 0.0);
+        assert_eq!(fog.apply(black, 5.0), black);
+        assert_eq!(fog.apply(black, 15.0), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(fog.apply(black, f32::INFINITY), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn exponential_fog_thickens_with_distance() {
+        let fog = Fog::Exponential {
+            color: Color::new(1.0, 1.0, 1.0),
+            density: 0.5,
+        };
+        assert!(fp_equal(fog.amount(0.0), 0.0));
+        assert!(fp_equal(fog.amount(2.0), 1.0 - (-1.0f32).exp()));
+        assert!(fp_equal(fog.amount(f32::INFINITY), 1.0));
+    }
+
+    #[test]
+    fn medium_transmittance_and_reach() {
+        let m = Medium::new(0.5, Color::new(1.0, 1.0, 1.0));
+        assert!(fp_equal(m.transmittance(0.0), 1.0));
+        assert!(fp_equal(m.transmittance(2.0), (-1.0f32).exp()));
+        assert!(m.transmittance(m.reach()) < 0.0011);
+        assert_eq!(Medium::new(0.0, Color::new(1.0, 1.0, 1.0)).reach(), 0.0);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::canvas::Canvas;
+use std::io::{self, Write};
+
+// Writes the canvases as a looping animated GIF, showing each for delay_ms
+// milliseconds. Needs the `image` feature.
+pub fn write_gif<W: Write>(writer: W, frames: &[Canvas], delay_ms: u32) -> io::Result<()> {
+    write_gif_frames(writer, frames.len(), delay_ms, |i| frames[i].clone())
+}
+
+// Like write_gif, but asks for each frame in turn so only one needs to be
+// in memory at a time, e.g. rendering frame i as it's written.
+#[cfg(feature = "image")]
+pub fn write_gif_frames<W, F>(
+    writer: W,
+    count: usize,
+    delay_ms: u32,
+    mut frame: F,
+) -> io::Result<()>
+where
+    W: Write,
+    F: FnMut(usize) -> Canvas,
+{
+    use image::codecs::gif::{GifEncoder, Repeat};
+    use image::{Delay, DynamicImage, Frame, RgbImage};
+
+    let mut encoder = GifEncoder::new_with_speed(writer, 10);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(io::Error::other)?;
+    for i in 0..count {
+        let canvas = frame(i);
+        let rgb = RgbImage::from_raw(
+            canvas.width,
+            canvas.height,
+            canvas.rgb_bytes(&canvas.grid.concat()),
+        )
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty GIF frame"))?;
+        let rgba = DynamicImage::ImageRgb8(rgb).into_rgba8();
+        let delay = Delay::from_numer_denom_ms(delay_ms, 1);
+        encoder
+            .encode_frame(Frame::from_parts(rgba, 0, 0, delay))
+            .map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "image"))]
+pub fn write_gif_frames<W, F>(
+    _writer: W,
+    _count: usize,
+    _delay_ms: u32,
+    _frame: F,
+) -> io::Result<()>
+where
+    W: Write,
+    F: FnMut(usize) -> Canvas,
+{
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "GIF output needs the `image` feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn filled(color: Color) -> Canvas {
+        let mut c = Canvas::new(4, 4);
+        for pixel in c.grid.iter_mut().flatten() {
+            *pixel = color;
+        }
+        c
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn writing_an_animated_gif() {
+        use image::codecs::gif::GifDecoder;
+        use image::AnimationDecoder;
+
+        let frames = vec![
+            filled(Color::new(1.0, 0.0, 0.0)),
+            filled(Color::new(0.0, 0.0, 1.0)),
+        ];
+        let mut gif = vec![];
+        write_gif(&mut gif, &frames, 100).unwrap();
+        assert_eq!(&gif[0..6], b"GIF89a");
+
+        let decoded = GifDecoder::new(&gif[..])
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].delay().numer_denom_ms(), (100, 1));
+        assert_eq!(decoded[0].buffer().get_pixel(2, 2)[0], 255);
+        assert_eq!(decoded[1].buffer().get_pixel(2, 2)[2], 255);
+    }
+
+    #[cfg(not(feature = "image"))]
+    #[test]
+    fn writing_a_gif_needs_the_image_feature() {
+        let frames = vec![filled(Color::new(1.0, 0.0, 0.0))];
+        assert!(write_gif(vec![], &frames, 100).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::bounding_box::BoundingBox;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::vector::Vector;
+use std::any::Any;
+use std::borrow::Cow;
+
+// A collection of shapes sharing the group's transform. Children keep their
+// own transforms relative to the group; each intersection a group returns
+// borrows the child and carries the transforms of every enclosing group, so
+// normals come out in world space. The group keeps the merged
+// bounds of its children and skips them all when a ray misses that box.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Group {
+    transform: Transform,
+    material: Material,
+    children: Vec<Box<dyn Shape>>,
+    bounds: BoundingBox,
+}
+
+impl Group {
+    pub fn new(matrix: Matrix, children: Vec<Box<dyn Shape>>) -> Group {
+        let bounds = children.iter().fold(BoundingBox::default(), |b, c| {
+            b.merge(&c.parent_space_bounds())
+        });
+        Group {
+            transform: Transform::new(matrix),
+            material: Material::default(),
+            children,
+            bounds,
+        }
+    }
+
+    pub fn children(&self) -> &Vec<Box<dyn Shape>> {
+        &self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub fn add_child(mut self, child: Box<dyn Shape>) -> Self {
+        self.bounds = self.bounds.merge(&child.parent_space_bounds());
+        self.children.push(child);
+        self
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Group {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if !self.bounds.intersects(ray) {
+            return vec![];
+        }
+
+        let mut inters: Vec<Intersection> = self
+            .children
+            .iter()
+            .flat_map(|child| child.intersect(ray))
+            .map(|inter| inter.apply_parent_transform(Cow::Borrowed(&self.transform)))
+            .collect();
+        inters.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+        inters
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        unreachable!("groups have no surface; normals come from their children")
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Group>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cylinder::Cylinder;
+    use crate::pattern::StripePattern;
+    use crate::sphere::Sphere;
+    use crate::utils::{fp_equal, ApproxEq, Tolerance};
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn creating_a_new_group() {
+        let g = Group::default();
+        assert!(*g.transform() == Matrix::identity());
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn adding_a_child_to_a_group() {
+        let s = Sphere::default();
+        let g = Group::default().add_child(Box::new(s.clone()));
+        assert_eq!(g.children().len(), 1);
+        assert!(g.children()[0] == (Box::new(s) as Box<dyn Shape>));
+    }
+
+    #[test]
+    fn intersecting_ray_with_empty_group() {
+        let g = Group::default();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(g.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersecting_ray_with_nonempty_group() {
+        let s1 = Sphere::default();
+        let s2 = Sphere::default().set_transform(Matrix::translation(0.0, 0.0, -3.0));
+        let s3 = Sphere::default().set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        let g = Group::default()
+            .add_child(Box::new(s1.clone()))
+            .add_child(Box::new(s2.clone()))
+            .add_child(Box::new(s3));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.local_intersect(&r);
+        assert_eq!(xs.len(), 4);
+        assert!(xs[0].object() == &s2 as &dyn Shape);
+        assert!(xs[1].object() == &s2 as &dyn Shape);
+        assert!(xs[2].object() == &s1 as &dyn Shape);
+        assert!(xs[3].object() == &s1 as &dyn Shape);
+    }
+
+    #[test]
+    fn intersecting_a_transformed_group() {
+        let s = Sphere::default().set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        let g = Group::default()
+            .set_transform(Matrix::scaling(2.0, 2.0, 2.0))
+            .add_child(Box::new(s));
+        let r = Ray::new(Point::new(10.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.len(), 2);
+    }
+
+    #[test]
+    fn group_bounds_contain_all_children() {
+        let s = Sphere::default()
+            .set_transform(Matrix::translation(2.0, 5.0, -3.0) * Matrix::scaling(2.0, 2.0, 2.0));
+        let c = Cylinder::default()
+            .set_minimum(-2.0)
+            .set_maximum(2.0)
+            .set_transform(Matrix::translation(-4.0, -1.0, 4.0) * Matrix::scaling(0.5, 1.0, 0.5));
+        let g = Group::default()
+            .add_child(Box::new(s))
+            .add_child(Box::new(c));
+        assert_eq!(g.bounds().min(), Point::new(-4.5, -3.0, -5.0));
+        assert_eq!(g.bounds().max(), Point::new(4.0, 7.0, 4.5));
+    }
+
+    #[test]
+    fn ray_missing_group_bounds_skips_children() {
+        let s = Sphere::default().set_transform(Matrix::translation(0.0, 10.0, 0.0));
+        let g = Group::default().add_child(Box::new(s));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(!g.bounds().intersects(&r));
+        assert!(g.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        let s = Sphere::default().set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        let g2 = Group::default()
+            .set_transform(Matrix::scaling(2.0, 2.0, 2.0))
+            .add_child(Box::new(s));
+        let g1 = Group::default()
+            .set_transform(Matrix::rotation_y(FRAC_PI_2))
+            .add_child(Box::new(g2));
+        let r = Ray::new(Point::new(0.0, 0.0, -20.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g1.intersect(&r);
+        let p = xs[0].world_to_object(Point::new(-2.0, 0.0, -10.0));
+        assert_eq!(p, Point::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn converting_a_normal_from_object_to_world_space() {
+        let s = Sphere::default().set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        let g2 = Group::default()
+            .set_transform(Matrix::scaling(1.0, 2.0, 3.0))
+            .add_child(Box::new(s));
+        let g1 = Group::default()
+            .set_transform(Matrix::rotation_y(FRAC_PI_2))
+            .add_child(Box::new(g2));
+        let r = Ray::new(Point::new(0.0, 0.0, -20.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g1.intersect(&r);
+        let x = 3.0_f32.sqrt() / 3.0;
+        let n = xs[0].normal_to_world(Vector::new(x, x, x));
+        // The book's expected normals are given to four decimal places.
+        assert!(n.approx_eq_with(
+            &Vector::new(0.2857, 0.4286, -0.8571),
+            Tolerance::absolute(0.0001)
+        ));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_child_object() {
+        let s = Sphere::default().set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        let g2 = Group::default()
+            .set_transform(Matrix::scaling(1.0, 2.0, 3.0))
+            .add_child(Box::new(s));
+        let g1 = Group::default()
+            .set_transform(Matrix::rotation_y(FRAC_PI_2))
+            .add_child(Box::new(g2));
+        let r = Ray::new(Point::new(0.0, 0.0, -20.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g1.intersect(&r);
+        let n = xs[0].normal_at(Point::new(1.7321, 1.1547, -5.5774));
+        assert!(n.approx_eq_with(
+            &Vector::new(0.2857, 0.4286, -0.8571),
+            Tolerance::absolute(0.0001)
+        ));
+        assert!(fp_equal(n.magnitude(), 1.0));
+    }
+
+    #[test]
+    fn patterns_on_a_child_see_the_groups_transform() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let stripes = StripePattern::new(white, black);
+        let s = Sphere::default().set_material(Material::default().set_pattern(Box::new(stripes)));
+        let g = Group::default()
+            .set_transform(Matrix::translation(1.0, 0.0, 0.0))
+            .add_child(Box::new(s));
+        let r = Ray::new(Point::new(1.5, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        let material = xs[0].object().material();
+        // 0.5 along x in the sphere's space, on a white stripe.
+        assert_eq!(material.color_at(&xs[0], Point::new(1.5, 0.0, 0.0)), white);
+    }
+}
@@ -0,0 +1,178 @@
+// Conversions between points, vectors and matrices and those of glam and
+// nalgebra, behind the `glam` and `nalgebra` features, for embedding the
+// tracer in code that already uses one of them. Points and vectors both
+// become three-element vectors in glam, which doesn't tell them apart.
+// Only 4 x 4 matrices convert; others give MatrixError::BadShape.
+
+use crate::matrix::{Matrix, MatrixError};
+
+#[cfg(feature = "glam")]
+mod glam_conversions {
+    use super::{Matrix, MatrixError};
+    use crate::point::Point;
+    use crate::vector::Vector;
+    use glam::{Mat4, Vec3};
+    use std::convert::TryFrom;
+
+    impl From<Point> for Vec3 {
+        fn from(p: Point) -> Vec3 {
+            Vec3::new(p.x, p.y, p.z)
+        }
+    }
+
+    impl From<Vec3> for Point {
+        fn from(v: Vec3) -> Point {
+            Point::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl From<Vector> for Vec3 {
+        fn from(v: Vector) -> Vec3 {
+            Vec3::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl From<Vec3> for Vector {
+        fn from(v: Vec3) -> Vector {
+            Vector::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl TryFrom<&Matrix> for Mat4 {
+        type Error = MatrixError;
+
+        fn try_from(m: &Matrix) -> Result<Mat4, MatrixError> {
+            let c = super::columns(m)?;
+            Ok(Mat4::from_cols_array_2d(&c))
+        }
+    }
+
+    impl TryFrom<Matrix> for Mat4 {
+        type Error = MatrixError;
+
+        fn try_from(m: Matrix) -> Result<Mat4, MatrixError> {
+            Mat4::try_from(&m)
+        }
+    }
+
+    impl From<Mat4> for Matrix {
+        fn from(m: Mat4) -> Matrix {
+            super::from_columns(&m.to_cols_array_2d())
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_conversions {
+    use super::{Matrix, MatrixError};
+    use crate::point::Point;
+    use crate::vector::Vector;
+    use nalgebra::{Matrix4, Point3, Vector3};
+    use std::convert::TryFrom;
+
+    impl From<Point> for Point3<f32> {
+        fn from(p: Point) -> Point3<f32> {
+            Point3::new(p.x, p.y, p.z)
+        }
+    }
+
+    impl From<Point3<f32>> for Point {
+        fn from(p: Point3<f32>) -> Point {
+            Point::new(p.x, p.y, p.z)
+        }
+    }
+
+    impl From<Vector> for Vector3<f32> {
+        fn from(v: Vector) -> Vector3<f32> {
+            Vector3::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl From<Vector3<f32>> for Vector {
+        fn from(v: Vector3<f32>) -> Vector {
+            Vector::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl TryFrom<&Matrix> for Matrix4<f32> {
+        type Error = MatrixError;
+
+        fn try_from(m: &Matrix) -> Result<Matrix4<f32>, MatrixError> {
+            let c = super::columns(m)?;
+            Ok(Matrix4::from_fn(|r, col| c[col][r]))
+        }
+    }
+
+    impl TryFrom<Matrix> for Matrix4<f32> {
+        type Error = MatrixError;
+
+        fn try_from(m: Matrix) -> Result<Matrix4<f32>, MatrixError> {
+            Matrix4::try_from(&m)
+        }
+    }
+
+    impl From<Matrix4<f32>> for Matrix {
+        fn from(m: Matrix4<f32>) -> Matrix {
+            let c: [[f32; 4]; 4] = std::array::from_fn(|col| std::array::from_fn(|r| m[(r, col)]));
+            super::from_columns(&c)
+        }
+    }
+}
+
+// The columns of a 4 x 4 matrix, as both libraries store them.
+fn columns(m: &Matrix) -> Result<[[f32; 4]; 4], MatrixError> {
+    // Only a 4 x 4 matrix has a bottom right corner at (3, 3).
+    m.try_get(3, 3).map_err(|_| MatrixError::BadShape)?;
+    Ok(std::array::from_fn(|c| {
+        std::array::from_fn(|r| m.get(r as u32, c as u32))
+    }))
+}
+
+fn from_columns(c: &[[f32; 4]; 4]) -> Matrix {
+    let rows: [[f32; 4]; 4] = std::array::from_fn(|r| std::array::from_fn(|col| c[col][r]));
+    Matrix::new_filled(&[&rows[0], &rows[1], &rows[2], &rows[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point;
+    use crate::vector::Vector;
+    use std::convert::TryFrom;
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn converting_to_and_from_glam() {
+        use glam::{Mat4, Vec3};
+        let p = Point::new(1.0, 2.0, 3.0);
+        assert_eq!(Vec3::from(p), Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(Point::from(Vec3::from(p)), p);
+        let v = Vector::new(-1.0, 0.5, 2.0);
+        assert_eq!(Vector::from(Vec3::from(v)), v);
+
+        let m = Matrix::translation(1.0, 2.0, 3.0) * Matrix::rotation_y(0.5);
+        let g = Mat4::try_from(&m).unwrap();
+        assert_eq!(Point::from(g.transform_point3(p.into())), m.clone() * p);
+        assert_eq!(Matrix::from(g), m);
+        let small = Matrix::new(3, 3);
+        assert_eq!(Mat4::try_from(small), Err(MatrixError::BadShape));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn converting_to_and_from_nalgebra() {
+        use nalgebra::{Matrix4, Point3, Vector3};
+        let p = Point::new(1.0, 2.0, 3.0);
+        assert_eq!(Point3::from(p), Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(Point::from(Point3::from(p)), p);
+        let v = Vector::new(-1.0, 0.5, 2.0);
+        assert_eq!(Vector::from(Vector3::from(v)), v);
+
+        let m = Matrix::translation(1.0, 2.0, 3.0) * Matrix::rotation_y(0.5);
+        let n = Matrix4::try_from(&m).unwrap();
+        assert_eq!(Point::from(n.transform_point(&p.into())), m.clone() * p);
+        assert_eq!(Matrix::from(n), m);
+        let small = Matrix::new(3, 3);
+        assert_eq!(Matrix4::<f32>::try_from(small), Err(MatrixError::BadShape));
+    }
+}
@@ -1,13 +1,61 @@
+pub mod adjustment;
+pub mod animation;
+pub mod aov;
+pub mod background;
+pub mod bounding_box;
 pub mod camera;
+pub mod camera_path;
 pub mod canvas;
+pub mod capsule;
+pub mod checkpoint;
 pub mod color;
+pub mod cube;
+pub mod cylinder;
+pub mod debug_view;
+pub mod disc;
+pub mod draw;
+pub mod environment;
+pub mod fog;
+pub mod gif;
+pub mod group;
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+mod interop;
+pub mod light;
 pub mod material;
 pub mod matrix;
+pub mod mesh;
+pub mod moving;
+pub mod obj;
+pub mod orthonormal_basis;
+pub mod pattern;
+pub mod physical_camera;
 pub mod point;
 pub mod point_light;
+pub mod progress;
+pub mod quality;
+pub mod quaternion;
 pub mod ray;
+pub mod rectangle;
+pub mod rng;
+pub mod scene;
+#[cfg(feature = "yaml")]
+pub mod scene_file;
+pub mod shading_trace;
+pub mod shape;
+mod simd;
+pub mod sky;
 pub mod sphere;
+pub mod spot_light;
+pub mod stats;
+pub mod stl;
+pub mod test_scenes;
+pub mod texture;
+pub mod tile;
+pub mod transform;
 pub mod utils;
+pub mod uv;
 pub mod vector;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub mod world;
 pub mod world_intersection;
@@ -0,0 +1,143 @@
+use crate::color::Color;
+use crate::material::Material;
+use crate::point::Point;
+use crate::shape::ObjectSpace;
+use crate::stats;
+use crate::vector::Vector;
+use std::any::Any;
+use std::fmt::Debug;
+
+// How a light dims with distance.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Attenuation {
+    None,
+    // Fades linearly to nothing at radius.
+    Linear { radius: f32 },
+    // Physical falloff, as if from a sphere of the given radius: the
+    // intensity is divided by the squared distance, or by radius squared
+    // nearer than that. A radius of zero is an ideal point source.
+    InverseSquare { radius: f32 },
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: f32) -> f32 {
+        match *self {
+            Attenuation::None => 1.0,
+            Attenuation::Linear { radius } => (1.0 - distance / radius).max(0.0),
+            Attenuation::InverseSquare { radius } => {
+                let d = distance.max(radius);
+                1.0 / (d * d)
+            }
+        }
+    }
+}
+
+// A light source in a World. Implementors say where light comes from and how
+// much of it reaches a point; the Phong shading is shared.
+#[cfg_attr(feature = "serde", typetag::serde)]
+pub trait Light: Debug + Send + Sync {
+    fn position(&self) -> Point;
+
+    // The light's color and brightness as seen from pos.
+    fn intensity_at(&self, pos: Point) -> Color;
+
+    fn box_clone(&self) -> Box<dyn Light>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn light_eq(&self, other: &dyn Light) -> bool;
+
+    fn lighting(
+        &self,
+        mat: &Material,
+        object: &dyn ObjectSpace,
+        pos: Point,
+        eye: Vector,
+        normal: Vector,
+        in_shadow: bool,
+    ) -> Color {
+        if in_shadow {
+            stats::count_shading();
+            return mat.color_at(object, pos) * self.intensity_at(pos) * mat.ambient;
+        }
+        self.lighting_with_ambient(mat, mat.ambient, object, pos, eye, normal)
+    }
+
+    // As lighting for an unshadowed point, with ambient in place of the
+    // material's. The path tracer gathers indirect light itself, and passes
+    // zero.
+    fn lighting_with_ambient(
+        &self,
+        mat: &Material,
+        ambient: f32,
+        object: &dyn ObjectSpace,
+        pos: Point,
+        eye: Vector,
+        normal: Vector,
+    ) -> Color {
+        stats::count_shading();
+        let intensity = self.intensity_at(pos);
+        let effective_color = mat.color_at(object, pos) * intensity;
+        let lightv = (self.position() - pos).normalize();
+        let ambient = effective_color * ambient;
+        let light_dot_normal = lightv.dot(&normal);
+
+        let (diffuse, specular) = if light_dot_normal < 0.0 {
+            let black = Color::new(0.0, 0.0, 0.0);
+            (black, black)
+        } else {
+            let diff = effective_color * mat.diffuse * light_dot_normal;
+            let reflectv = (-lightv).reflect(&normal);
+            let reflect_dot_eye = reflectv.dot(&eye);
+            if reflect_dot_eye <= 0.0 {
+                (diff, Color::new(0.0, 0.0, 0.0))
+            } else {
+                let factor = reflect_dot_eye.powf(mat.shininess);
+                let specular = intensity * mat.specular * factor;
+                (diff, specular)
+            }
+        };
+        ambient + diffuse + specular
+    }
+}
+
+impl Clone for Box<dyn Light> {
+    fn clone(&self) -> Box<dyn Light> {
+        self.box_clone()
+    }
+}
+
+impl<'a> PartialEq for dyn Light + 'a {
+    fn eq(&self, other: &(dyn Light + 'a)) -> bool {
+        self.light_eq(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn no_attenuation_is_constant() {
+        assert!(fp_equal(Attenuation::None.factor(100.0), 1.0));
+    }
+
+    #[test]
+    fn linear_attenuation_reaches_zero_at_radius() {
+        let a = Attenuation::Linear { radius: 10.0 };
+        assert!(fp_equal(a.factor(0.0), 1.0));
+        assert!(fp_equal(a.factor(5.0), 0.5));
+        assert!(fp_equal(a.factor(10.0), 0.0));
+        assert!(fp_equal(a.factor(20.0), 0.0));
+    }
+
+    #[test]
+    fn inverse_square_attenuation_is_capped_inside_radius() {
+        let a = Attenuation::InverseSquare { radius: 0.5 };
+        assert!(fp_equal(a.factor(2.0), 0.25));
+        assert!(fp_equal(a.factor(4.0), 0.0625));
+        assert!(fp_equal(a.factor(0.1), 4.0));
+    }
+}
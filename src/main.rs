@@ -1,91 +1,309 @@
-use raytracer::camera::Camera;
-use raytracer::color::*;
-use raytracer::material::*;
-use raytracer::matrix::*;
-use raytracer::point::*;
-use raytracer::point_light::*;
-use raytracer::sphere::*;
-use raytracer::vector::Vector;
-use raytracer::world::World;
-
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
+use clap::{Args, Parser, Subcommand};
+use raytracer::canvas::Canvas;
+use raytracer::checkpoint::Checkpoint;
+use raytracer::quality::Quality;
+use raytracer::scene::{Scene, SceneFile};
+use raytracer::stats::RenderStats;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+
+const STANDARD_SCENE: &str = include_str!("../scenes/spheres.yaml");
+
+#[derive(Parser)]
+#[command(name = "raytracer", about = "Renders scenes described in YAML files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Render a scene to an image")]
+    Render(RenderArgs),
+    #[command(about = "Render the scene's keyframes as a numbered image sequence")]
+    Animate(AnimateArgs),
+    #[command(about = "Time renders of a standard scene and report the work done")]
+    Benchmark(BenchmarkArgs),
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    #[arg(help = "Scene file to render")]
+    scene: PathBuf,
+    #[arg(
+        short,
+        long,
+        default_value = "output.ppm",
+        help = "Image to write; the extension picks the format"
+    )]
+    output: PathBuf,
+    #[arg(
+        long,
+        help = "Carry on from the checkpoint a stopped render left next to the output"
+    )]
+    resume: bool,
+    #[arg(
+        long,
+        default_value_t = 60,
+        value_name = "SECONDS",
+        help = "How often to save a checkpoint while rendering"
+    )]
+    checkpoint_every: u64,
+    #[command(flatten)]
+    options: RenderOptions,
+}
+
+#[derive(Args)]
+struct AnimateArgs {
+    #[arg(help = "Scene file to render")]
+    scene: PathBuf,
+    #[arg(
+        short,
+        long,
+        default_value = "frame.ppm",
+        help = "Image to write; each frame's number is added before the extension"
+    )]
+    output: PathBuf,
+    #[arg(long, default_value_t = 24, help = "Number of frames to render")]
+    frames: u32,
+    #[command(flatten)]
+    options: RenderOptions,
+}
+
+#[derive(Args)]
+struct BenchmarkArgs {
+    #[arg(help = "Scene file to render [default: scenes/spheres.yaml, built in]")]
+    scene: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 3,
+        help = "Number of times to render the scene"
+    )]
+    runs: u32,
+    #[command(flatten)]
+    options: RenderOptions,
+}
+
+#[derive(Args)]
+struct RenderOptions {
+    #[arg(
+        long,
+        value_name = "PROFILE",
+        help = "draft, preview, final or a YAML quality profile, in place of the scene's"
+    )]
+    quality: Option<String>,
+    #[arg(long, help = "Image width, overriding the scene's camera")]
+    width: Option<u32>,
+    #[arg(long, help = "Image height, overriding the scene's camera")]
+    height: Option<u32>,
+    #[arg(long, help = "Samples per pixel")]
+    samples: Option<u32>,
+    #[arg(long, help = "Worker threads [default: one per CPU]")]
+    threads: Option<usize>,
+}
 
 fn main() {
-    let floormaterial = Material::default()
-        .set_color(Color::new(1.0, 0.9, 0.9))
-        .set_specular(0.0);
-
-    let floor = Sphere::default()
-        .set_transform(Matrix::scaling(10.0, 0.01, 10.0))
-        .set_material(floormaterial.clone());
-
-    let left_wall = Sphere::default()
-        .set_transform(
-            Matrix::translation(0.0, 0.0, 5.0)
-                * Matrix::rotation_y(-std::f32::consts::PI / 4.0)
-                * Matrix::rotation_x(std::f32::consts::PI / 2.0)
-                * Matrix::scaling(10.0, 0.01, 10.0),
-        )
-        .set_material(floormaterial.clone());
-
-    let right_wall = Sphere::default()
-        .set_transform(
-            Matrix::translation(0.0, 0.0, 5.0)
-                * Matrix::rotation_y(std::f32::consts::PI / 4.0)
-                * Matrix::rotation_x(std::f32::consts::PI / 2.0)
-                * Matrix::scaling(10.0, 0.01, 10.0),
-        )
-        .set_material(floormaterial);
-
-    let middle = Sphere::default()
-        .set_transform(Matrix::translation(-0.5, 1.0, 0.5))
-        .set_material(
-            Material::default()
-                .set_color(Color::new(0.1, 1.0, 0.5))
-                .set_diffuse(0.7)
-                .set_specular(0.3),
-        );
+    let result = match Cli::parse().command {
+        Command::Render(args) => render(args),
+        Command::Animate(args) => animate(args),
+        Command::Benchmark(args) => benchmark(args),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
 
-    let right = Sphere::default()
-        .set_transform(Matrix::translation(1.5, 0.5, -0.5) * Matrix::scaling(0.5, 0.5, 0.5))
-        .set_material(
-            Material::default()
-                .set_color(Color::new(0.5, 1.0, 0.1))
-                .set_diffuse(0.7)
-                .set_specular(0.3),
-        );
+// Long renders save a checkpoint beside the output every so often, which is
+// removed once the image is written. Resuming needs the same scene and
+// options as the render that was stopped, or the finished tiles won't match
+// the new ones.
+fn render(args: RenderArgs) -> Result<(), String> {
+    check_output(&args.output)?;
+    let (file, quality) = take_quality(SceneFile::load(&args.scene)?, &args.options)?;
+    let scene = configure(file.start()?, quality.as_ref(), &args.options)?;
+    let camera = scene.camera;
+    let (width, height, tile_size) = (camera.hsize(), camera.vsize(), camera.tile_size());
 
-    let left = Sphere::default()
-        .set_transform(Matrix::translation(-1.5, 0.33, -0.75) * Matrix::scaling(0.33, 0.33, 0.33))
-        .set_material(
-            Material::default()
-                .set_color(Color::new(1.0, 0.8, 0.1))
-                .set_diffuse(0.7)
-                .set_specular(0.3),
+    let mut path = args.output.clone().into_os_string();
+    path.push(".checkpoint");
+    let path = PathBuf::from(path);
+    let mut checkpoint = if args.resume {
+        let checkpoint = Checkpoint::load(&path)
+            .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+        if !checkpoint.fits(width, height, tile_size) {
+            return Err(format!("{} is for a different image size", path.display()));
+        }
+        println!(
+            "resuming with {} of {} tiles done",
+            checkpoint.tiles_done(),
+            checkpoint.tiles().len()
         );
+        checkpoint
+    } else {
+        Checkpoint::new(width, height, tile_size)
+    };
+
+    let every = Duration::from_secs(args.checkpoint_every);
+    let mut last_saved = Instant::now();
+    let image = camera.render_resumable(scene.world, &mut checkpoint, |checkpoint| {
+        if last_saved.elapsed() >= every && !checkpoint.is_complete() {
+            if let Err(e) = checkpoint.save(&path) {
+                eprintln!("warning: couldn't save {}: {}", path.display(), e);
+            }
+            last_saved = Instant::now();
+        }
+    });
+    write(image, &args.output)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("couldn't remove {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
 
-    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+fn animate(args: AnimateArgs) -> Result<(), String> {
+    if args.frames == 0 {
+        return Err(String::from("frames must be at least 1"));
+    }
+    check_output(&args.output)?;
+    let (file, quality) = take_quality(SceneFile::load(&args.scene)?, &args.options)?;
+    let (start, end) = file.time_range().unwrap_or((0.0, 0.0));
+    let digits = args.frames.to_string().len().max(4);
+    if let Some(dir) = args
+        .output
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        fs::create_dir_all(dir).map_err(|e| format!("couldn't create {}: {}", dir.display(), e))?;
+    }
+    for frame in 0..args.frames {
+        // The first and last frames land on the first and last keyframes.
+        let time = if args.frames == 1 {
+            start
+        } else {
+            start + (end - start) * frame as f32 / (args.frames - 1) as f32
+        };
+        let scene = configure(file.at(time)?, quality.as_ref(), &args.options)?;
+        let output = numbered(&args.output, frame, digits);
+        write(scene.camera.render(scene.world), &output)?;
+    }
+    Ok(())
+}
 
-    let camera = Camera::new(500, 500, std::f32::consts::PI / 3.0)
-        .set_transform(Matrix::view_transform(
-            Point::new(0.0, 1.5, -5.0),
-            Point::new(0.0, 1.0, 0.0),
-            Vector::new(0.0, 1.0, 0.0),
-        ));
+// The scene is the same from run to run, so only the times differ; the
+// fastest run is reported as the least disturbed by anything else going on.
+fn benchmark(args: BenchmarkArgs) -> Result<(), String> {
+    if args.runs == 0 {
+        return Err(String::from("runs must be at least 1"));
+    }
+    let file = match &args.scene {
+        Some(path) => SceneFile::load(path)?,
+        None => SceneFile::parse(STANDARD_SCENE)?,
+    };
+    let (file, quality) = take_quality(file, &args.options)?;
+    let mut size = (0, 0);
+    let mut fastest: Option<RenderStats> = None;
+    for run in 1..=args.runs {
+        // Rendering uses up the world, so each run builds the scene afresh.
+        let scene = configure(file.start()?, quality.as_ref(), &args.options)?;
+        size = (scene.camera.hsize(), scene.camera.vsize());
+        let (_, stats) = scene.camera.render_with_stats(scene.world);
+        println!("run {}: {:.3}s", run, stats.elapsed.as_secs_f64());
+        if fastest.is_none_or(|f| stats.elapsed < f.elapsed) {
+            fastest = Some(stats);
+        }
+    }
+    let stats = fastest.unwrap();
+    println!("image:              {}x{}", size.0, size.1);
+    println!("rays cast:          {}", stats.rays_cast);
+    println!("intersection tests: {}", stats.intersection_tests);
+    println!("shading calls:      {}", stats.shading_calls);
+    println!("fastest run:        {:.3}s", stats.elapsed.as_secs_f64());
+    println!("rays per second:    {:.0}", stats.rays_per_second());
+    Ok(())
+}
 
-    let world = World::new(vec![floor, left_wall, right_wall, middle, right, left], light);
-    let canvas = camera.render(world);
+// frames/shot.png becomes frames/shot_0007.png for frame 7.
+fn numbered(path: &Path, frame: u32, digits: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}_{:0width$}", stem, frame, width = digits);
+    if let Some(extension) = path.extension() {
+        name = format!("{}.{}", name, extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
 
-    let path = Path::new("output.ppm");
-    let mut file = match File::create(&path) {
-        Err(e) => panic!("couldn't create file: {}", e),
-        Ok(file) => file,
+// The quality to render at, taken off the scene file: the one asked for on
+// the command line, or else the scene's own. configure applies it once the
+// size is settled, so that it scales a size given on the command line too.
+fn take_quality(
+    file: SceneFile,
+    args: &RenderOptions,
+) -> Result<(SceneFile, Option<Quality>), String> {
+    let quality = match &args.quality {
+        Some(name) => Some(match Quality::preset(name) {
+            Some(quality) => quality,
+            None => {
+                let profile = fs::read_to_string(name)
+                    .map_err(|e| format!("couldn't read quality profile {}: {}", name, e))?;
+                Quality::from_yaml(&profile).map_err(|e| format!("{}: {}", name, e))?
+            }
+        }),
+        None => file.quality().cloned(),
     };
+    Ok((file.set_quality(None), quality))
+}
 
-    match file.write_all(canvas.to_ppm().as_bytes()) {
-        Err(e) => panic!("couldn't write file: {}", e),
-        Ok(_) => println!("written file"),
+fn configure(
+    scene: Scene,
+    quality: Option<&Quality>,
+    args: &RenderOptions,
+) -> Result<Scene, String> {
+    let (mut camera, mut world) = (scene.camera, scene.world);
+    // Giving only one of width and height keeps the camera's aspect ratio.
+    let aspect = camera.hsize() as f32 / camera.vsize() as f32;
+    let (width, height) = match (args.width, args.height) {
+        (Some(w), Some(h)) => (w, h),
+        (Some(w), None) => (w, (w as f32 / aspect).round() as u32),
+        (None, Some(h)) => ((h as f32 * aspect).round() as u32, h),
+        (None, None) => (camera.hsize(), camera.vsize()),
+    };
+    if width == 0 || height == 0 {
+        return Err(format!("image size {}x{} is empty", width, height));
+    }
+    camera = camera.set_size(width, height);
+    // The profile scales the size and sets the samples, unless --samples
+    // says otherwise.
+    if let Some(quality) = quality {
+        camera = camera.set_quality(quality);
+        world = world.set_quality(quality);
+    }
+    if let Some(samples) = args.samples {
+        if samples == 0 {
+            return Err(String::from("samples must be at least 1"));
+        }
+        camera = camera.set_samples(samples);
     }
+    if let Some(threads) = args.threads {
+        camera = camera.set_threads(threads);
+    }
+    Ok(Scene { camera, world })
+}
+
+// Catches an output the build can't write before the render, not after it.
+fn check_output(output: &Path) -> Result<(), String> {
+    Canvas::check_format(output)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn write(canvas: Canvas, output: &Path) -> Result<(), String> {
+    canvas
+        .save(output)
+        .map_err(|e| format!("couldn't write {}: {}", output.display(), e))?;
+    println!("wrote {}", output.display());
+    Ok(())
 }
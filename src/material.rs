@@ -1,12 +1,23 @@
 use crate::color::Color;
+use crate::pattern::Pattern;
+use crate::point::Point;
+use crate::shape::ObjectSpace;
+use crate::vector::Vector;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material {
     pub color: Color,
+    pub pattern: Option<Box<dyn Pattern>>,
+    pub normal_map: Option<Box<dyn Pattern>>,
     pub ambient: f32,
     pub diffuse: f32,
     pub specular: f32,
     pub shininess: f32,
+    pub reflective: f32,
+    pub transparency: f32,
+    pub refractive_index: f32,
+    pub emissive: Color,
 }
 
 impl Material {
@@ -19,18 +30,66 @@ impl Material {
     ) -> Material {
         Material {
             color,
+            pattern: None,
+            normal_map: None,
             ambient,
             diffuse,
             specular,
             shininess,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            emissive: Color::new(0.0, 0.0, 0.0),
         }
     }
 
+    // Clear, mostly transmissive and strongly reflective, with the
+    // refractive index of window glass.
+    pub fn glass() -> Material {
+        Material::default()
+            .set_ambient(0.0)
+            .set_diffuse(0.1)
+            .set_specular(1.0)
+            .set_shininess(300.0)
+            .set_reflective(0.9)
+            .set_transparency(0.9)
+            .set_refractive_index(1.5)
+    }
+
+    pub fn metal() -> Material {
+        Material::default()
+            .set_color(Color::new(0.8, 0.8, 0.8))
+            .set_diffuse(0.3)
+            .set_specular(1.0)
+            .set_shininess(300.0)
+            .set_reflective(0.8)
+    }
+
+    pub fn matte() -> Material {
+        Material::default()
+            .set_diffuse(0.9)
+            .set_specular(0.0)
+            .set_shininess(10.0)
+    }
+
     pub fn set_color(mut self, new: Color) -> Self {
         self.color = new;
         self
     }
 
+    // Overrides color wherever the material is lit.
+    pub fn set_pattern(mut self, new: Box<dyn Pattern>) -> Self {
+        self.pattern = Some(new);
+        self
+    }
+
+    // A tangent-space normal map: each colour channel, scaled from 0-1 to
+    // -1-1, gives the offset along the tangent, bitangent and surface normal.
+    pub fn set_normal_map(mut self, new: Box<dyn Pattern>) -> Self {
+        self.normal_map = Some(new);
+        self
+    }
+
     pub fn set_ambient(mut self, new: f32) -> Self {
         self.ambient = new;
         self
@@ -50,23 +109,85 @@ impl Material {
         self.shininess = new;
         self
     }
+
+    pub fn set_reflective(mut self, new: f32) -> Self {
+        self.reflective = new;
+        self
+    }
+
+    pub fn set_transparency(mut self, new: f32) -> Self {
+        self.transparency = new;
+        self
+    }
+
+    pub fn set_refractive_index(mut self, new: f32) -> Self {
+        self.refractive_index = new;
+        self
+    }
+
+    // Light given off by the surface itself. Only the path tracer treats
+    // emissive surfaces as light sources.
+    pub fn set_emissive(mut self, new: Color) -> Self {
+        self.emissive = new;
+        self
+    }
+
+    // The surface color at a world-space point on object, or on the shape a
+    // hit is on.
+    pub fn color_at(&self, object: &dyn ObjectSpace, point: Point) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern.pattern_at_shape(object, point),
+            None => self.color,
+        }
+    }
+
+    // Tilts an object-space normal by the normal map, if any. The tangent
+    // runs around the y axis, the direction spherical and cylindrical
+    // mappings increase u; on surfaces facing along y it is the x axis.
+    pub fn perturb_normal(&self, point: Point, normal: Vector) -> Vector {
+        let map = match &self.normal_map {
+            Some(m) => m,
+            None => return normal,
+        };
+        let n = normal.normalize();
+        let around = n.cross(&Vector::new(0.0, 1.0, 0.0));
+        let tangent = if around.magnitude() < 0.00001 {
+            Vector::new(1.0, 0.0, 0.0)
+        } else {
+            around.normalize()
+        };
+        let bitangent = tangent.cross(&n);
+        let c = map.pattern_at(&map.transform().inverse() * &point);
+        let offset = |x: f32| x * 2.0 - 1.0;
+        (tangent * offset(c.red) + bitangent * offset(c.green) + n * offset(c.blue)).normalize()
+    }
 }
 
 impl Default for Material {
     fn default() -> Material {
         Material {
             color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
+            normal_map: None,
             ambient: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            emissive: Color::new(0.0, 0.0, 0.0),
         }
     }
 }
 
 impl PartialEq for Material {
     fn eq(&self, other: &Material) -> bool {
-        if self.color != other.color {
+        if self.color != other.color
+            || self.pattern != other.pattern
+            || self.normal_map != other.normal_map
+            || self.emissive != other.emissive
+        {
             return false;
         }
         for (a, b) in [
@@ -74,6 +195,9 @@ impl PartialEq for Material {
             (self.diffuse, other.diffuse),
             (self.specular, other.specular),
             (self.shininess, other.shininess),
+            (self.reflective, other.reflective),
+            (self.transparency, other.transparency),
+            (self.refractive_index, other.refractive_index),
         ] {
             if a != b {
                 return false;
@@ -86,6 +210,7 @@ impl PartialEq for Material {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pattern::StripePattern;
     use crate::utils::fp_equal;
 
     #[test]
@@ -96,5 +221,49 @@ mod tests {
         assert!(fp_equal(m.diffuse, 0.9));
         assert!(fp_equal(m.specular, 0.9));
         assert!(fp_equal(m.shininess, 200.0));
+        assert!(fp_equal(m.reflective, 0.0));
+        assert!(fp_equal(m.transparency, 0.0));
+        assert!(fp_equal(m.refractive_index, 1.0));
+        assert!(m.emissive == Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn glass_preset_is_transparent_and_refracts() {
+        let m = Material::glass();
+        assert!(m.transparency > 0.0);
+        assert!(m.reflective > 0.0);
+        assert!(fp_equal(m.refractive_index, 1.5));
+    }
+
+    #[test]
+    fn metal_and_matte_presets_are_opaque() {
+        let metal = Material::metal();
+        let matte = Material::matte();
+        assert!(metal.reflective > 0.0);
+        assert!(fp_equal(metal.transparency, 0.0));
+        assert!(fp_equal(matte.reflective, 0.0));
+        assert!(fp_equal(matte.specular, 0.0));
+        assert!(fp_equal(matte.transparency, 0.0));
+    }
+
+    #[test]
+    fn flat_normal_map_leaves_normal_unchanged() {
+        let flat = StripePattern::new(Color::new(0.5, 0.5, 1.0), Color::new(0.5, 0.5, 1.0));
+        let m = Material::default().set_normal_map(Box::new(flat));
+        let n = Vector::new(0.0, 0.0, -1.0);
+        assert_eq!(m.perturb_normal(Point::new(0.0, 0.0, -1.0), n), n);
+    }
+
+    #[test]
+    fn normal_map_tilts_along_tangent_and_bitangent() {
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let tilt = Color::new(0.5 + x / 2.0, 0.5, 0.5 + x / 2.0);
+        let m = Material::default().set_normal_map(Box::new(StripePattern::new(tilt, tilt)));
+        let n = m.perturb_normal(Point::new(0.0, 0.0, -1.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(n, Vector::new(x, 0.0, -x));
+        let tilt = Color::new(0.5, 0.5 + x / 2.0, 0.5 + x / 2.0);
+        let m = Material::default().set_normal_map(Box::new(StripePattern::new(tilt, tilt)));
+        let n = m.perturb_normal(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(n, Vector::new(0.0, x, x));
     }
 }
@@ -1,62 +1,83 @@
 use crate::point::Point;
-use crate::utils::fp_equal;
+use crate::quaternion::Quaternion;
+use crate::simd;
+use crate::utils::{ApproxEq, Tolerance, EPSILON};
 use crate::vector::Vector;
-use std::convert::TryInto;
+use std::fmt;
+
+// Up to 4 x 4, stored inline so that making one never allocates. Smaller
+// matrices, such as submatrices, use the top-left corner of the grid.
+const SIZE: usize = 4;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix {
     row: u32,
     col: u32,
-    grid: Vec<f32>,
+    grid: [f32; SIZE * SIZE],
 }
 
 impl Matrix {
     pub fn new(row: u32, col: u32) -> Matrix {
+        assert!(row as usize <= SIZE && col as usize <= SIZE);
         Matrix {
             row,
             col,
-            grid: vec![0.0; (row * col).try_into().unwrap()],
+            grid: [0.0; SIZE * SIZE],
         }
     }
 
     pub fn new_filled(arr: &[&[f32]]) -> Matrix {
-        let mut grid = vec![];
-        for row in arr {
-            for col in *row {
-                grid.push(*col)
-            }
+        Matrix::try_new_filled(arr).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new_filled(arr: &[&[f32]]) -> Result<Matrix, MatrixError> {
+        let cols = arr.first().map_or(0, |row| row.len());
+        let fits = |n: usize| (1..=SIZE).contains(&n);
+        if !fits(arr.len()) || !fits(cols) || arr.iter().any(|row| row.len() != cols) {
+            return Err(MatrixError::BadShape);
         }
-        let row = (*arr).len() as u32;
-        let col = arr[0].len() as u32;
-        Matrix { row, col, grid }
+        let mut m = Matrix::new(arr.len() as u32, cols as u32);
+        for (i, row) in arr.iter().enumerate() {
+            m.grid[i * SIZE..i * SIZE + cols].copy_from_slice(row);
+        }
+        Ok(m)
     }
 
     pub fn get(&self, row: u32, col: u32) -> f32 {
-        if row > self.row {
-            panic!("row out of bounds")
-        };
-        if col > self.col {
-            panic!("col out of bounds")
-        }
-        let col = col as usize;
-        self.grid[row as usize * self.col as usize + col]
+        self[(row, col)]
+    }
+
+    pub fn try_get(&self, row: u32, col: u32) -> Result<f32, MatrixError> {
+        self.offset(row, col).map(|i| self.grid[i])
     }
 
     pub fn set(&mut self, row: u32, col: u32, val: f32) {
-        if row > self.row {
-            panic!("row out of bounds")
-        };
-        if col > self.col {
-            panic!("col out of bounds")
-        };
-        let col = col as usize;
-        self.grid[row as usize * self.col as usize + col] = val;
+        self[(row, col)] = val;
+    }
+
+    pub fn try_set(&mut self, row: u32, col: u32, val: f32) -> Result<(), MatrixError> {
+        let i = self.offset(row, col)?;
+        self.grid[i] = val;
+        Ok(())
+    }
+
+    fn offset(&self, row: u32, col: u32) -> Result<usize, MatrixError> {
+        if row >= self.row || col >= self.col {
+            return Err(MatrixError::OutOfBounds {
+                row,
+                col,
+                rows: self.row,
+                cols: self.col,
+            });
+        }
+        Ok(row as usize * SIZE + col as usize)
     }
 
     pub fn transpose(self) -> Matrix {
-        let mut m = Matrix::new(4, 4);
-        for i in 0..4 {
-            for j in 0..4 {
+        let mut m = Matrix::new(self.col, self.row);
+        for i in 0..self.row {
+            for j in 0..self.col {
                 m.set(j, i, self.get(i, j))
             }
         }
@@ -75,6 +96,10 @@ impl Matrix {
     pub fn determinant(&self) -> f32 {
         assert!(self.row == self.col);
         match self.row {
+            4 => {
+                let (s, c) = self.pair_determinants();
+                s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+            }
             2 => self.get(0, 0) * self.get(1, 1) - self.get(0, 1) * self.get(1, 0),
             _ => {
                 let mut det = 0.0;
@@ -86,18 +111,24 @@ impl Matrix {
         }
     }
 
+    // Blends each element from self at t = 0 to other at t = 1.
+    pub fn lerp(&self, other: &Matrix, t: f32) -> Matrix {
+        assert!(self.row == other.row && self.col == other.col);
+        let mut m = self.clone();
+        for (a, b) in m.grid.iter_mut().zip(other.grid.iter()) {
+            *a += (b - *a) * t;
+        }
+        m
+    }
+
     pub fn submatrix(&self, drow: u32, dcol: u32) -> Matrix {
         let mut m = Matrix::new(self.row - 1, self.col - 1);
-        let mut index = 0;
-        for (i, val) in self.grid.iter().enumerate() {
-            if (i >= (drow * self.col).try_into().unwrap()
-                && i < ((drow + 1) * self.col).try_into().unwrap())
-                || (i as u32) % self.col == dcol
-            {
-                continue;
+        let rows = (0..self.row).filter(|&r| r != drow);
+        for (i, row) in rows.enumerate() {
+            let cols = (0..self.col).filter(|&c| c != dcol);
+            for (j, col) in cols.enumerate() {
+                m.set(i as u32, j as u32, self.get(row, col));
             }
-            m.grid[index] = *val;
-            index += 1;
         }
         m
     }
@@ -107,25 +138,109 @@ impl Matrix {
     }
 
     pub fn cofactor(&self, drow: u32, dcol: u32) -> f32 {
-        self.minor(drow, dcol) * if (drow + dcol) % 2 == 0 { 1.0 } else { -1.0 }
+        self.minor(drow, dcol) * if (drow + dcol).is_multiple_of(2) { 1.0 } else { -1.0 }
     }
 
     pub fn invertible(&self) -> bool {
-        !fp_equal(self.determinant(), 0.0)
+        self.row == self.col && !self.singular(self.determinant())
+    }
+
+    // Whether det, the determinant, is too small for the matrix to be
+    // inverted. It can be no bigger than the product of the row lengths, or
+    // of the column lengths, so it's measured against the smaller of those:
+    // a uniformly tiny scale is as invertible as a huge one, but a matrix
+    // whose rows or columns are nearly dependent is not.
+    fn singular(&self, det: f32) -> bool {
+        let (mut rows, mut cols) = (1.0, 1.0);
+        for i in 0..self.row {
+            let (mut row, mut col) = (0.0, 0.0);
+            for j in 0..self.col {
+                row += self.get(i, j) * self.get(i, j);
+                col += self.get(j, i) * self.get(j, i);
+            }
+            rows *= f32::sqrt(row);
+            cols *= f32::sqrt(col);
+        }
+        det.is_nan() || det.abs() <= EPSILON * f32::min(rows, cols)
+    }
+
+    // The determinants of the 2 x 2 matrices made from each pair of columns
+    // in the top two rows, and likewise in the bottom two. Every cofactor of
+    // a 4 x 4 matrix is a sum of three of them times an entry, which makes
+    // the determinant and inverse far cheaper than expanding cofactors.
+    fn pair_determinants(&self) -> ([f32; 6], [f32; 6]) {
+        let pair = |r: usize, c0: usize, c1: usize| {
+            let m = &self.grid;
+            m[r * SIZE + c0] * m[(r + 1) * SIZE + c1] - m[(r + 1) * SIZE + c0] * m[r * SIZE + c1]
+        };
+        let columns = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+        let (mut s, mut c) = ([0.0; 6], [0.0; 6]);
+        for (i, &(c0, c1)) in columns.iter().enumerate() {
+            s[i] = pair(0, c0, c1);
+            c[i] = pair(2, c0, c1);
+        }
+        (s, c)
     }
 
     pub fn inverse(&self) -> Matrix {
-        assert!(self.invertible());
+        self.try_inverse().unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let mut m = Matrix::new(self.row, self.col);
+    pub fn try_inverse(&self) -> Result<Matrix, MatrixError> {
+        if self.row != self.col {
+            return Err(MatrixError::NotSquare);
+        }
+        if self.row != 4 {
+            return self.cofactor_inverse();
+        }
+        let (s, c) = self.pair_determinants();
+        let det = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if self.singular(det) {
+            return Err(MatrixError::NotInvertible);
+        }
+
+        let a = |r: usize, col: usize| self.grid[r * SIZE + col] / det;
+        Ok(Matrix::new_filled(&[
+            &[
+                a(1, 1) * c[5] - a(1, 2) * c[4] + a(1, 3) * c[3],
+                -a(0, 1) * c[5] + a(0, 2) * c[4] - a(0, 3) * c[3],
+                a(3, 1) * s[5] - a(3, 2) * s[4] + a(3, 3) * s[3],
+                -a(2, 1) * s[5] + a(2, 2) * s[4] - a(2, 3) * s[3],
+            ],
+            &[
+                -a(1, 0) * c[5] + a(1, 2) * c[2] - a(1, 3) * c[1],
+                a(0, 0) * c[5] - a(0, 2) * c[2] + a(0, 3) * c[1],
+                -a(3, 0) * s[5] + a(3, 2) * s[2] - a(3, 3) * s[1],
+                a(2, 0) * s[5] - a(2, 2) * s[2] + a(2, 3) * s[1],
+            ],
+            &[
+                a(1, 0) * c[4] - a(1, 1) * c[2] + a(1, 3) * c[0],
+                -a(0, 0) * c[4] + a(0, 1) * c[2] - a(0, 3) * c[0],
+                a(3, 0) * s[4] - a(3, 1) * s[2] + a(3, 3) * s[0],
+                -a(2, 0) * s[4] + a(2, 1) * s[2] - a(2, 3) * s[0],
+            ],
+            &[
+                -a(1, 0) * c[3] + a(1, 1) * c[1] - a(1, 2) * c[0],
+                a(0, 0) * c[3] - a(0, 1) * c[1] + a(0, 2) * c[0],
+                -a(3, 0) * s[3] + a(3, 1) * s[1] - a(3, 2) * s[0],
+                a(2, 0) * s[3] - a(2, 1) * s[1] + a(2, 2) * s[0],
+            ],
+        ]))
+    }
+
+    fn cofactor_inverse(&self) -> Result<Matrix, MatrixError> {
+        let det = self.determinant();
+        if self.singular(det) {
+            return Err(MatrixError::NotInvertible);
+        }
 
+        let mut m = Matrix::new(self.row, self.col);
         for row in 0..self.row {
             for col in 0..self.col {
-                let c = self.cofactor(row, col);
-                m.set(col, row, c / self.determinant());
+                m.set(col, row, self.cofactor(row, col) / det);
             }
         }
-        m
+        Ok(m)
     }
 
     pub fn translation(x: f32, y: f32, z: f32) -> Matrix {
@@ -172,6 +287,17 @@ impl Matrix {
         ])
     }
 
+    // Turns by angle radians about axis, which needn't be normalized.
+    pub fn rotation_axis(axis: Vector, angle: f32) -> Matrix {
+        Quaternion::from_axis_angle(axis, angle).to_matrix()
+    }
+
+    // Rolls about z, then pitches about x, then yaws about y, so that yaw
+    // turns whatever way the object ends up facing.
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Matrix {
+        Matrix::rotation_y(yaw) * Matrix::rotation_x(pitch) * Matrix::rotation_z(roll)
+    }
+
     pub fn shearing(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Matrix {
         Matrix::new_filled(&[
             &[1.0, xy, xz, 0.0],
@@ -197,131 +323,135 @@ impl Matrix {
     }
 }
 
-#[allow(clippy::suspicious_arithmetic_impl)]
+// What the try_ methods return instead of panicking.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatrixError {
+    OutOfBounds {
+        row: u32,
+        col: u32,
+        rows: u32,
+        cols: u32,
+    },
+    // Rows that are missing, of different lengths, or more than 4 x 4, or a
+    // matrix that isn't the size asked for.
+    BadShape,
+    NotSquare,
+    // The determinant is zero: the matrix flattens space, so nothing can
+    // undo it.
+    NotInvertible,
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatrixError::OutOfBounds {
+                row,
+                col,
+                rows,
+                cols,
+            } => write!(
+                f,
+                "({}, {}) is outside a {}x{} matrix",
+                row, col, rows, cols
+            ),
+            MatrixError::BadShape => write!(f, "a matrix needs 1 to 4 rows of 1 to 4 numbers"),
+            MatrixError::NotSquare => write!(f, "only square matrices can be inverted"),
+            MatrixError::NotInvertible => write!(f, "matrix is not invertible"),
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+// m[(row, col)], with the same bounds checks as get and set.
+impl std::ops::Index<(u32, u32)> for Matrix {
+    type Output = f32;
+
+    fn index(&self, (row, col): (u32, u32)) -> &f32 {
+        let i = self.offset(row, col).unwrap_or_else(|e| panic!("{}", e));
+        &self.grid[i]
+    }
+}
+
+impl std::ops::IndexMut<(u32, u32)> for Matrix {
+    fn index_mut(&mut self, (row, col): (u32, u32)) -> &mut f32 {
+        let i = self.offset(row, col).unwrap_or_else(|e| panic!("{}", e));
+        &mut self.grid[i]
+    }
+}
+
 impl std::ops::Mul<Matrix> for Matrix {
     type Output = Self;
     fn mul(self, other: Matrix) -> Matrix {
-        let mut m = Matrix::new(4, 4);
-        for row in 0..4 {
-            for col in 0..4 {
-                let val = self.get(row, 0) * other.get(0, col)
-                    + self.get(row, 1) * other.get(1, col)
-                    + self.get(row, 2) * other.get(2, col)
-                    + self.get(row, 3) * other.get(3, col);
-                m.set(row, col, val);
-            }
-        }
-        m
+        &self * &other
     }
 }
 
-#[allow(clippy::suspicious_arithmetic_impl)]
 impl std::ops::Mul<&Matrix> for &Matrix {
     type Output = Matrix;
     fn mul(self, other: &Matrix) -> Matrix {
-        let mut m = Matrix::new(4, 4);
-        for row in 0..4 {
-            for col in 0..4 {
-                let val = self.get(row, 0) * other.get(0, col)
-                    + self.get(row, 1) * other.get(1, col)
-                    + self.get(row, 2) * other.get(2, col)
-                    + self.get(row, 3) * other.get(3, col);
-                m.set(row, col, val);
-            }
+        assert!(self.row == 4 && self.col == 4 && other.row == 4 && other.col == 4);
+        Matrix {
+            row: 4,
+            col: 4,
+            grid: simd::mul_matrices(&self.grid, &other.grid),
         }
-        m
     }
 }
 
-#[allow(clippy::suspicious_arithmetic_impl)]
 impl std::ops::Mul<Point> for Matrix {
     type Output = Point;
     fn mul(self, other: Point) -> Point {
-        let vals: Vec<f32> = vec![0, 1, 2, 3]
-            .into_iter()
-            .map(|x| {
-                self.get(x, 0) * other.x
-                    + self.get(x, 1) * other.y
-                    + self.get(x, 2) * other.z
-                    + self.get(x, 3) * 1.0
-            })
-            .collect();
-        Point {
-            x: vals[0],
-            y: vals[1],
-            z: vals[2],
-        }
+        &self * &other
     }
 }
 
-#[allow(clippy::suspicious_arithmetic_impl)]
 impl std::ops::Mul<&Point> for &Matrix {
     type Output = Point;
     fn mul(self, other: &Point) -> Point {
-        let vals: Vec<f32> = vec![0, 1, 2, 3]
-            .into_iter()
-            .map(|x| {
-                self.get(x, 0) * other.x
-                    + self.get(x, 1) * other.y
-                    + self.get(x, 2) * other.z
-                    + self.get(x, 3) * 1.0
-            })
-            .collect();
-        Point {
-            x: vals[0],
-            y: vals[1],
-            z: vals[2],
-        }
+        let [x, y, z] = simd::mul_tuple(&self.grid, [other.x, other.y, other.z, 1.0]);
+        Point { x, y, z }
     }
 }
 
-#[allow(clippy::suspicious_arithmetic_impl)]
 impl std::ops::Mul<Vector> for Matrix {
     type Output = Vector;
     fn mul(self, other: Vector) -> Vector {
-        let vals: Vec<f32> = vec![0, 1, 2, 3]
-            .into_iter()
-            .map(|x| self.get(x, 0) * other.x + self.get(x, 1) * other.y + self.get(x, 2) * other.z)
-            .collect();
-        Vector {
-            x: vals[0],
-            y: vals[1],
-            z: vals[2],
-        }
+        &self * &other
     }
 }
 
-#[allow(clippy::suspicious_arithmetic_impl)]
 impl std::ops::Mul<&Vector> for &Matrix {
     type Output = Vector;
     fn mul(self, other: &Vector) -> Vector {
-        let vals: Vec<f32> = vec![0, 1, 2, 3]
-            .into_iter()
-            .map(|x| self.get(x, 0) * other.x + self.get(x, 1) * other.y + self.get(x, 2) * other.z)
-            .collect();
-        Vector {
-            x: vals[0],
-            y: vals[1],
-            z: vals[2],
-        }
+        let [x, y, z] = simd::mul_tuple(&self.grid, [other.x, other.y, other.z, 0.0]);
+        Vector { x, y, z }
+    }
+}
+
+impl ApproxEq for Matrix {
+    fn approx_eq_with(&self, other: &Self, tolerance: Tolerance) -> bool {
+        self.row == other.row
+            && self.col == other.col
+            && self
+                .grid
+                .iter()
+                .zip(other.grid.iter())
+                .all(|(a, b)| tolerance.equal(*a, *b))
     }
 }
 
 impl PartialEq for Matrix {
     fn eq(&self, other: &Self) -> bool {
-        assert!(self.row == other.row && self.col == other.col);
-        for i in 0..self.grid.len() {
-            if !fp_equal(self.grid[i], other.grid[i]) {
-                return false;
-            }
-        }
-        true
+        self.approx_eq(other)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::fp_equal;
+    use std::f32::consts::PI;
 
     #[test]
     fn constructing_and_inspecting_a_4x4_matrix() {
@@ -340,6 +470,48 @@ mod tests {
         assert!(fp_equal(m.get(3, 2), 15.5));
     }
 
+    #[test]
+    fn indexing_a_matrix() {
+        let mut m = Matrix::identity();
+        m[(1, 2)] = 5.5;
+        m[(3, 3)] *= 2.0;
+        assert_eq!(m[(1, 2)], 5.5);
+        assert_eq!(m.get(3, 3), 2.0);
+        assert_eq!(m[(0, 0)], 1.0);
+    }
+
+    #[test]
+    fn checked_access_reports_errors() {
+        let mut m = Matrix::new_filled(&[&[1.0, 2.0], &[3.0, 4.0]]);
+        assert_eq!(m.try_get(1, 0), Ok(3.0));
+        assert_eq!(m.try_set(0, 1, 5.0), Ok(()));
+        assert_eq!(m.get(0, 1), 5.0);
+        let outside = MatrixError::OutOfBounds {
+            row: 2,
+            col: 0,
+            rows: 2,
+            cols: 2,
+        };
+        assert_eq!(m.try_get(2, 0), Err(outside));
+        assert_eq!(m.try_set(2, 0, 1.0), Err(outside));
+        assert_eq!(outside.to_string(), "(2, 0) is outside a 2x2 matrix");
+
+        let ragged: &[&[f32]] = &[&[1.0, 2.0], &[3.0]];
+        assert_eq!(
+            Matrix::try_new_filled(ragged).err(),
+            Some(MatrixError::BadShape)
+        );
+        assert_eq!(
+            Matrix::try_new_filled(&[]).err(),
+            Some(MatrixError::BadShape)
+        );
+        let wide: &[&[f32]] = &[&[0.0; 5]];
+        assert_eq!(
+            Matrix::try_new_filled(wide).err(),
+            Some(MatrixError::BadShape)
+        );
+    }
+
     #[test]
     fn a_2x2_matrix() {
         let m = Matrix::new_filled(&[&[-3.0, 5.0], &[1.0, -2.0]]);
@@ -562,6 +734,42 @@ mod tests {
         ]);
         assert!(fp_equal(a.determinant(), 0.0));
         assert!(!a.invertible());
+        assert_eq!(a.try_inverse(), Err(MatrixError::NotInvertible));
+        assert_eq!(
+            Matrix::scaling(1.0, 0.0, 1.0).try_inverse(),
+            Err(MatrixError::NotInvertible)
+        );
+        let flat = Matrix::new_filled(&[&[1.0, 2.0], &[2.0, 4.0]]);
+        assert_eq!(flat.try_inverse(), Err(MatrixError::NotInvertible));
+        let wide = Matrix::new_filled(&[&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]]);
+        assert_eq!(wide.try_inverse(), Err(MatrixError::NotSquare));
+    }
+
+    #[test]
+    fn uniformly_small_scales_are_invertible() {
+        let a = Matrix::scaling(0.02, 0.02, 0.02);
+        assert!(a.invertible());
+        let b = Matrix::translation(100.0, -50.0, 20.0) * a;
+        let inverse = b.try_inverse().unwrap();
+        assert_eq!(inverse * b.clone(), Matrix::identity());
+        assert!(Matrix::scaling(0.001, 0.001, 0.001).try_inverse().is_ok());
+    }
+
+    #[test]
+    fn a_flattened_axis_is_not_invertible() {
+        for m in &[
+            Matrix::scaling(1.0, 0.0, 1.0),
+            Matrix::scaling(0.02, 0.02, 0.0),
+            Matrix::new_filled(&[
+                &[1.0, 2.0, 0.0, 0.0],
+                &[2.0, 4.0, 0.0, 0.0],
+                &[0.0, 0.0, 1.0, 0.0],
+                &[0.0, 0.0, 0.0, 1.0],
+            ]),
+        ] {
+            assert!(!m.invertible());
+            assert_eq!(m.try_inverse().err(), Some(MatrixError::NotInvertible));
+        }
     }
 
     #[test]
@@ -605,6 +813,19 @@ mod tests {
         assert!(a.inverse() == b);
     }
 
+    #[test]
+    fn inverse_matches_cofactor_expansion() {
+        let a = Matrix::new_filled(&[
+            &[8.0, -5.0, 9.0, 2.0],
+            &[7.0, 5.0, 6.0, 1.0],
+            &[-6.0, 0.0, 9.0, 6.0],
+            &[-3.0, 0.0, -9.0, -4.0],
+        ]);
+        assert!(Ok(a.inverse()) == a.cofactor_inverse());
+        let b = Matrix::new_filled(&[&[1.0, 2.0, 6.0], &[-5.0, 8.0, -4.0], &[2.0, 6.0, 4.0]]);
+        assert!(b.inverse().inverse() == b);
+    }
+
     #[test]
     fn calculating_inverse_of_matrix3() {
         let a = Matrix::new_filled(&[
@@ -726,6 +947,24 @@ mod tests {
         assert!(full_quarter * p == Point::new(-1.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn rotating_about_an_arbitrary_axis() {
+        let m = Matrix::rotation_axis(Vector::new(1.0, 1.0, 1.0), 2.0 * PI / 3.0);
+        assert_eq!(m * Point::new(1.0, 0.0, 0.0), Point::new(0.0, 1.0, 0.0));
+        let m = Matrix::rotation_axis(Vector::new(0.0, -3.0, 0.0), 0.6);
+        assert_eq!(m, Matrix::rotation_y(-0.6));
+    }
+
+    #[test]
+    fn rotating_by_euler_angles() {
+        let m = Matrix::from_euler(PI / 2.0, 0.0, 0.0);
+        assert_eq!(m, Matrix::rotation_y(PI / 2.0));
+        // x rolls round to y, pitches up to z and yaws back to x.
+        let m = Matrix::from_euler(PI / 2.0, PI / 2.0, PI / 2.0);
+        assert_eq!(&m * &Vector::new(1.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(m * Vector::new(0.0, 0.0, 1.0), Vector::new(0.0, -1.0, 0.0));
+    }
+
     #[test]
     fn shearing_transformation_moves_x_in_proportion_to_y() {
         let transform = Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
@@ -0,0 +1,542 @@
+use crate::bounding_box::BoundingBox;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::utils::EPSILON;
+use crate::vector::Vector;
+use std::any::Any;
+
+const LEAF_SIZE: usize = 4;
+
+// Triangle mesh kept in flat arrays: every three entries of `indices` name
+// the vertices (and, if present, per-vertex normals) of one face. Faces are
+// organised into a bounding volume hierarchy when the mesh is built, and a
+// hit borrows the mesh and names the face it's on.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mesh {
+    transform: Transform,
+    pub material: Material,
+    vertices: Vec<Point>,
+    indices: Vec<u32>,
+    normals: Vec<Vector>,
+    order: Vec<u32>,
+    nodes: Vec<Node>,
+}
+
+// A node of the hierarchy. Leaves cover order[start..start + count]; inner
+// nodes have count 0 and two children.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Node {
+    bounds: BoundingBox,
+    start: usize,
+    count: usize,
+    left: usize,
+    right: usize,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<Point>, indices: Vec<u32>) -> Mesh {
+        assert!(
+            indices.len().is_multiple_of(3),
+            "mesh indices must come in groups of three"
+        );
+        assert!(
+            indices.iter().all(|&i| (i as usize) < vertices.len()),
+            "mesh index out of range"
+        );
+        let mut mesh = Mesh {
+            transform: Transform::default(),
+            material: Material::default(),
+            vertices,
+            indices,
+            normals: vec![],
+            order: vec![],
+            nodes: vec![],
+        };
+        mesh.build();
+        mesh
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn normals(&self) -> Option<&[Vector]> {
+        if self.normals.is_empty() {
+            None
+        } else {
+            Some(&self.normals)
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn triangle(&self, face: usize) -> MeshTriangle {
+        let idx = &self.indices[face * 3..face * 3 + 3];
+        let normals = self
+            .normals()
+            .map(|n| [n[idx[0] as usize], n[idx[1] as usize], n[idx[2] as usize]]);
+        MeshTriangle {
+            transform: self.transform.clone(),
+            material: self.material.clone(),
+            points: self.face_points(face),
+            normals,
+        }
+    }
+
+    // Per-vertex normals for smooth shading, one for each vertex.
+    pub fn set_normals(mut self, normals: Vec<Vector>) -> Self {
+        assert_eq!(
+            normals.len(),
+            self.vertices.len(),
+            "mesh needs one normal per vertex"
+        );
+        self.normals = normals;
+        self
+    }
+
+    pub fn set_material(mut self, m: Material) -> Self {
+        self.material = m;
+        self
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
+    }
+
+    fn face_points(&self, face: usize) -> [Point; 3] {
+        let idx = &self.indices[face * 3..face * 3 + 3];
+        [
+            self.vertices[idx[0] as usize],
+            self.vertices[idx[1] as usize],
+            self.vertices[idx[2] as usize],
+        ]
+    }
+
+    fn face_bounds(&self, face: usize) -> BoundingBox {
+        let mut b = BoundingBox::default();
+        for p in self.face_points(face) {
+            b.add_point(p);
+        }
+        b
+    }
+
+    fn build(&mut self) {
+        let count = self.triangle_count();
+        self.order = (0..count as u32).collect();
+        self.nodes.clear();
+        if count > 0 {
+            let bounds: Vec<BoundingBox> = (0..count).map(|f| self.face_bounds(f)).collect();
+            self.build_node(&bounds, 0, count);
+        }
+    }
+
+    fn build_node(&mut self, bounds: &[BoundingBox], start: usize, end: usize) -> usize {
+        let node_bounds = self.order[start..end]
+            .iter()
+            .fold(BoundingBox::default(), |b, &f| b.merge(&bounds[f as usize]));
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds: node_bounds,
+            start,
+            count: end - start,
+            left: 0,
+            right: 0,
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+
+        // Split at the median centroid along the longest axis.
+        let centroid = |f: u32| {
+            let b = &bounds[f as usize];
+            [
+                (b.min().x + b.max().x) / 2.0,
+                (b.min().y + b.max().y) / 2.0,
+                (b.min().z + b.max().z) / 2.0,
+            ]
+        };
+        let extent = node_bounds.max() - node_bounds.min();
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        self.order[start..end]
+            .sort_by(|&a, &b| centroid(a)[axis].partial_cmp(&centroid(b)[axis]).unwrap());
+
+        let mid = (start + end) / 2;
+        let left = self.build_node(bounds, start, mid);
+        let right = self.build_node(bounds, mid, end);
+        let node = &mut self.nodes[index];
+        node.count = 0;
+        node.left = left;
+        node.right = right;
+        index
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Mesh {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut inters = vec![];
+        if self.nodes.is_empty() {
+            return inters;
+        }
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.intersects(ray) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.left);
+                stack.push(node.right);
+                continue;
+            }
+            for &face in &self.order[node.start..node.start + node.count] {
+                let face = face as usize;
+                if let Some((t, u, v)) = intersect_triangle(ray, &self.face_points(face)) {
+                    inters.push(Intersection::new(t, self).set_face(face).set_uv(u, v));
+                }
+            }
+        }
+        inters.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+        inters
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        unreachable!("mesh normals depend on the face hit; see local_hit_normal_at")
+    }
+
+    fn local_hit_normal_at(&self, _point: Point, hit: &Intersection) -> Vector {
+        let face = hit.face().expect("mesh hits say which face they're on");
+        let idx = &self.indices[face * 3..face * 3 + 3];
+        let normals = self
+            .normals()
+            .map(|n| [n[idx[0] as usize], n[idx[1] as usize], n[idx[2] as usize]]);
+        triangle_normal(&self.face_points(face), normals, hit.uv())
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.nodes.first().map(|n| n.bounds).unwrap_or_default()
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Mesh>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+// One face of a Mesh, carrying the mesh's transform and material, for
+// working with a face on its own.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshTriangle {
+    transform: Transform,
+    material: Material,
+    points: [Point; 3],
+    normals: Option<[Vector; 3]>,
+}
+
+impl MeshTriangle {
+    pub fn points(&self) -> &[Point; 3] {
+        &self.points
+    }
+
+    pub fn normals(&self) -> Option<&[Vector; 3]> {
+        self.normals.as_ref()
+    }
+}
+
+// Smooth normals are blended by the hit's u and v, as the intersection test
+// found them. Without both the face is flat.
+fn triangle_normal(
+    points: &[Point; 3],
+    normals: Option<[Vector; 3]>,
+    uv: Option<(f32, f32)>,
+) -> Vector {
+    match (normals, uv) {
+        (Some([n1, n2, n3]), Some((u, v))) => n2 * u + n3 * v + n1 * (1.0 - u - v),
+        _ => {
+            let [p1, p2, p3] = *points;
+            (p3 - p1).cross(&(p2 - p1)).normalize()
+        }
+    }
+}
+
+// Möller–Trumbore ray/triangle test, giving t and the hit's barycentric u
+// and v.
+fn intersect_triangle(ray: &Ray, points: &[Point; 3]) -> Option<(f32, f32, f32)> {
+    let [p1, p2, p3] = *points;
+    let e1 = p2 - p1;
+    let e2 = p3 - p1;
+    let dir_cross_e2 = ray.direction().cross(&e2);
+    let det = e1.dot(&dir_cross_e2);
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin() - p1;
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(&e1);
+    let v = f * ray.direction().dot(&origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    Some((f * e2.dot(&origin_cross_e1), u, v))
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for MeshTriangle {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        match intersect_triangle(ray, &self.points) {
+            Some((t, u, v)) => vec![Intersection::new(t, self).set_uv(u, v)],
+            None => vec![],
+        }
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        triangle_normal(&self.points, self.normals, None)
+    }
+
+    fn local_hit_normal_at(&self, _point: Point, hit: &Intersection) -> Vector {
+        triangle_normal(&self.points, self.normals, hit.uv())
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::default();
+        for p in self.points {
+            b.add_point(p);
+        }
+        b
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<MeshTriangle>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+    use crate::world_intersection::WorldIntersection;
+
+    fn single_triangle() -> Mesh {
+        Mesh::new(
+            vec![
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(-1.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+            ],
+            vec![0, 1, 2],
+        )
+    }
+
+    // A flat n by n grid of unit squares in the z = 0 plane, two faces each.
+    fn grid(n: u32) -> Mesh {
+        let mut vertices = vec![];
+        for y in 0..=n {
+            for x in 0..=n {
+                vertices.push(Point::new(x as f32, y as f32, 0.0));
+            }
+        }
+        let mut indices = vec![];
+        for y in 0..n {
+            for x in 0..n {
+                let i = y * (n + 1) + x;
+                indices.extend_from_slice(&[i, i + 1, i + n + 1]);
+                indices.extend_from_slice(&[i + 1, i + n + 2, i + n + 1]);
+            }
+        }
+        Mesh::new(vertices, indices)
+    }
+
+    #[test]
+    fn mesh_stores_flat_arrays() {
+        let m = grid(2);
+        assert_eq!(m.vertices().len(), 9);
+        assert_eq!(m.indices().len(), 24);
+        assert_eq!(m.triangle_count(), 8);
+        assert!(m.normals().is_none());
+    }
+
+    #[test]
+    fn normal_on_a_flat_triangle() {
+        let t = single_triangle().triangle(0);
+        let n = Vector::new(0.0, 0.0, -1.0);
+        assert_eq!(t.local_normal_at(Point::new(0.0, 0.5, 0.0)), n);
+        assert_eq!(t.local_normal_at(Point::new(-0.5, 0.75, 0.0)), n);
+    }
+
+    #[test]
+    fn ray_parallel_to_triangle_misses() {
+        let m = single_triangle();
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 0.0));
+        assert!(m.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_misses_triangle_edges() {
+        let m = single_triangle();
+        for origin in [
+            Point::new(1.0, 1.0, -2.0),
+            Point::new(-1.0, 1.0, -2.0),
+            Point::new(0.0, -1.0, -2.0),
+        ] {
+            let r = Ray::new(origin, Vector::new(0.0, 0.0, 1.0));
+            assert!(m.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_triangle() {
+        let m = single_triangle();
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = m.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert!(fp_equal(xs[0].t(), 2.0));
+        assert!(xs[0].object() == &m as &dyn Shape);
+        assert_eq!(xs[0].face(), Some(0));
+    }
+
+    #[test]
+    fn triangle_hits_carry_u_and_v() {
+        let m = single_triangle();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = m.intersect(&r);
+        let (u, v) = xs[0].uv().unwrap();
+        assert!(fp_equal(u, 0.45) && fp_equal(v, 0.25));
+        assert_eq!(m.triangle(0).intersect(&r)[0].uv(), xs[0].uv());
+
+        let comps = WorldIntersection::precompute(xs[0].clone(), &r, &xs);
+        assert_eq!(comps.uv(), xs[0].uv());
+    }
+
+    #[test]
+    fn smooth_normals_are_interpolated() {
+        let m = single_triangle().set_normals(vec![
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        ]);
+        let t = m.triangle(0);
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = t.intersect(&r);
+        let n = t.local_hit_normal_at(Point::new(-0.2, 0.3, 0.0), &xs[0]);
+        assert_eq!(n, Vector::new(-0.2, 0.3, 0.0));
+        let n = xs[0].normal_at(Point::new(-0.2, 0.3, 0.0));
+        assert!(fp_equal(n.magnitude(), 1.0));
+        assert_eq!(m.intersect(&r)[0].normal_at(Point::new(-0.2, 0.3, 0.0)), n);
+        // Without a hit to say where, the face is shaded flat.
+        assert_eq!(
+            t.local_normal_at(Point::new(-0.2, 0.3, 0.0)),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn hierarchy_finds_the_same_hits_as_testing_every_face() {
+        let m = grid(8);
+        for (x, y) in [(0.3, 0.6), (4.5, 2.25), (7.9, 7.1), (9.0, 1.0)] {
+            let r = Ray::new(Point::new(x, y, -1.0), Vector::new(0.0, 0.0, 1.0));
+            let brute = (0..m.triangle_count())
+                .filter(|&f| intersect_triangle(&r, &m.face_points(f)).is_some())
+                .count();
+            assert_eq!(m.local_intersect(&r).len(), brute);
+        }
+    }
+
+    #[test]
+    fn mesh_bounds_cover_every_vertex() {
+        let m = grid(3).set_transform(Matrix::translation(1.0, 0.0, 0.0));
+        assert_eq!(m.bounds().min(), Point::new(0.0, 0.0, 0.0));
+        assert_eq!(m.bounds().max(), Point::new(3.0, 3.0, 0.0));
+        assert_eq!(m.parent_space_bounds().min(), Point::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn transformed_mesh_hits_report_world_normals() {
+        let m = single_triangle().set_transform(Matrix::translation(0.0, 0.0, 3.0));
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = m.intersect(&r);
+        assert!(fp_equal(xs[0].t(), 5.0));
+        let n = xs[0].normal_at(Point::new(0.0, 0.5, 3.0));
+        assert_eq!(n, Vector::new(0.0, 0.0, -1.0));
+    }
+}
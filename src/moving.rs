@@ -0,0 +1,197 @@
+use crate::bounding_box::BoundingBox;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::vector::Vector;
+use std::any::Any;
+use std::borrow::Cow;
+
+// A shape whose transform changes over time, for motion blur. The child is
+// moved by `from` at time 0 and by `to` at time 1, blending between the two
+// for rays cast in between and holding still before and after. The blend is
+// per matrix element, which is exact for translation and scaling but
+// shrinks objects part way through large rotations.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Moving {
+    transform: Transform,
+    material: Material,
+    child: Box<dyn Shape>,
+    from: Matrix,
+    to: Matrix,
+    bounds: BoundingBox,
+}
+
+impl Moving {
+    pub fn new(child: Box<dyn Shape>, from: Matrix, to: Matrix) -> Moving {
+        // Every blended transform moves a point onto the line between where
+        // from and to put it, so both end boxes together bound the motion.
+        let child_bounds = child.parent_space_bounds();
+        let bounds = child_bounds
+            .transform(&from)
+            .merge(&child_bounds.transform(&to));
+        Moving {
+            transform: Transform::default(),
+            material: Material::default(),
+            child,
+            from,
+            to,
+            bounds,
+        }
+    }
+
+    pub fn child(&self) -> &dyn Shape {
+        self.child.as_ref()
+    }
+
+    pub fn motion_at(&self, time: f32) -> Matrix {
+        self.from.lerp(&self.to, time.clamp(0.0, 1.0))
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
+    }
+}
+
+impl PartialEq for Moving {
+    fn eq(&self, other: &Moving) -> bool {
+        self.transform == other.transform
+            && self.material == other.material
+            && *self.child == *other.child
+            && self.from == other.from
+            && self.to == other.to
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Moving {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    // Like a group of one, with the motion at the ray's time applied between
+    // the child and this shape's own transform.
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if !self.bounds.intersects(ray) {
+            return vec![];
+        }
+
+        // Squashed flat at this instant, leaving nothing to hit.
+        let motion = match Transform::try_new(self.motion_at(ray.time())) {
+            Ok(motion) => motion,
+            Err(_) => return vec![],
+        };
+        self.child
+            .intersect(&ray.transform(motion.inverse().clone()))
+            .into_iter()
+            .map(|inter| {
+                inter
+                    .apply_parent_transform(Cow::Owned(motion.clone()))
+                    .apply_parent_transform(Cow::Borrowed(&self.transform))
+            })
+            .collect()
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        unreachable!("moving shapes have no surface; normals come from their child")
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Moving>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+    use crate::utils::fp_equal;
+
+    fn sliding_sphere() -> Moving {
+        Moving::new(
+            Box::new(Sphere::default()),
+            Matrix::identity(),
+            Matrix::translation(4.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn motion_blends_between_end_transforms() {
+        let m = sliding_sphere();
+        assert_eq!(m.motion_at(0.0), Matrix::identity());
+        assert_eq!(m.motion_at(0.5), Matrix::translation(2.0, 0.0, 0.0));
+        assert_eq!(m.motion_at(3.0), Matrix::translation(4.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn moving_shape_is_hit_where_it_is_at_ray_time() {
+        let m = sliding_sphere();
+        let r = Ray::new(Point::new(2.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(m.intersect(&r).is_empty());
+        let xs = m.intersect(&r.set_time(0.5));
+        assert_eq!(xs.len(), 2);
+        assert!(fp_equal(xs[0].t(), 4.0));
+        let n = xs[0].normal_at(Point::new(2.0, 0.0, -1.0));
+        assert_eq!(n, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn shape_flattened_midway_is_missed_then() {
+        let flipping = Moving::new(
+            Box::new(Sphere::default()),
+            Matrix::identity(),
+            Matrix::scaling(1.0, 1.0, -1.0),
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(flipping.intersect(&r).len(), 2);
+        assert!(flipping.intersect(&r.set_time(0.5)).is_empty());
+    }
+
+    #[test]
+    fn small_moving_shape_is_still_hit() {
+        let small = Matrix::scaling(0.02, 0.02, 0.02);
+        let m = Moving::new(Box::new(Sphere::default()), small.clone(), small);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = m.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!((xs[0].t() - 4.98).abs() < 0.001);
+    }
+
+    #[test]
+    fn moving_bounds_cover_the_whole_motion() {
+        let m = sliding_sphere();
+        assert_eq!(m.bounds().min(), Point::new(-1.0, -1.0, -1.0));
+        assert_eq!(m.bounds().max(), Point::new(5.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,294 @@
+use crate::group::Group;
+use crate::mesh::Mesh;
+use crate::point::Point;
+use crate::vector::Vector;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// One corner of a face: a vertex index and, if given, a normal index, both
+// zero-based.
+type Corner = (usize, Option<usize>);
+
+// The contents of a Wavefront OBJ file. Vertices (`v`), normals (`vn`),
+// faces (`f`) and groups (`g`/`o`) are understood; anything else, and any
+// line that fails to parse, is counted in `ignored` and skipped. Faces with
+// more than three corners are split into a triangle fan.
+#[derive(Clone, Debug, Default)]
+pub struct ObjFile {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    groups: Vec<(String, Vec<[Corner; 3]>)>,
+    ignored: usize,
+}
+
+impl ObjFile {
+    pub fn parse(source: &str) -> ObjFile {
+        let mut obj = ObjFile {
+            groups: vec![(String::new(), vec![])],
+            ..ObjFile::default()
+        };
+        for line in source.lines() {
+            if !obj.parse_line(line) {
+                obj.ignored += 1;
+            }
+        }
+        obj.groups.retain(|(_, faces)| !faces.is_empty());
+        obj
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<ObjFile> {
+        Ok(ObjFile::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Vector] {
+        &self.normals
+    }
+
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    // Names of the groups that have faces, in file order. Faces before the
+    // first `g` line belong to the unnamed group "".
+    pub fn group_names(&self) -> Vec<&str> {
+        self.groups.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.groups.iter().map(|(_, faces)| faces.len()).sum()
+    }
+
+    pub fn to_mesh(&self, name: &str) -> Option<Mesh> {
+        self.groups
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, faces)| self.build_mesh(faces))
+    }
+
+    // Every group of the file as a Mesh, gathered under one Group.
+    pub fn to_group(&self) -> Group {
+        self.groups.iter().fold(Group::default(), |g, (_, faces)| {
+            g.add_child(Box::new(self.build_mesh(faces)))
+        })
+    }
+
+    fn parse_line(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(k) => k,
+            None => return true,
+        };
+        let args: Vec<&str> = words.collect();
+        match keyword {
+            "v" => match parse_floats(&args) {
+                Some([x, y, z]) => self.vertices.push(Point::new(x, y, z)),
+                None => return false,
+            },
+            "vn" => match parse_floats(&args) {
+                Some([x, y, z]) => self.normals.push(Vector::new(x, y, z)),
+                None => return false,
+            },
+            "f" => {
+                let corners: Option<Vec<Corner>> =
+                    args.iter().map(|a| self.parse_corner(a)).collect();
+                match corners {
+                    Some(c) if c.len() >= 3 => {
+                        let faces = &mut self.groups.last_mut().unwrap().1;
+                        for i in 1..c.len() - 1 {
+                            faces.push([c[0], c[i], c[i + 1]]);
+                        }
+                    }
+                    _ => return false,
+                }
+            }
+            "g" | "o" => self.groups.push((args.join(" "), vec![])),
+            _ => return keyword.starts_with('#'),
+        }
+        true
+    }
+
+    // Accepts `v`, `v/vt`, `v//vn` and `v/vt/vn`. Indices are one-based,
+    // or negative to count back from the most recent element.
+    fn parse_corner(&self, word: &str) -> Option<Corner> {
+        let mut parts = word.split('/');
+        let vertex = resolve_index(parts.next()?, self.vertices.len())?;
+        let normal = match parts.nth(1) {
+            Some(n) if !n.is_empty() => Some(resolve_index(n, self.normals.len())?),
+            _ => None,
+        };
+        Some((vertex, normal))
+    }
+
+    // Each distinct vertex/normal pair becomes one mesh vertex, so that the
+    // mesh can carry per-vertex normals. Normals are dropped if any corner
+    // in the group lacks one.
+    fn build_mesh(&self, faces: &[[Corner; 3]]) -> Mesh {
+        let smooth = faces.iter().flatten().all(|(_, n)| n.is_some());
+        let mut lookup: HashMap<Corner, u32> = HashMap::new();
+        let mut vertices = vec![];
+        let mut normals = vec![];
+        let mut indices = vec![];
+        for &(v, n) in faces.iter().flatten() {
+            let key = if smooth { (v, n) } else { (v, None) };
+            let index = *lookup.entry(key).or_insert_with(|| {
+                vertices.push(self.vertices[v]);
+                if let Some(n) = key.1 {
+                    normals.push(self.normals[n]);
+                }
+                vertices.len() as u32 - 1
+            });
+            indices.push(index);
+        }
+        let mesh = Mesh::new(vertices, indices);
+        if smooth {
+            mesh.set_normals(normals)
+        } else {
+            mesh
+        }
+    }
+}
+
+fn parse_floats(args: &[&str]) -> Option<[f32; 3]> {
+    if args.len() < 3 {
+        return None;
+    }
+    let mut out = [0.0; 3];
+    for (o, a) in out.iter_mut().zip(args) {
+        *o = a.parse().ok()?;
+    }
+    Some(out)
+}
+
+fn resolve_index(word: &str, len: usize) -> Option<usize> {
+    let i: i64 = word.parse().ok()?;
+    let index = if i < 0 { len as i64 + i } else { i - 1 };
+    if (0..len as i64).contains(&index) {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright\n\
+                         who traveled much faster than light.\n\
+                         She set out one day\n\
+                         in a relative way,\n\
+                         and came back the previous night.\n";
+        let obj = ObjFile::parse(gibberish);
+        assert_eq!(obj.ignored(), 5);
+    }
+
+    #[test]
+    fn vertex_records() {
+        let obj = ObjFile::parse("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n");
+        assert_eq!(obj.vertices().len(), 4);
+        assert_eq!(obj.vertices()[0], Point::new(-1.0, 1.0, 0.0));
+        assert_eq!(obj.vertices()[1], Point::new(-1.0, 0.5, 0.0));
+        assert_eq!(obj.vertices()[3], Point::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let obj = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4\n");
+        let mesh = obj.to_mesh("").unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(
+            mesh.triangle(1).points(),
+            &[
+                Point::new(-1.0, 1.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(1.0, 1.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let obj = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5\n");
+        let mesh = obj.to_mesh("").unwrap();
+        assert_eq!(mesh.triangle_count(), 3);
+        assert_eq!(mesh.triangle(2).points()[2], Point::new(0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn triangles_in_groups() {
+        let obj = ObjFile::parse(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+             g FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\n",
+        );
+        assert_eq!(obj.group_names(), vec!["FirstGroup", "SecondGroup"]);
+        assert_eq!(obj.to_mesh("FirstGroup").unwrap().triangle_count(), 1);
+        assert_eq!(obj.to_mesh("SecondGroup").unwrap().triangle_count(), 1);
+        assert!(obj.to_mesh("ThirdGroup").is_none());
+    }
+
+    #[test]
+    fn converting_obj_file_to_group() {
+        let obj = ObjFile::parse(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+             g FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\n",
+        );
+        let g = obj.to_group();
+        assert_eq!(g.children().len(), 2);
+        let r = Ray::new(Point::new(0.5, 0.75, -1.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).len(), 1);
+    }
+
+    #[test]
+    fn vertex_normal_records() {
+        let obj = ObjFile::parse("vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3\n");
+        assert_eq!(obj.normals()[0], Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(obj.normals()[1], Vector::new(0.707, 0.0, -0.707));
+        assert_eq!(obj.normals()[2], Vector::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let obj = ObjFile::parse(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+             vn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\
+             f 1//3 2//1 3//2\nf 1/0/3 2/102/1 3/14/2\n",
+        );
+        let mesh = obj.to_mesh("").unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.vertices().len(), 3);
+        let t = mesh.triangle(0);
+        assert_eq!(
+            t.normals().unwrap(),
+            &[
+                Vector::new(0.0, 1.0, 0.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                Vector::new(1.0, 0.0, 0.0)
+            ]
+        );
+        assert!(mesh.triangle(1) == t);
+    }
+
+    #[test]
+    fn negative_indices_count_back() {
+        let obj = ObjFile::parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nf -3 -2 -1\n");
+        assert_eq!(obj.triangle_count(), 1);
+        assert_eq!(obj.ignored(), 0);
+    }
+
+    #[test]
+    fn faces_with_bad_indices_are_ignored() {
+        let obj = ObjFile::parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 7\nf 1 2\nf 1 2 3\n");
+        assert_eq!(obj.triangle_count(), 1);
+        assert_eq!(obj.ignored(), 2);
+    }
+}
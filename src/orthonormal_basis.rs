@@ -0,0 +1,88 @@
+use crate::vector::Vector;
+
+// Three perpendicular unit vectors around a surface normal, for turning
+// directions worked out relative to a surface (with the normal as z) into
+// world space and back. The tangent and bitangent point in arbitrary but
+// consistent directions around the normal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrthonormalBasis {
+    tangent: Vector,
+    bitangent: Vector,
+    normal: Vector,
+}
+
+impl OrthonormalBasis {
+    // Duff et al., "Building an Orthonormal Basis, Revisited", which has no
+    // special case where the normal lines up with an axis.
+    pub fn from_normal(normal: Vector) -> OrthonormalBasis {
+        let n = normal.normalize();
+        let sign = 1.0_f32.copysign(n.z);
+        let a = -1.0 / (sign + n.z);
+        let b = n.x * n.y * a;
+        OrthonormalBasis {
+            tangent: Vector::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+            bitangent: Vector::new(b, sign + n.y * n.y * a, -n.y),
+            normal: n,
+        }
+    }
+
+    pub fn tangent(&self) -> Vector {
+        self.tangent
+    }
+
+    pub fn bitangent(&self) -> Vector {
+        self.bitangent
+    }
+
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+
+    pub fn to_world(&self, local: Vector) -> Vector {
+        self.tangent * local.x + self.bitangent * local.y + self.normal * local.z
+    }
+
+    pub fn to_local(&self, world: Vector) -> Vector {
+        Vector::new(
+            world.dot(&self.tangent),
+            world.dot(&self.bitangent),
+            world.dot(&self.normal),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn basis_is_orthonormal_and_right_handed() {
+        let normals = [
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.3, -2.0, 0.7),
+            Vector::new(-0.01, 0.02, -5.0),
+        ];
+        for n in normals.iter() {
+            let b = OrthonormalBasis::from_normal(*n);
+            assert_eq!(b.normal(), n.normalize());
+            for v in [b.tangent(), b.bitangent()].iter() {
+                assert!(fp_equal(v.magnitude(), 1.0));
+                assert!(fp_equal(v.dot(&b.normal()), 0.0));
+            }
+            assert!(fp_equal(b.tangent().dot(&b.bitangent()), 0.0));
+            assert_eq!(b.tangent().cross(&b.bitangent()), b.normal());
+        }
+    }
+
+    #[test]
+    fn converting_between_local_and_world() {
+        let b = OrthonormalBasis::from_normal(Vector::new(1.0, 1.0, 0.0));
+        let up = b.to_world(Vector::new(0.0, 0.0, 2.0));
+        assert_eq!(up, Vector::new(1.0, 1.0, 0.0).normalize() * 2.0);
+        let v = Vector::new(0.2, -0.4, 0.9);
+        assert_eq!(b.to_local(b.to_world(v)), v);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::color::Color;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::shape::ObjectSpace;
+use std::any::Any;
+use std::fmt::Debug;
+
+// A colour that varies over space. Patterns are evaluated in their own
+// space: the world point is taken into the shape's object space and then
+// through the inverse of the pattern's transform.
+#[cfg_attr(feature = "serde", typetag::serde)]
+pub trait Pattern: Debug + Send + Sync {
+    fn transform(&self) -> &Matrix;
+
+    fn pattern_at(&self, point: Point) -> Color;
+
+    fn box_clone(&self) -> Box<dyn Pattern>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool;
+
+    fn pattern_at_shape(&self, object: &dyn ObjectSpace, world_point: Point) -> Color {
+        let object_point = object.world_to_object(world_point);
+        let pattern_point = &self.transform().inverse() * &object_point;
+        self.pattern_at(pattern_point)
+    }
+}
+
+impl Clone for Box<dyn Pattern> {
+    fn clone(&self) -> Box<dyn Pattern> {
+        self.box_clone()
+    }
+}
+
+impl<'a> PartialEq for dyn Pattern + 'a {
+    fn eq(&self, other: &(dyn Pattern + 'a)) -> bool {
+        self.pattern_eq(other)
+    }
+}
+
+fn is_even(x: f32) -> bool {
+    (x.floor() as i64).rem_euclid(2) == 0
+}
+
+// Alternates between a and b every unit along x.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StripePattern {
+    a: Color,
+    b: Color,
+    matrix: Matrix,
+}
+
+impl StripePattern {
+    pub fn new(a: Color, b: Color) -> StripePattern {
+        StripePattern {
+            a,
+            b,
+            matrix: Matrix::identity(),
+        }
+    }
+
+    pub fn a(&self) -> Color {
+        self.a
+    }
+
+    pub fn b(&self) -> Color {
+        self.b
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.matrix = m;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Pattern for StripePattern {
+    fn transform(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    fn box_clone(&self) -> Box<dyn Pattern> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<StripePattern>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        if is_even(point.x) {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+// Blends linearly from a to b across each unit along x.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GradientPattern {
+    a: Color,
+    b: Color,
+    matrix: Matrix,
+}
+
+impl GradientPattern {
+    pub fn new(a: Color, b: Color) -> GradientPattern {
+        GradientPattern {
+            a,
+            b,
+            matrix: Matrix::identity(),
+        }
+    }
+
+    pub fn a(&self) -> Color {
+        self.a
+    }
+
+    pub fn b(&self) -> Color {
+        self.b
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.matrix = m;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Pattern for GradientPattern {
+    fn transform(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    fn box_clone(&self) -> Box<dyn Pattern> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<GradientPattern>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let fraction = point.x - point.x.floor();
+        self.a + (self.b - self.a) * fraction
+    }
+}
+
+// Concentric unit-wide rings around the y axis.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RingPattern {
+    a: Color,
+    b: Color,
+    matrix: Matrix,
+}
+
+impl RingPattern {
+    pub fn new(a: Color, b: Color) -> RingPattern {
+        RingPattern {
+            a,
+            b,
+            matrix: Matrix::identity(),
+        }
+    }
+
+    pub fn a(&self) -> Color {
+        self.a
+    }
+
+    pub fn b(&self) -> Color {
+        self.b
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.matrix = m;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Pattern for RingPattern {
+    fn transform(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    fn box_clone(&self) -> Box<dyn Pattern> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<RingPattern>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        if is_even((point.x * point.x + point.z * point.z).sqrt()) {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+// Unit cubes alternating in all three dimensions.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckerPattern {
+    a: Color,
+    b: Color,
+    matrix: Matrix,
+}
+
+impl CheckerPattern {
+    pub fn new(a: Color, b: Color) -> CheckerPattern {
+        CheckerPattern {
+            a,
+            b,
+            matrix: Matrix::identity(),
+        }
+    }
+
+    pub fn a(&self) -> Color {
+        self.a
+    }
+
+    pub fn b(&self) -> Color {
+        self.b
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.matrix = m;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Pattern for CheckerPattern {
+    fn transform(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    fn box_clone(&self) -> Box<dyn Pattern> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<CheckerPattern>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        if is_even(point.x.floor() + point.y.floor() + point.z.floor()) {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct TestPattern {
+        matrix: Matrix,
+    }
+
+    #[cfg_attr(feature = "serde", typetag::serde)]
+    impl Pattern for TestPattern {
+        fn transform(&self) -> &Matrix {
+            &self.matrix
+        }
+
+        fn box_clone(&self) -> Box<dyn Pattern> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+            other
+                .as_any()
+                .downcast_ref::<TestPattern>()
+                .is_some_and(|o| self == o)
+        }
+
+        fn pattern_at(&self, point: Point) -> Color {
+            Color::new(point.x, point.y, point.z)
+        }
+    }
+
+    #[test]
+    fn stripe_pattern_is_constant_in_y_and_z() {
+        let p = StripePattern::new(white(), black());
+        assert_eq!(p.pattern_at(Point::new(0.0, 1.0, 0.0)), white());
+        assert_eq!(p.pattern_at(Point::new(0.0, 2.0, 0.0)), white());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 1.0)), white());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 2.0)), white());
+    }
+
+    #[test]
+    fn stripe_pattern_alternates_in_x() {
+        let p = StripePattern::new(white(), black());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(p.pattern_at(Point::new(0.9, 0.0, 0.0)), white());
+        assert_eq!(p.pattern_at(Point::new(1.0, 0.0, 0.0)), black());
+        assert_eq!(p.pattern_at(Point::new(-0.1, 0.0, 0.0)), black());
+        assert_eq!(p.pattern_at(Point::new(-1.0, 0.0, 0.0)), black());
+        assert_eq!(p.pattern_at(Point::new(-1.1, 0.0, 0.0)), white());
+    }
+
+    #[test]
+    fn pattern_with_object_transformation() {
+        let shape = Sphere::default().set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let p = TestPattern {
+            matrix: Matrix::identity(),
+        };
+        let c = p.pattern_at_shape(&shape, Point::new(2.0, 3.0, 4.0));
+        assert_eq!(c, Color::new(1.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn pattern_with_pattern_transformation() {
+        let shape = Sphere::default();
+        let p = TestPattern {
+            matrix: Matrix::scaling(2.0, 2.0, 2.0),
+        };
+        let c = p.pattern_at_shape(&shape, Point::new(2.0, 3.0, 4.0));
+        assert_eq!(c, Color::new(1.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn pattern_with_object_and_pattern_transformation() {
+        let shape = Sphere::default().set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let p = TestPattern {
+            matrix: Matrix::translation(0.5, 1.0, 1.5),
+        };
+        let c = p.pattern_at_shape(&shape, Point::new(2.5, 3.0, 3.5));
+        assert_eq!(c, Color::new(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn gradient_linearly_interpolates_between_colors() {
+        let p = GradientPattern::new(white(), black());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(
+            p.pattern_at(Point::new(0.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(
+            p.pattern_at(Point::new(0.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            p.pattern_at(Point::new(0.75, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn ring_extends_in_both_x_and_z() {
+        let p = RingPattern::new(white(), black());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(p.pattern_at(Point::new(1.0, 0.0, 0.0)), black());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 1.0)), black());
+        assert_eq!(p.pattern_at(Point::new(0.708, 0.0, 0.708)), black());
+    }
+
+    #[test]
+    fn checkers_repeat_in_every_dimension() {
+        let p = CheckerPattern::new(white(), black());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(p.pattern_at(Point::new(0.99, 0.0, 0.0)), white());
+        assert_eq!(p.pattern_at(Point::new(1.01, 0.0, 0.0)), black());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.99, 0.0)), white());
+        assert_eq!(p.pattern_at(Point::new(0.0, 1.01, 0.0)), black());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.99)), white());
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 1.01)), black());
+    }
+
+    #[test]
+    fn boxed_patterns_compare_by_value() {
+        let a: Box<dyn Pattern> = Box::new(StripePattern::new(white(), black()));
+        let b: Box<dyn Pattern> = Box::new(StripePattern::new(black(), white()));
+        let c: Box<dyn Pattern> = Box::new(RingPattern::new(white(), black()));
+        assert!(a == a.clone());
+        assert!(a != b);
+        assert!(a != c);
+    }
+}
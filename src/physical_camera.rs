@@ -0,0 +1,155 @@
+// Real-world camera settings. Lengths on the camera body are in millimetres,
+// while the focus distance is in world units, which are taken to be metres.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalCamera {
+    pub sensor_width: f32,
+    pub focal_length: f32,
+    pub f_stop: f32,
+    pub iso: f32,
+    pub shutter: f32,
+    pub focus_distance: f32,
+}
+
+impl PhysicalCamera {
+    pub fn new(
+        sensor_width: f32,
+        focal_length: f32,
+        f_stop: f32,
+        iso: f32,
+        shutter: f32,
+        focus_distance: f32,
+    ) -> PhysicalCamera {
+        PhysicalCamera {
+            sensor_width,
+            focal_length,
+            f_stop,
+            iso,
+            shutter,
+            focus_distance,
+        }
+    }
+
+    pub fn set_sensor_width(mut self, new: f32) -> Self {
+        self.sensor_width = new;
+        self
+    }
+
+    pub fn set_focal_length(mut self, new: f32) -> Self {
+        self.focal_length = new;
+        self
+    }
+
+    pub fn set_f_stop(mut self, new: f32) -> Self {
+        self.f_stop = new;
+        self
+    }
+
+    pub fn set_iso(mut self, new: f32) -> Self {
+        self.iso = new;
+        self
+    }
+
+    pub fn set_shutter(mut self, new: f32) -> Self {
+        self.shutter = new;
+        self
+    }
+
+    pub fn set_focus_distance(mut self, new: f32) -> Self {
+        self.focus_distance = new;
+        self
+    }
+
+    // The sensor width is matched against the longer edge of the image, the
+    // same edge Camera::new applies its field of view to.
+    pub fn field_of_view(&self) -> f32 {
+        2.0 * (self.sensor_width / (2.0 * self.focal_length)).atan()
+    }
+
+    pub fn aperture_radius(&self) -> f32 {
+        self.focal_length / self.f_stop / 2.0 / 1000.0
+    }
+
+    pub fn ev100(&self) -> f32 {
+        (self.f_stop.powi(2) / self.shutter * 100.0 / self.iso).log2()
+    }
+
+    pub fn exposure(&self) -> f32 {
+        exposure_from_ev100(self.ev100())
+    }
+}
+
+// Scale applied to scene luminance for a camera exposed at `ev100`, using the
+// saturation-based sensitivity model (78% of sensor saturation at ISO 100).
+pub fn exposure_from_ev100(ev100: f32) -> f32 {
+    1.0 / (1.2 * 2.0_f32.powf(ev100))
+}
+
+impl Default for PhysicalCamera {
+    fn default() -> PhysicalCamera {
+        PhysicalCamera {
+            sensor_width: 36.0,
+            focal_length: 50.0,
+            f_stop: 8.0,
+            iso: 100.0,
+            shutter: 1.0 / 125.0,
+            focus_distance: 5.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn default_physical_camera() {
+        let p = PhysicalCamera::default();
+        assert!(fp_equal(p.sensor_width, 36.0));
+        assert!(fp_equal(p.focal_length, 50.0));
+        assert!(fp_equal(p.f_stop, 8.0));
+        assert!(fp_equal(p.iso, 100.0));
+        assert!(fp_equal(p.shutter, 1.0 / 125.0));
+    }
+
+    #[test]
+    fn field_of_view_from_sensor_and_focal_length() {
+        let p = PhysicalCamera::default()
+            .set_sensor_width(36.0)
+            .set_focal_length(18.0);
+        assert!(fp_equal(p.field_of_view(), std::f32::consts::PI / 2.0));
+    }
+
+    #[test]
+    fn longer_focal_length_narrows_field_of_view() {
+        let wide = PhysicalCamera::default().set_focal_length(24.0);
+        let tele = PhysicalCamera::default().set_focal_length(200.0);
+        assert!(tele.field_of_view() < wide.field_of_view());
+    }
+
+    #[test]
+    fn aperture_radius_from_f_stop() {
+        let p = PhysicalCamera::default()
+            .set_focal_length(50.0)
+            .set_f_stop(2.0);
+        assert!(fp_equal(p.aperture_radius(), 0.0125));
+    }
+
+    #[test]
+    fn exposure_value_at_unit_settings() {
+        let p = PhysicalCamera::default()
+            .set_f_stop(1.0)
+            .set_shutter(1.0)
+            .set_iso(100.0);
+        assert!(fp_equal(p.ev100(), 0.0));
+        assert!(fp_equal(p.exposure(), 1.0 / 1.2));
+    }
+
+    #[test]
+    fn doubling_iso_doubles_exposure() {
+        let p = PhysicalCamera::default();
+        let p2 = p.clone().set_iso(p.iso * 2.0);
+        assert!(fp_equal(p2.exposure() / p.exposure(), 2.0));
+    }
+}
@@ -1,7 +1,8 @@
-use crate::utils::fp_equal;
+use crate::utils::{ApproxEq, Tolerance};
 use crate::vector::Vector;
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -12,16 +13,24 @@ impl Point {
     pub fn new(x: f32, y: f32, z: f32) -> Point {
         Point { x, y, z }
     }
+
+    // Self at t = 0 and other at t = 1, along the line between.
+    pub fn lerp(&self, other: &Point, t: f32) -> Point {
+        *self + (*other - *self) * t
+    }
+}
+
+impl ApproxEq for Point {
+    fn approx_eq_with(&self, other: &Self, tolerance: Tolerance) -> bool {
+        [(self.x, other.x), (self.y, other.y), (self.z, other.z)]
+            .iter()
+            .all(|&(i, j)| tolerance.equal(i, j))
+    }
 }
 
 impl PartialEq for Point {
     fn eq(&self, other: &Self) -> bool {
-        for (i, j) in [(self.x, other.x), (self.y, other.y), (self.z, other.z)] {
-            if !fp_equal(i, j) {
-                return false;
-            }
-        }
-        true
+        self.approx_eq(other)
     }
 }
 
@@ -53,6 +62,15 @@ impl std::ops::Add<Vector> for Point {
     }
 }
 
+// Scales a point's distance from the origin.
+impl std::ops::Mul<Point> for f32 {
+    type Output = Point;
+
+    fn mul(self, point: Point) -> Point {
+        Point::new(self * point.x, self * point.y, self * point.z)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,4 +88,31 @@ mod tests {
         let v = Vector::new(5.0, 6.0, 7.0);
         assert!(p - v == Point::new(-2.0, -4.0, -6.0));
     }
+
+    #[test]
+    fn comparing_points_at_large_and_small_scales() {
+        // Far from the origin, rounding differences outgrow a fixed epsilon.
+        let far = Point::new(50_000.0, 0.0, 0.0);
+        let rounded = Point::new(50_000.004, 0.0, 0.0);
+        assert!(far == rounded);
+        assert!(!far.approx_eq_with(&rounded, Tolerance::absolute(0.00001)));
+        let near = Point::new(0.001, 0.0, 0.0);
+        assert!(near.approx_eq_with(&Point::new(0.0011, 0.0, 0.0), Tolerance::absolute(0.001)));
+        assert!(near != Point::new(0.0011, 0.0, 0.0));
+    }
+
+    #[test]
+    fn interpolating_points() {
+        let a = Point::new(1.0, 2.0, 3.0);
+        let b = Point::new(3.0, -2.0, 3.0);
+        assert!(a.lerp(&b, 0.0) == a);
+        assert!(a.lerp(&b, 0.25) == Point::new(1.5, 1.0, 3.0));
+        assert!(a.lerp(&b, 1.0) == b);
+    }
+
+    #[test]
+    fn multiplying_a_point_by_a_scalar() {
+        let p = Point::new(1.0, -2.0, 3.0);
+        assert!(2.0 * p == Point::new(2.0, -4.0, 6.0));
+    }
 }
@@ -1,12 +1,18 @@
 use crate::color::Color;
-use crate::material::Material;
+use crate::light::{Attenuation, Light};
 use crate::point::Point;
-use crate::vector::Vector;
+use std::any::Any;
 
-#[derive(PartialEq, Clone)]
+// Luminous efficacy of monochromatic 555nm light, the most lumens a watt of
+// radiant power can produce.
+const LUMENS_PER_WATT: f32 = 683.0;
+
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointLight {
     position: Point,
     intensity: Color,
+    attenuation: Attenuation,
 }
 
 impl PointLight {
@@ -14,49 +20,77 @@ impl PointLight {
         PointLight {
             position,
             intensity,
+            attenuation: Attenuation::None,
         }
     }
 
-    pub fn position(&self) -> Point {
-        self.position
+    // A light emitting `lumens` evenly in all directions. The intensity is in
+    // candela and falls off with the square of the distance, so it is meant
+    // to be paired with a physical camera exposure.
+    pub fn from_lumens(position: Point, color: Color, lumens: f32) -> PointLight {
+        PointLight {
+            position,
+            intensity: color * (lumens / (4.0 * std::f32::consts::PI)),
+            attenuation: Attenuation::InverseSquare { radius: 0.0 },
+        }
+    }
+
+    pub fn from_watts(position: Point, color: Color, watts: f32) -> PointLight {
+        PointLight::from_lumens(position, color, watts * LUMENS_PER_WATT)
+    }
+
+    pub fn set_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn inverse_square(&self) -> bool {
+        matches!(self.attenuation, Attenuation::InverseSquare { .. })
     }
 
     pub fn intensity(&self) -> Color {
         self.intensity
     }
+}
 
-    pub fn lighting(&self, mat: &Material, pos: Point, eye: Vector, normal: Vector, in_shadow: bool) -> Color {
-        let effective_color = mat.color * self.intensity;
-        let lightv = (self.position - pos).normalize();
-        let ambient = effective_color * mat.ambient;
-        let light_dot_normal = lightv.dot(&normal);
-
-        let (diffuse, specular) = if light_dot_normal < 0.0 {
-            let black = Color::new(0.0, 0.0, 0.0);
-            (black, black)
-        } else {
-            let diff = effective_color * mat.diffuse * light_dot_normal;
-            let reflectv = (-lightv).reflect(&normal);
-            let reflect_dot_eye = reflectv.dot(&eye);
-            if reflect_dot_eye <= 0.0 {
-                (diff, Color::new(0.0, 0.0, 0.0))
-            } else {
-                let factor = reflect_dot_eye.powf(mat.shininess);
-                let specular = self.intensity * mat.specular * factor;
-                (diff, specular)
-            }
-        };
-        if in_shadow {
-            ambient
-        } else {
-            ambient + diffuse + specular
-        }
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Light for PointLight {
+    fn position(&self) -> Point {
+        self.position
+    }
+
+    fn intensity_at(&self, pos: Point) -> Color {
+        let distance = (self.position - pos).magnitude();
+        self.intensity * self.attenuation.factor(distance)
+    }
+
+    fn box_clone(&self) -> Box<dyn Light> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn light_eq(&self, other: &dyn Light) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<PointLight>()
+            .is_some_and(|o| self == o)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::material::Material;
+    use crate::pattern::StripePattern;
+    use crate::sphere::Sphere;
+    use crate::vector::Vector;
 
     #[test]
     fn point_light_has_position_and_intensity() {
@@ -75,7 +109,7 @@ mod tests {
         let eye = Vector::new(0.0, 0.0, -1.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = light.lighting(&m, position, eye, normal, false);
+        let result = light.lighting(&m, &Sphere::default(), position, eye, normal, false);
         assert!(result == Color::new(1.9, 1.9, 1.9));
     }
 
@@ -88,7 +122,7 @@ mod tests {
         let eye = Vector::new(0.0, x, -x);
         let normal = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = light.lighting(&m, position, eye, normal, false);
+        let result = light.lighting(&m, &Sphere::default(), position, eye, normal, false);
         assert!(result == Color::new(1.0, 1.0, 1.0));
     }
 
@@ -100,7 +134,7 @@ mod tests {
         let eye = Vector::new(0.0, 0.0, -1.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = light.lighting(&m, position, eye, normal, false);
+        let result = light.lighting(&m, &Sphere::default(), position, eye, normal, false);
         assert!(result == Color::new(0.7364, 0.7364, 0.7364));
     }
 
@@ -113,7 +147,7 @@ mod tests {
         let eye = Vector::new(0.0, -x, -x);
         let normal = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = light.lighting(&m, position, eye, normal, false);
+        let result = light.lighting(&m, &Sphere::default(), position, eye, normal, false);
         assert!(result == Color::new(1.63639, 1.63639, 1.63639));
     }
 
@@ -125,7 +159,7 @@ mod tests {
         let eye = Vector::new(0.0, 0.0, -1.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
-        let result = light.lighting(&m, position, eye, normal, false);
+        let result = light.lighting(&m, &Sphere::default(), position, eye, normal, false);
         assert!(result == Color::new(0.1, 0.1, 0.1));
     }
 
@@ -138,8 +172,84 @@ mod tests {
         let normal = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let in_shadow = true;
-        let result = light.lighting(&m, position, eye, normal, in_shadow);
+        let result = light.lighting(&m, &Sphere::default(), position, eye, normal, in_shadow);
         assert!(result == Color::new(0.1, 0.1, 0.1));
     }
 
+    #[test]
+    fn light_from_lumens_has_candela_intensity() {
+        let lumens = 4.0 * std::f32::consts::PI * 100.0;
+        let light =
+            PointLight::from_lumens(Point::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), lumens);
+        assert!(light.inverse_square());
+        assert!(light.intensity() == Color::new(100.0, 100.0, 100.0));
+    }
+
+    #[test]
+    fn light_from_watts_uses_luminous_efficacy() {
+        let position = Point::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let a = PointLight::from_watts(position, white, 2.0);
+        let b = PointLight::from_lumens(position, white, 2.0 * 683.0);
+        assert!(a.intensity() == b.intensity());
+    }
+
+    #[test]
+    fn physical_light_falls_off_with_square_of_distance() {
+        let light = PointLight::from_lumens(
+            Point::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            4.0 * std::f32::consts::PI * 8.0,
+        );
+        assert!(light.intensity_at(Point::new(0.0, 2.0, 0.0)) == Color::new(2.0, 2.0, 2.0));
+        assert!(light.intensity_at(Point::new(0.0, 4.0, 0.0)) == Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn plain_light_does_not_fall_off() {
+        let light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        assert!(light.intensity_at(Point::new(0.0, 100.0, 0.0)) == Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn linear_attenuation_dims_distant_points() {
+        let light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0))
+            .set_attenuation(Attenuation::Linear { radius: 8.0 });
+        assert!(light.intensity_at(Point::new(0.0, 2.0, 0.0)) == Color::new(0.75, 0.75, 0.75));
+        assert!(light.intensity_at(Point::new(0.0, 9.0, 0.0)) == Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn lighting_with_physical_light() {
+        let m = Material::default();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::from_lumens(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+            4.0 * std::f32::consts::PI * 100.0,
+        );
+        let result = light.lighting(&m, &Sphere::default(), position, eye, normal, false);
+        assert!(result == Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn lighting_with_pattern_applied() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let m = Material::default()
+            .set_pattern(Box::new(StripePattern::new(white, black)))
+            .set_ambient(1.0)
+            .set_diffuse(0.0)
+            .set_specular(0.0);
+        let object = Sphere::default();
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), white);
+        let c1 = light.lighting(&m, &object, Point::new(0.9, 0.0, 0.0), eye, normal, false);
+        let c2 = light.lighting(&m, &object, Point::new(1.1, 0.0, 0.0), eye, normal, false);
+        assert!(c1 == white);
+        assert!(c2 == black);
+    }
 }
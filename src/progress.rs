@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// How far a render has got, reported after each finished tile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+    pub pixels_done: u64,
+    pub pixels_total: u64,
+}
+
+impl Progress {
+    // From 0 when nothing is done to 1 when everything is.
+    pub fn fraction(&self) -> f32 {
+        if self.pixels_total == 0 {
+            1.0
+        } else {
+            self.pixels_done as f32 / self.pixels_total as f32
+        }
+    }
+}
+
+// Lets another thread, or a progress callback, stop a render early. Clones
+// share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelling_a_clone_cancels_the_original() {
+        let token = CancelToken::new();
+        assert!(!token.is_cancelled());
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn progress_fraction() {
+        let p = Progress {
+            tiles_done: 1,
+            tiles_total: 4,
+            pixels_done: 25,
+            pixels_total: 100,
+        };
+        assert_eq!(p.fraction(), 0.25);
+    }
+}
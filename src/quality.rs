@@ -0,0 +1,123 @@
+// A named bundle of render settings trading speed for quality. Profiles can
+// be saved as YAML and read back, and scene files can name one. Every light
+// casts a single hard shadow ray, so there's no shadow sample count to set.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    any(feature = "serde", feature = "yaml"),
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct Quality {
+    pub name: String,
+    pub samples: u32,
+    pub max_recursion: u32,
+    pub resolution_scale: f32,
+}
+
+impl Quality {
+    pub fn new(name: &str, samples: u32, max_recursion: u32, resolution_scale: f32) -> Quality {
+        Quality {
+            name: name.to_string(),
+            samples,
+            max_recursion,
+            resolution_scale,
+        }
+    }
+
+    pub fn draft() -> Quality {
+        Quality::new("draft", 1, 1, 0.5)
+    }
+
+    pub fn preview() -> Quality {
+        Quality::new("preview", 4, 3, 1.0)
+    }
+
+    pub fn final_quality() -> Quality {
+        Quality::new("final", 16, 5, 1.0)
+    }
+
+    pub fn preset(name: &str) -> Option<Quality> {
+        match name {
+            "draft" => Some(Quality::draft()),
+            "preview" => Some(Quality::preview()),
+            "final" => Some(Quality::final_quality()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Quality {
+    fn default() -> Quality {
+        Quality::preview()
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl Quality {
+    // A profile in YAML: a preset's name, or a mapping of any of the fields,
+    // with the rest taken from the preview settings.
+    pub fn from_yaml(source: &str) -> Result<Quality, String> {
+        Quality::from_value(serde_yaml_ng::from_str(source).map_err(|e| e.to_string())?)
+    }
+
+    pub(crate) fn from_value(value: serde_yaml_ng::Value) -> Result<Quality, String> {
+        if let Some(name) = value.as_str() {
+            return Quality::preset(name).ok_or_else(|| format!("unknown quality {}", name));
+        }
+        serde_yaml_ng::from_value(value).map_err(|e| e.to_string())
+    }
+
+    pub fn to_yaml(&self) -> String {
+        // Every field is a plain number or string.
+        serde_yaml_ng::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_by_name() {
+        assert_eq!(Quality::preset("draft"), Some(Quality::draft()));
+        assert_eq!(Quality::preset("preview"), Some(Quality::preview()));
+        assert_eq!(Quality::preset("final"), Some(Quality::final_quality()));
+        assert_eq!(Quality::preset("ultra"), None);
+    }
+
+    #[test]
+    fn presets_increase_in_quality() {
+        let draft = Quality::draft();
+        let preview = Quality::preview();
+        let fin = Quality::final_quality();
+        assert!(draft.samples < preview.samples && preview.samples < fin.samples);
+        assert!(draft.max_recursion < fin.max_recursion);
+        assert!(draft.resolution_scale < fin.resolution_scale);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn custom_profile_round_trip() {
+        let q = Quality::new("overnight", 64, 8, 2.0);
+        let reloaded = Quality::from_yaml(&q.to_yaml()).unwrap();
+        assert_eq!(reloaded, q);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn profile_defaults_missing_fields() {
+        let q = Quality::from_yaml("# quick look\nname: quick\nsamples: 2\n").unwrap();
+        assert_eq!(q.name, "quick");
+        assert_eq!(q.samples, 2);
+        assert_eq!(q.max_recursion, Quality::preview().max_recursion);
+        assert_eq!(Quality::from_yaml("draft").unwrap(), Quality::draft());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn invalid_profiles_are_rejected() {
+        assert!(Quality::from_yaml("samples: many").is_err());
+        assert!(Quality::from_yaml("ultra").is_err());
+        assert!(Quality::from_yaml("colour: red").is_err());
+    }
+}
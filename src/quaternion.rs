@@ -0,0 +1,246 @@
+use crate::matrix::Matrix;
+use crate::utils::{ApproxEq, Tolerance};
+use crate::vector::Vector;
+
+// A rotation as w + xi + yj + zk. Unit quaternions blend smoothly with
+// slerp, which makes them the thing to interpolate between orientations;
+// blending rotation matrices element by element shrinks whatever they turn.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quaternion {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Quaternion {
+    pub fn new(w: f32, x: f32, y: f32, z: f32) -> Quaternion {
+        Quaternion { w, x, y, z }
+    }
+
+    // No rotation.
+    pub fn identity() -> Quaternion {
+        Quaternion::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    // Turns by angle radians about axis, which needn't be normalized, in the
+    // same direction as Matrix::rotation_x and friends.
+    pub fn from_axis_angle(axis: Vector, angle: f32) -> Quaternion {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quaternion::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    // The rotation in the top-left 3 x 3 of m, which must be a pure rotation:
+    // no scaling, shearing or reflection.
+    pub fn from_matrix(m: &Matrix) -> Quaternion {
+        let e = |r, c| m.get(r, c);
+        let trace = e(0, 0) + e(1, 1) + e(2, 2);
+        // Divide by the largest of the four possible terms, to keep precision.
+        let q = if trace > 0.0 {
+            let s = 2.0 * (1.0 + trace).sqrt();
+            Quaternion::new(
+                s / 4.0,
+                (e(2, 1) - e(1, 2)) / s,
+                (e(0, 2) - e(2, 0)) / s,
+                (e(1, 0) - e(0, 1)) / s,
+            )
+        } else if e(0, 0) > e(1, 1) && e(0, 0) > e(2, 2) {
+            let s = 2.0 * (1.0 + e(0, 0) - e(1, 1) - e(2, 2)).sqrt();
+            Quaternion::new(
+                (e(2, 1) - e(1, 2)) / s,
+                s / 4.0,
+                (e(0, 1) + e(1, 0)) / s,
+                (e(0, 2) + e(2, 0)) / s,
+            )
+        } else if e(1, 1) > e(2, 2) {
+            let s = 2.0 * (1.0 + e(1, 1) - e(0, 0) - e(2, 2)).sqrt();
+            Quaternion::new(
+                (e(0, 2) - e(2, 0)) / s,
+                (e(0, 1) + e(1, 0)) / s,
+                s / 4.0,
+                (e(1, 2) + e(2, 1)) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + e(2, 2) - e(0, 0) - e(1, 1)).sqrt();
+            Quaternion::new(
+                (e(1, 0) - e(0, 1)) / s,
+                (e(0, 2) + e(2, 0)) / s,
+                (e(1, 2) + e(2, 1)) / s,
+                s / 4.0,
+            )
+        };
+        q.normalize()
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        let Quaternion { w, x, y, z } = self.normalize();
+        Matrix::new_filled(&[
+            &[
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            &[
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            &[
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            &[0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f32 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        let mag = self.magnitude();
+        Quaternion::new(self.w / mag, self.x / mag, self.y / mag, self.z / mag)
+    }
+
+    // The opposite rotation, for unit quaternions.
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn rotate(&self, v: Vector) -> Vector {
+        let q = self.normalize();
+        let axis = Vector::new(q.x, q.y, q.z);
+        let t = axis.cross(&v) * 2.0;
+        v + t * q.w + axis.cross(&t)
+    }
+
+    // Turns at a steady rate from self at t = 0 to other at t = 1, the short
+    // way round.
+    pub fn slerp(&self, other: &Quaternion, t: f32) -> Quaternion {
+        let (a, mut b) = (self.normalize(), other.normalize());
+        let mut cos = a.dot(&b);
+        // q and -q are the same rotation; pick whichever is nearer.
+        if cos < 0.0 {
+            b = Quaternion::new(-b.w, -b.x, -b.y, -b.z);
+            cos = -cos;
+        }
+        // Nearly the same, where sin(theta) is too small to divide by.
+        let (wa, wb) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Quaternion::new(
+            a.w * wa + b.w * wb,
+            a.x * wa + b.x * wb,
+            a.y * wa + b.y * wb,
+            a.z * wa + b.z * wb,
+        )
+        .normalize()
+    }
+}
+
+impl ApproxEq for Quaternion {
+    fn approx_eq_with(&self, other: &Self, tolerance: Tolerance) -> bool {
+        tolerance.equal(self.w, other.w)
+            && tolerance.equal(self.x, other.x)
+            && tolerance.equal(self.y, other.y)
+            && tolerance.equal(self.z, other.z)
+    }
+}
+
+impl PartialEq for Quaternion {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other)
+    }
+}
+
+// The rotation other followed by self.
+impl std::ops::Mul for Quaternion {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Quaternion::new(
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    #[test]
+    fn axis_angle_matches_rotation_matrices() {
+        let x = Quaternion::from_axis_angle(Vector::new(2.0, 0.0, 0.0), FRAC_PI_4);
+        assert_eq!(x.to_matrix(), Matrix::rotation_x(FRAC_PI_4));
+        let y = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), 1.2);
+        assert_eq!(y.to_matrix(), Matrix::rotation_y(1.2));
+        let z = Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), -2.5);
+        assert_eq!(z.to_matrix(), Matrix::rotation_z(-2.5));
+    }
+
+    #[test]
+    fn converting_from_a_matrix_and_back() {
+        let rotations = [
+            Matrix::identity(),
+            Matrix::rotation_x(PI),
+            Matrix::rotation_y(PI),
+            Matrix::rotation_z(3.0),
+            Matrix::rotation_x(0.7) * Matrix::rotation_y(-1.9) * Matrix::rotation_z(2.2),
+        ];
+        for m in rotations.iter() {
+            assert_eq!(&Quaternion::from_matrix(m).to_matrix(), m);
+        }
+    }
+
+    #[test]
+    fn rotating_a_vector() {
+        let q = Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), FRAC_PI_2);
+        assert_eq!(
+            q.rotate(Vector::new(1.0, 0.0, 0.0)),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+        let v = Vector::new(0.3, -1.0, 2.0);
+        assert_eq!(q.conjugate().rotate(q.rotate(v)), v);
+    }
+
+    #[test]
+    fn multiplying_composes_rotations() {
+        let a = Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), 0.4);
+        let b = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), 1.1);
+        let p = Point::new(1.0, 2.0, 3.0);
+        let m = Matrix::rotation_x(0.4) * Matrix::rotation_y(1.1);
+        assert_eq!((a * b).to_matrix() * p, m * p);
+    }
+
+    #[test]
+    fn slerp_turns_at_a_steady_rate() {
+        let a = Quaternion::identity();
+        let b = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), FRAC_PI_2);
+        assert_eq!(a.slerp(&b, 0.0), a);
+        assert_eq!(a.slerp(&b, 1.0), b);
+        let quarter = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), FRAC_PI_4 / 2.0);
+        assert_eq!(a.slerp(&b, 0.25), quarter);
+        // The long way round is the same rotation as -b; slerp goes the short way.
+        let minus_b = Quaternion::new(-b.w, -b.x, -b.y, -b.z);
+        assert_eq!(a.slerp(&minus_b, 0.25), quarter);
+    }
+}
@@ -1,18 +1,49 @@
 use crate::matrix::Matrix;
 use crate::point::Point;
-use crate::sphere::Sphere;
+use crate::shape::{ObjectSpace, Shape};
+use crate::transform::Transform;
 use crate::utils::fp_equal;
 use crate::vector::Vector;
+use std::borrow::Cow;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct Ray {
     origin: Point,
     direction: Vector,
+    time: f32,
+    seed: u64,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vector) -> Ray {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+            seed: 0,
+        }
+    }
+
+    // The moment the ray is cast at, which decides where moving shapes are.
+    pub fn set_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    // Seeds the random choices made in shading wherever the ray lands, such
+    // as the environment's diffuse samples. The camera draws it from the
+    // pixel's stream, so each sample of each pixel gets its own.
+    pub fn set_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
     pub fn origin(&self) -> Point {
@@ -27,62 +58,134 @@ impl Ray {
         self.origin + self.direction * t
     }
 
-    pub fn intersect(&self, s: &Sphere) -> Vec<Intersection> {
-        let ray = self.transform(s.transform().inverse());
-        let sphere_to_ray = ray.origin - Point::new(0.0, 0.0, 0.0);
-        let a = ray.direction().dot(&ray.direction());
-        let b = 2.0 * ray.direction().dot(&sphere_to_ray);
-        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
-        let discriminant = b.powi(2) - 4.0 * a * c;
-
-        if discriminant < 0.0 {
-            return vec![];
-        }
-
-        let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
-        let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
-
-        let s1 = s.clone();
-        let s2 = s.clone();
-        vec![Intersection::new(t1, s1), Intersection::new(t2, s2)]
+    pub fn intersect<'a>(&self, s: &'a dyn Shape) -> Vec<Intersection<'a>> {
+        s.intersect(self)
     }
 
     pub fn transform(&self, m: Matrix) -> Ray {
         let origin = &m * &self.origin();
         let direction = &m * &self.direction();
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            time: self.time,
+            seed: self.seed,
+        }
     }
 }
 
+// Intersections borrow the shape they hit, which may sit inside groups,
+// moving shapes or a mesh. Rather than copying the shape with the
+// transforms around it applied, a hit keeps those transforms, innermost
+// first, and for meshes the face it's on. Groups lend their transforms, so
+// the inverses they already hold are reused for every hit.
 #[derive(Clone, Debug)]
-pub struct Intersection {
+pub struct Intersection<'a> {
     t: f32,
-    object: Sphere,
+    object: &'a dyn Shape,
+    parents: Vec<Cow<'a, Transform>>,
+    face: Option<usize>,
+    uv: Option<(f32, f32)>,
 }
 
-impl Intersection {
-    pub fn new(t: f32, object: Sphere) -> Intersection {
-        Intersection { t, object }
+impl<'a> Intersection<'a> {
+    pub fn new(t: f32, object: &'a dyn Shape) -> Intersection<'a> {
+        Intersection {
+            t,
+            object,
+            parents: vec![],
+            face: None,
+            uv: None,
+        }
+    }
+
+    // Which face of a mesh the hit is on.
+    pub fn set_face(mut self, face: usize) -> Self {
+        self.face = Some(face);
+        self
+    }
+
+    // Where on the surface the hit is, for shapes that know: the barycentric
+    // weights of a triangle's second and third corners.
+    pub fn set_uv(mut self, u: f32, v: f32) -> Self {
+        self.uv = Some((u, v));
+        self
+    }
+
+    // Places the hit in the space of a group or other shape holding the
+    // object, on top of any transforms already applied.
+    pub fn apply_parent_transform(mut self, parent: Cow<'a, Transform>) -> Self {
+        self.parents.push(parent);
+        self
     }
 
     pub fn t(&self) -> f32 {
         self.t
     }
 
-    pub fn object(&self) -> &Sphere {
-        &self.object
+    pub fn uv(&self) -> Option<(f32, f32)> {
+        self.uv
+    }
+
+    pub fn face(&self) -> Option<usize> {
+        self.face
+    }
+
+    pub fn object(&self) -> &'a dyn Shape {
+        self.object
+    }
+
+    // The transforms around the object, innermost first.
+    pub fn parents(&self) -> impl Iterator<Item = &Transform> {
+        self.parents.iter().map(|parent| parent.as_ref())
+    }
+
+    pub fn world_to_object(&self, point: Point) -> Point {
+        let point = self
+            .parents
+            .iter()
+            .rev()
+            .fold(point, |point, parent| parent.inverse() * &point);
+        self.object.world_to_object(point)
+    }
+
+    pub fn normal_to_world(&self, normal: Vector) -> Vector {
+        let normal = self.object.normal_to_world(normal);
+        if self.parents.is_empty() {
+            return normal;
+        }
+        self.parents
+            .iter()
+            .fold(normal, |normal, parent| {
+                parent.inverse_transpose() * &normal
+            })
+            .normalize()
+    }
+
+    // The world-space surface normal at a world-space point on the hit.
+    pub fn normal_at(&self, point: Point) -> Vector {
+        let local_point = self.world_to_object(point);
+        let local_normal = self.object.local_hit_normal_at(local_point, self);
+        let material = self.object.material();
+        self.normal_to_world(material.perturb_normal(local_point, local_normal))
+    }
+}
+
+impl ObjectSpace for Intersection<'_> {
+    fn world_to_object(&self, point: Point) -> Point {
+        Intersection::world_to_object(self, point)
     }
 }
 
-impl PartialEq for Intersection {
+impl PartialEq for Intersection<'_> {
     fn eq(&self, other: &Self) -> bool {
         fp_equal(self.t(), other.t())
     }
 }
 
-pub fn hit(intersections: Vec<Intersection>) -> Option<Intersection> {
+pub fn hit<'a>(intersections: &[Intersection<'a>]) -> Option<Intersection<'a>> {
     let above_zero = intersections.iter().filter(|x| x.t() > 0.0);
-    let mut current = std::f32::MAX;
+    let mut current = f32::MAX;
     let mut cinter: Option<Intersection> = None;
     above_zero.into_iter().for_each(|inter| {
         if inter.t() < current {
@@ -96,9 +199,10 @@ pub fn hit(intersections: Vec<Intersection>) -> Option<Intersection> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sphere::Sphere;
     use crate::utils::fp_equal;
 
-    fn intersections(inters: &[Intersection]) -> Vec<Intersection> {
+    fn intersections<'a>(inters: &[Intersection<'a>]) -> Vec<Intersection<'a>> {
         inters.to_vec()
     }
 
@@ -164,12 +268,38 @@ mod tests {
         assert!(fp_equal(xs[1].t, -4.0));
     }
 
+    #[test]
+    fn intersections_borrow_the_shape_they_hit() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Sphere::default();
+        let xs = r.intersect(&s);
+        let address = |shape: &dyn Shape| shape as *const dyn Shape as *const u8;
+        let original = &s as *const Sphere as *const u8;
+        assert_eq!(address(xs[0].object()), original);
+
+        // Placing the hit inside a group keeps borrowing the shape.
+        let inner = Transform::new(Matrix::translation(0.0, 0.0, 1.0));
+        let outer = Transform::new(Matrix::scaling(2.0, 2.0, 2.0));
+        let placed = xs[0]
+            .clone()
+            .apply_parent_transform(Cow::Borrowed(&inner))
+            .apply_parent_transform(Cow::Borrowed(&outer));
+        assert_eq!(address(placed.object()), original);
+        // Both transforms are borrowed, inverses and all.
+        let parents: Vec<&Transform> = placed.parents().collect();
+        assert!(std::ptr::eq(parents[0], &inner) && std::ptr::eq(parents[1], &outer));
+        let p = placed.world_to_object(Point::new(0.0, 0.0, 4.0));
+        assert_eq!(p, Point::new(0.0, 0.0, 1.0));
+        let n = placed.normal_at(Point::new(0.0, 0.0, 4.0));
+        assert_eq!(n, Vector::new(0.0, 0.0, 1.0));
+    }
+
     #[test]
     fn aggregating_intersections() {
         let s = Sphere::default();
         let s2 = s.clone();
-        let i1 = Intersection::new(1.0, s);
-        let i2 = Intersection::new(2.0, s2);
+        let i1 = Intersection::new(1.0, &s);
+        let i2 = Intersection::new(2.0, &s2);
         let xs = intersections(&[i1, i2]);
         assert!(xs.len() == 2);
         assert!(fp_equal(xs[0].t(), 1.0));
@@ -181,19 +311,19 @@ mod tests {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let s = Sphere::default();
         let xs = r.intersect(&s);
-        assert!(xs[0].object() == &s);
-        assert!(xs[1].object() == &s);
+        assert!(xs[0].object() == &s as &dyn Shape);
+        assert!(xs[1].object() == &s as &dyn Shape);
     }
 
     #[test]
     fn hit_when_all_positive_t() {
         let s = Sphere::default();
         let s2 = s.clone();
-        let i1 = Intersection::new(1.0, s);
+        let i1 = Intersection::new(1.0, &s);
         let i1c = i1.clone();
-        let i2 = Intersection::new(2.0, s2);
+        let i2 = Intersection::new(2.0, &s2);
         let xs = intersections(&[i2, i1c]);
-        let i = hit(xs);
+        let i = hit(&xs);
         assert!(i == Some(i1.clone()));
     }
 
@@ -201,11 +331,11 @@ mod tests {
     fn hit_when_some_negative_t() {
         let s = Sphere::default();
         let s2 = s.clone();
-        let i1 = Intersection::new(-1.0, s);
-        let i2 = Intersection::new(1.0, s2);
+        let i1 = Intersection::new(-1.0, &s);
+        let i2 = Intersection::new(1.0, &s2);
         let i2c = i2.clone();
         let xs = intersections(&[i2c, i1]);
-        let i = hit(xs);
+        let i = hit(&xs);
         assert!(i == Some(i2));
     }
 
@@ -213,11 +343,11 @@ mod tests {
     fn hit_when_all_negative_t() {
         let s = Sphere::default();
         let s2 = s.clone();
-        let i1 = Intersection::new(-2.0, s);
-        let i2 = Intersection::new(-1.0, s2);
+        let i1 = Intersection::new(-2.0, &s);
+        let i2 = Intersection::new(-1.0, &s2);
         let xs = intersections(&[i2, i1]);
-        let i = hit(xs);
-        assert!(i == None);
+        let i = hit(&xs);
+        assert!(i.is_none());
     }
 
     #[test]
@@ -226,13 +356,13 @@ mod tests {
         let s2 = s.clone();
         let s3 = s.clone();
         let s4 = s.clone();
-        let i1 = Intersection::new(5.0, s);
-        let i2 = Intersection::new(7.0, s2);
-        let i3 = Intersection::new(-3.0, s3);
-        let i4 = Intersection::new(2.0, s4);
+        let i1 = Intersection::new(5.0, &s);
+        let i2 = Intersection::new(7.0, &s2);
+        let i3 = Intersection::new(-3.0, &s3);
+        let i4 = Intersection::new(2.0, &s4);
         let i4c = i4.clone();
         let xs = intersections(&[i1, i2, i3, i4c]);
-        let i = hit(xs);
+        let i = hit(&xs);
         assert!(i == Some(i4));
     }
 
@@ -0,0 +1,169 @@
+use crate::bounding_box::BoundingBox;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::utils::EPSILON;
+use crate::vector::Vector;
+use std::any::Any;
+
+// Bounded plane in the xz plane, centred on the origin and facing +y. Width
+// runs along x and height along z.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rectangle {
+    transform: Transform,
+    pub material: Material,
+    width: f32,
+    height: f32,
+}
+
+impl Default for Rectangle {
+    fn default() -> Rectangle {
+        Rectangle {
+            transform: Transform::default(),
+            material: Material::default(),
+            width: 1.0,
+            height: 1.0,
+        }
+    }
+}
+
+impl Rectangle {
+    pub fn new(width: f32, height: f32) -> Rectangle {
+        Rectangle {
+            width,
+            height,
+            ..Rectangle::default()
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn set_material(mut self, m: Material) -> Self {
+        self.material = m;
+        self
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
+    }
+
+    pub fn set_size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Rectangle {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if ray.direction().y.abs() < EPSILON {
+            return vec![];
+        }
+
+        let t = -ray.origin().y / ray.direction().y;
+        let p = ray.position(t);
+        if p.x.abs() > self.width / 2.0 || p.z.abs() > self.height / 2.0 {
+            return vec![];
+        }
+        vec![Intersection::new(t, self)]
+    }
+
+    fn local_normal_at(&self, _point: Point) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let (hw, hh) = (self.width / 2.0, self.height / 2.0);
+        BoundingBox::new(Point::new(-hw, 0.0, -hh), Point::new(hw, 0.0, hh))
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Rectangle>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn ray_strikes_a_rectangle() {
+        let rect = Rectangle::new(4.0, 2.0);
+        let r = Ray::new(Point::new(1.9, -2.0, 0.9), Vector::new(0.0, 1.0, 0.0));
+        let xs = rect.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert!(fp_equal(xs[0].t(), 2.0));
+    }
+
+    #[test]
+    fn ray_outside_rectangle_misses() {
+        let rect = Rectangle::new(4.0, 2.0);
+        for origin in [Point::new(2.1, 1.0, 0.0), Point::new(0.0, 1.0, 1.1)] {
+            let r = Ray::new(origin, Vector::new(0.0, -1.0, 0.0));
+            assert!(rect.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_parallel_to_a_rectangle_misses() {
+        let rect = Rectangle::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(rect.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn normal_of_a_rectangle_is_constant() {
+        let rect = Rectangle::default();
+        assert_eq!(
+            rect.local_normal_at(Point::new(0.25, 0.0, -0.25)),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn rectangle_bounds_follow_its_size() {
+        let b = Rectangle::default().set_size(6.0, 2.0).bounds();
+        assert_eq!(b.min(), Point::new(-3.0, 0.0, -1.0));
+        assert_eq!(b.max(), Point::new(3.0, 0.0, 1.0));
+    }
+}
@@ -0,0 +1,122 @@
+// Small SplitMix64 generator. Stochastic rendering draws from one of these
+// per pixel, seeded from the render seed and the pixel position, so a
+// render is reproducible whatever order its pixels are traced in.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    // The seed and each coordinate go through the mixer in turn, so no two
+    // (seed, x, y) share a stream the way XORing them together would let
+    // them.
+    pub fn for_pixel(seed: u64, x: u32, y: u32) -> Rng {
+        Rng::for_pass(seed, 0, x, y)
+    }
+
+    // A pixel's stream for one pass of a progressive render. Pass 0 is the
+    // pixel's own stream.
+    pub fn for_pass(seed: u64, pass: u32, x: u32, y: u32) -> Rng {
+        Rng::new(mix(mix(mix(mix(seed) ^ pass as u64) ^ x as u64) ^ y as u64))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+// One SplitMix64 step from state, scrambling every bit of it.
+fn mix(state: u64) -> u64 {
+    Rng::new(state).next_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_sequences() {
+        let mut a = Rng::new(1);
+        let mut b = Rng::new(2);
+        assert_ne!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn floats_are_in_unit_interval() {
+        let mut r = Rng::new(7);
+        for _ in 0..1000 {
+            let f = r.next_f32();
+            assert!((0.0..1.0).contains(&f));
+        }
+    }
+
+    #[test]
+    fn pixel_generators_are_independent_of_order() {
+        let mut a = Rng::for_pixel(5, 3, 4);
+        let _ = Rng::for_pixel(5, 0, 0).next_u64();
+        let mut b = Rng::for_pixel(5, 3, 4);
+        assert_eq!(a.next_u64(), b.next_u64());
+        assert_ne!(
+            Rng::for_pixel(5, 3, 4).next_u64(),
+            Rng::for_pixel(5, 4, 3).next_u64()
+        );
+    }
+
+    #[test]
+    fn neighbouring_seeds_and_pixels_get_their_own_streams() {
+        assert_ne!(
+            Rng::for_pixel(1, 7, 10).next_u64(),
+            Rng::for_pixel(0, 7, 11).next_u64()
+        );
+        let mut seen = std::collections::HashSet::new();
+        for seed in 0..4 {
+            for x in 0..16 {
+                for y in 0..16 {
+                    assert!(seen.insert(Rng::for_pixel(seed, x, y).next_u64()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn passes_of_neighbouring_rows_draw_different_samples() {
+        for y in (0..16).step_by(2) {
+            assert_ne!(
+                Rng::for_pass(3, 1, 5, y).next_u64(),
+                Rng::for_pass(3, 0, 5, y + 1).next_u64()
+            );
+        }
+        let mut seen = std::collections::HashSet::new();
+        for pass in 0..8 {
+            for y in 0..16 {
+                assert!(seen.insert(Rng::for_pass(3, pass, 5, y).next_u64()));
+            }
+        }
+        assert_eq!(
+            Rng::for_pass(3, 0, 5, 7).next_u64(),
+            Rng::for_pixel(3, 5, 7).next_u64()
+        );
+    }
+}
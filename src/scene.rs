@@ -0,0 +1,12 @@
+use crate::camera::Camera;
+use crate::world::World;
+
+#[cfg(feature = "yaml")]
+pub use crate::scene_file::SceneFile;
+
+// A camera and the world in front of it, ready to render. With the `yaml`
+// feature a scene can also be read from a file; see scene_file.rs.
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+}
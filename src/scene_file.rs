@@ -0,0 +1,735 @@
+use crate::camera::{Camera, CameraBuilder};
+use crate::capsule::Capsule;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::cylinder::Cylinder;
+use crate::disc::Disc;
+use crate::group::Group;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::obj::ObjFile;
+use crate::pattern::{CheckerPattern, GradientPattern, Pattern, RingPattern, StripePattern};
+use crate::point::Point;
+use crate::point_light::PointLight;
+use crate::quality::Quality;
+use crate::rectangle::Rectangle;
+use crate::scene::Scene;
+use crate::shape::Shape;
+use crate::sphere::Sphere;
+use crate::vector::Vector;
+use crate::world::World;
+use serde_yaml_ng::{Mapping, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Scenes read from YAML files in the format used by The Ray Tracer
+// Challenge: a list of `add` items for the camera, lights and shapes, and
+// `define` items naming materials and transforms for reuse.
+//
+//   - add: camera
+//     width: 640
+//     height: 480
+//     field-of-view: 1.0
+//     from: [0, 1.5, -5]
+//     to: [0, 1, 0]
+//     up: [0, 1, 0]
+//   - add: light
+//     at: [-10, 10, -10]
+//     intensity: [1, 1, 1]
+//   - define: shiny
+//     value: { color: [1, 0.2, 0.2], reflective: 0.3 }
+//   - add: sphere
+//     material: shiny
+//     transform:
+//       - [scale, 0.5, 0.5, 0.5]
+//       - [translate, 0, 0.5, 0]
+//
+// Transforms are applied in the order they're listed. Besides translate,
+// scale, rotate-x, rotate-y, rotate-z and shear, there's [rotate, x, y, z,
+// angle] to turn about any axis and [euler, yaw, pitch, roll].
+//
+// The camera, lights and shapes can be animated with a list of keyframes,
+// each giving a time and new values for any of the item's keys. Between two
+// keyframes every number in those values is blended linearly, so both must
+// have the same layout; before the first and after the last, the item holds
+// still.
+//
+//   - add: sphere
+//     keyframes:
+//       - time: 0
+//         transform: [[rotate-y, 0], [translate, 2, 0, 0]]
+//       - time: 1
+//         transform: [[rotate-y, 3.1416], [translate, 2, 0, 0]]
+//
+// A `quality` item sets the samples, recursion depth and resolution scale to
+// render at, by a preset's name or as a profile; see Quality.
+//
+//   - quality: draft
+//   - quality: { samples: 8, max_recursion: 4 }
+impl Scene {
+    // Relative paths in the scene, such as OBJ files, are taken from the
+    // current directory. Animated items are placed at their first keyframe.
+    pub fn parse(source: &str) -> Result<Scene, String> {
+        SceneFile::parse(source)?.start()
+    }
+
+    // Relative paths in the scene are taken from the scene file's directory.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, String> {
+        SceneFile::load(path)?.start()
+    }
+}
+
+// A parsed scene file, from which a Scene can be built at any time.
+pub struct SceneFile {
+    items: Vec<Mapping>,
+    base: PathBuf,
+    quality: Option<Quality>,
+}
+
+impl SceneFile {
+    pub fn parse(source: &str) -> Result<SceneFile, String> {
+        SceneFile::parse_in(source, Path::new("."))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<SceneFile, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        SceneFile::parse_in(&source, base)
+    }
+
+    fn parse_in(source: &str, base: &Path) -> Result<SceneFile, String> {
+        let items: Vec<Mapping> = serde_yaml_ng::from_str(source).map_err(|e| e.to_string())?;
+        let mut quality = None;
+        for (number, item) in items.iter().enumerate() {
+            if let Some(value) = item.get("quality") {
+                let profile = Quality::from_value(value.clone())
+                    .map_err(|e| format!("item {}: {}", number + 1, e))?;
+                quality = Some(profile);
+            }
+        }
+        Ok(SceneFile {
+            items,
+            base: base.to_path_buf(),
+            quality,
+        })
+    }
+
+    pub fn quality(&self) -> Option<&Quality> {
+        self.quality.as_ref()
+    }
+
+    // Replaces the scene's own quality profile. With None, scenes are built
+    // with the camera and world just as the file describes them.
+    pub fn set_quality(mut self, quality: Option<Quality>) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    // The earliest and latest keyframe times, or None if nothing moves.
+    pub fn time_range(&self) -> Option<(f32, f32)> {
+        let mut times = vec![];
+        for item in &self.items {
+            keyframe_times(item, &mut times);
+        }
+        let start = times.iter().cloned().fold(f32::INFINITY, f32::min);
+        let end = times.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        if times.is_empty() {
+            None
+        } else {
+            Some((start, end))
+        }
+    }
+
+    // The scene at its first keyframe.
+    pub fn start(&self) -> Result<Scene, String> {
+        self.at(self.time_range().map_or(0.0, |(start, _)| start))
+    }
+
+    pub fn at(&self, time: f32) -> Result<Scene, String> {
+        let mut parser = Parser {
+            defines: HashMap::new(),
+            base: self.base.clone(),
+            time,
+        };
+        let mut camera = None;
+        let mut lights = vec![];
+        let mut objects = vec![];
+        for (number, item) in self.items.iter().enumerate() {
+            let context = |e: String| format!("item {}: {}", number + 1, e);
+            if item.contains_key("quality") {
+                continue;
+            }
+            if let Some(name) = item.get("define") {
+                parser.define(name, item).map_err(context)?;
+                continue;
+            }
+            match item.get("add").and_then(Value::as_str) {
+                Some("camera") => camera = Some(parser.camera(item).map_err(context)?),
+                Some("light") => lights.push(parser.light(item).map_err(context)?),
+                Some(_) => objects.push(parser.shape(item).map_err(context)?),
+                None => return Err(context(String::from("expected `add` or `define`"))),
+            }
+        }
+
+        let mut camera = camera.ok_or("scene has no camera")?;
+        let mut lights = lights.into_iter();
+        let first = lights.next().ok_or("scene has no lights")?;
+        let mut world = lights.fold(World::new(objects, first), |w, l| w.add_light(l));
+        if let Some(quality) = &self.quality {
+            camera = camera.set_quality(quality);
+            world = world.set_quality(quality);
+        }
+        Ok(Scene { camera, world })
+    }
+}
+
+struct Parser {
+    defines: HashMap<String, Value>,
+    base: PathBuf,
+    time: f32,
+}
+
+impl Parser {
+    // A define may extend an earlier one: materials take the earlier keys
+    // and override them, transform lists follow on from the earlier list.
+    fn define(&mut self, name: &Value, item: &Mapping) -> Result<(), String> {
+        let name = name.as_str().ok_or("define name must be a string")?;
+        let mut value = item.get("value").cloned().ok_or("define has no value")?;
+        if let Some(parent) = item.get("extend") {
+            let parent = self.lookup(parent)?;
+            value = match (parent, value) {
+                (Value::Mapping(mut base), Value::Mapping(overrides)) => {
+                    base.extend(overrides);
+                    Value::Mapping(base)
+                }
+                (Value::Sequence(mut base), Value::Sequence(more)) => {
+                    base.extend(more);
+                    Value::Sequence(base)
+                }
+                _ => return Err(format!("{} can't extend a different kind of define", name)),
+            };
+        }
+        self.defines.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &Value) -> Result<Value, String> {
+        let name = name.as_str().ok_or("expected the name of a define")?;
+        self.defines
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("nothing is defined as {}", name))
+    }
+
+    // The item with its keyframed values at the parser's time.
+    fn animate(&self, item: &Mapping) -> Result<Mapping, String> {
+        let keyframes = match item.get("keyframes") {
+            None => return Ok(item.clone()),
+            Some(Value::Sequence(keyframes)) if !keyframes.is_empty() => keyframes,
+            Some(_) => return Err(String::from("keyframes must be a non-empty list")),
+        };
+        let mut frames = vec![];
+        for keyframe in keyframes {
+            let keyframe = keyframe.as_mapping().ok_or("keyframes must be mappings")?;
+            frames.push((number(required(keyframe, "time")?, "time")?, keyframe));
+        }
+        frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let after = frames.iter().position(|(t, _)| *t > self.time);
+        let (values, blended) = match after {
+            Some(0) => (frames[0].1.clone(), None),
+            None => (frames[frames.len() - 1].1.clone(), None),
+            Some(i) => {
+                let ((t0, a), (t1, b)) = (frames[i - 1], frames[i]);
+                (a.clone(), Some(((self.time - t0) / (t1 - t0), b, t0, t1)))
+            }
+        };
+
+        let mut animated = item.clone();
+        animated.remove("keyframes");
+        for (key, value) in values {
+            if key.as_str() == Some("time") {
+                continue;
+            }
+            let value = match blended {
+                Some((t, b, t0, t1)) => match b.get(&key) {
+                    Some(other) => lerp(&value, other, t).ok_or_else(|| {
+                        format!(
+                            "keyframes at {} and {} have different layouts for {}",
+                            t0,
+                            t1,
+                            key.as_str().unwrap_or_default()
+                        )
+                    })?,
+                    None => value,
+                },
+                None => value,
+            };
+            animated.insert(key, value);
+        }
+        Ok(animated)
+    }
+
+    fn camera(&self, item: &Mapping) -> Result<Camera, String> {
+        let item = &self.animate(item)?;
+        let mut builder = CameraBuilder::new(uint(item, "width")?, uint(item, "height")?)
+            .field_of_view(number(required(item, "field-of-view")?, "field-of-view")?)
+            .look_at(
+                point(required(item, "from")?, "from")?,
+                point(required(item, "to")?, "to")?,
+                vector(required(item, "up")?, "up")?,
+            );
+        if let Some(degrees) = item.get("field-of-view-degrees") {
+            builder = builder.field_of_view_degrees(number(degrees, "field-of-view-degrees")?);
+        }
+        let mut camera = builder.build()?;
+        if item.contains_key("samples") {
+            camera = camera.set_samples(uint(item, "samples")?);
+        }
+        Ok(camera)
+    }
+
+    fn light(&self, item: &Mapping) -> Result<Box<PointLight>, String> {
+        let item = &self.animate(item)?;
+        Ok(Box::new(PointLight::new(
+            point(required(item, "at")?, "at")?,
+            color(required(item, "intensity")?, "intensity")?,
+        )))
+    }
+
+    fn shape(&self, item: &Mapping) -> Result<Box<dyn Shape>, String> {
+        let item = &self.animate(item)?;
+        let kind = item.get("add").and_then(Value::as_str).unwrap_or_default();
+        let transform = match item.get("transform") {
+            Some(t) => self.transform(t)?,
+            None => Matrix::identity(),
+        };
+        let material = match item.get("material") {
+            Some(m) => self.material(m)?,
+            None => Material::default(),
+        };
+        let optional = |key: &str| item.get(key).map(|v| number(v, key)).transpose();
+
+        let shape: Box<dyn Shape> = match kind {
+            "sphere" => Box::new(Sphere::new(transform, material)),
+            "cube" => Box::new(Cube::new(transform, material)),
+            "cylinder" => {
+                let mut cylinder = Cylinder::new(transform, material);
+                if let Some(min) = optional("min")? {
+                    cylinder = cylinder.set_minimum(min);
+                }
+                if let Some(max) = optional("max")? {
+                    cylinder = cylinder.set_maximum(max);
+                }
+                if let Some(closed) = item.get("closed") {
+                    cylinder = cylinder
+                        .set_closed(closed.as_bool().ok_or("closed must be true or false")?);
+                }
+                Box::new(cylinder)
+            }
+            "capsule" => Box::new(
+                Capsule::new(
+                    optional("radius")?.unwrap_or(1.0),
+                    optional("length")?.unwrap_or(1.0),
+                )
+                .set_transform(transform)
+                .set_material(material),
+            ),
+            "disc" => Box::new(
+                Disc::new(optional("radius")?.unwrap_or(1.0))
+                    .set_transform(transform)
+                    .set_material(material),
+            ),
+            "rectangle" => Box::new(
+                Rectangle::new(
+                    optional("width")?.unwrap_or(1.0),
+                    optional("height")?.unwrap_or(1.0),
+                )
+                .set_transform(transform)
+                .set_material(material),
+            ),
+            "group" => {
+                let children = match item.get("children") {
+                    Some(Value::Sequence(children)) => children,
+                    _ => return Err(String::from("group needs a list of children")),
+                };
+                let mut group = Group::default().set_transform(transform);
+                for child in children {
+                    let child = child.as_mapping().ok_or("group children must be shapes")?;
+                    group = group.add_child(self.shape(child)?);
+                }
+                Box::new(group)
+            }
+            "obj" => {
+                let file = item
+                    .get("file")
+                    .and_then(Value::as_str)
+                    .ok_or("obj needs a file")?;
+                let obj = ObjFile::load(self.base.join(file))
+                    .map_err(|e| format!("couldn't read {}: {}", file, e))?;
+                Box::new(obj.to_group().set_transform(transform))
+            }
+            _ => return Err(format!("unknown shape {}", kind)),
+        };
+        Ok(shape)
+    }
+
+    fn transform(&self, value: &Value) -> Result<Matrix, String> {
+        let steps = match value {
+            Value::Sequence(steps) => steps.clone(),
+            Value::String(_) => return self.transform(&self.lookup(value)?),
+            _ => return Err(String::from("transform must be a list")),
+        };
+        let mut matrix = Matrix::identity();
+        for step in &steps {
+            let next = match step {
+                Value::String(_) => self.transform(&self.lookup(step)?)?,
+                Value::Sequence(parts) => transform_step(parts)?,
+                _ => return Err(String::from("transform steps must be lists")),
+            };
+            matrix = &next * &matrix;
+        }
+        // Caught here rather than partway through rendering.
+        if !matrix.invertible() {
+            return Err(String::from("transform is not invertible"));
+        }
+        Ok(matrix)
+    }
+
+    fn material(&self, value: &Value) -> Result<Material, String> {
+        let keys = match value {
+            Value::Mapping(keys) => keys.clone(),
+            Value::String(_) => return self.material(&self.lookup(value)?),
+            _ => return Err(String::from("material must be a mapping or a define")),
+        };
+        let mut material = Material::default();
+        for (key, value) in &keys {
+            let key = key.as_str().ok_or("material keys must be strings")?;
+            material = match key {
+                "color" => material.set_color(color(value, key)?),
+                "emissive" => material.set_emissive(color(value, key)?),
+                "ambient" => material.set_ambient(number(value, key)?),
+                "diffuse" => material.set_diffuse(number(value, key)?),
+                "specular" => material.set_specular(number(value, key)?),
+                "shininess" => material.set_shininess(number(value, key)?),
+                "reflective" => material.set_reflective(number(value, key)?),
+                "transparency" => material.set_transparency(number(value, key)?),
+                "refractive-index" => material.set_refractive_index(number(value, key)?),
+                "pattern" => material.set_pattern(self.pattern(value)?),
+                _ => return Err(format!("unknown material key {}", key)),
+            };
+        }
+        Ok(material)
+    }
+
+    fn pattern(&self, value: &Value) -> Result<Box<dyn Pattern>, String> {
+        let keys = value.as_mapping().ok_or("pattern must be a mapping")?;
+        let colors = match keys.get("colors") {
+            Some(Value::Sequence(colors)) if colors.len() == 2 => colors,
+            _ => return Err(String::from("pattern needs a list of two colors")),
+        };
+        let (a, b) = (color(&colors[0], "colors")?, color(&colors[1], "colors")?);
+        let transform = match keys.get("transform") {
+            Some(t) => self.transform(t)?,
+            None => Matrix::identity(),
+        };
+        let kind = keys.get("type").and_then(Value::as_str).unwrap_or_default();
+        let pattern: Box<dyn Pattern> = match kind {
+            "stripes" => Box::new(StripePattern::new(a, b).set_transform(transform)),
+            "gradient" => Box::new(GradientPattern::new(a, b).set_transform(transform)),
+            "rings" => Box::new(RingPattern::new(a, b).set_transform(transform)),
+            "checkers" => Box::new(CheckerPattern::new(a, b).set_transform(transform)),
+            _ => return Err(format!("unknown pattern type {}", kind)),
+        };
+        Ok(pattern)
+    }
+}
+
+fn transform_step(parts: &[Value]) -> Result<Matrix, String> {
+    let op = parts
+        .first()
+        .and_then(Value::as_str)
+        .ok_or("transform steps start with an operation name")?;
+    let args = parts[1..]
+        .iter()
+        .map(|v| number(v, op))
+        .collect::<Result<Vec<f32>, String>>()?;
+    let expect = |n: usize| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(format!("{} takes {} numbers, not {}", op, n, args.len()))
+        }
+    };
+    match op {
+        "translate" => expect(3).map(|_| Matrix::translation(args[0], args[1], args[2])),
+        "scale" => expect(3).map(|_| Matrix::scaling(args[0], args[1], args[2])),
+        "rotate-x" => expect(1).map(|_| Matrix::rotation_x(args[0])),
+        "rotate-y" => expect(1).map(|_| Matrix::rotation_y(args[0])),
+        "rotate-z" => expect(1).map(|_| Matrix::rotation_z(args[0])),
+        "rotate" => expect(4)
+            .map(|_| Matrix::rotation_axis(Vector::new(args[0], args[1], args[2]), args[3])),
+        "euler" => expect(3).map(|_| Matrix::from_euler(args[0], args[1], args[2])),
+        "shear" => expect(6)
+            .map(|_| Matrix::shearing(args[0], args[1], args[2], args[3], args[4], args[5])),
+        _ => Err(format!("unknown transform {}", op)),
+    }
+}
+
+// Blends every number in a towards the matching one in b. Anything else must
+// be the same in both.
+fn lerp(a: &Value, b: &Value, t: f32) -> Option<Value> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            let (x, y) = (x.as_f64()?, y.as_f64()?);
+            Some(Value::from(x + (y - x) * t as f64))
+        }
+        (Value::Sequence(xs), Value::Sequence(ys)) if xs.len() == ys.len() => xs
+            .iter()
+            .zip(ys)
+            .map(|(x, y)| lerp(x, y, t))
+            .collect::<Option<Vec<Value>>>()
+            .map(Value::Sequence),
+        (Value::Mapping(xs), Value::Mapping(ys)) if xs.len() == ys.len() => xs
+            .iter()
+            .map(|(k, x)| Some((k.clone(), lerp(x, ys.get(k)?, t)?)))
+            .collect::<Option<Mapping>>()
+            .map(Value::Mapping),
+        _ if a == b => Some(a.clone()),
+        _ => None,
+    }
+}
+
+fn keyframe_times(item: &Mapping, times: &mut Vec<f32>) {
+    if let Some(Value::Sequence(keyframes)) = item.get("keyframes") {
+        for keyframe in keyframes {
+            if let Some(time) = keyframe.get("time").and_then(Value::as_f64) {
+                times.push(time as f32);
+            }
+        }
+    }
+    if let Some(Value::Sequence(children)) = item.get("children") {
+        for child in children.iter().filter_map(Value::as_mapping) {
+            keyframe_times(child, times);
+        }
+    }
+}
+
+fn required<'a>(item: &'a Mapping, key: &str) -> Result<&'a Value, String> {
+    item.get(key).ok_or_else(|| format!("missing {}", key))
+}
+
+fn number(value: &Value, key: &str) -> Result<f32, String> {
+    value
+        .as_f64()
+        .map(|n| n as f32)
+        .ok_or_else(|| format!("{} must be a number", key))
+}
+
+fn uint(item: &Mapping, key: &str) -> Result<u32, String> {
+    required(item, key)?
+        .as_u64()
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or_else(|| format!("{} must be a whole number", key))
+}
+
+fn triple(value: &Value, key: &str) -> Result<(f32, f32, f32), String> {
+    match value {
+        Value::Sequence(v) if v.len() == 3 => Ok((
+            number(&v[0], key)?,
+            number(&v[1], key)?,
+            number(&v[2], key)?,
+        )),
+        _ => Err(format!("{} must be a list of three numbers", key)),
+    }
+}
+
+fn point(value: &Value, key: &str) -> Result<Point, String> {
+    triple(value, key).map(|(x, y, z)| Point::new(x, y, z))
+}
+
+fn vector(value: &Value, key: &str) -> Result<Vector, String> {
+    triple(value, key).map(|(x, y, z)| Vector::new(x, y, z))
+}
+
+fn color(value: &Value, key: &str) -> Result<Color, String> {
+    triple(value, key).map(|(r, g, b)| Color::new(r, g, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    const SCENE: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- define: red
+  value:
+    color: [1, 0, 0]
+    specular: 0
+- define: shiny-red
+  extend: red
+  value:
+    reflective: 0.5
+- define: small
+  value:
+    - [scale, 0.5, 0.5, 0.5]
+- add: sphere
+  material: shiny-red
+  transform:
+    - small
+    - [translate, 0, 0, 1]
+- add: group
+  children:
+    - add: cube
+      material:
+        pattern:
+          type: checkers
+          colors: [[1, 1, 1], [0, 0, 0]]
+";
+
+    #[test]
+    fn parsing_a_scene() {
+        let scene = Scene::parse(SCENE).unwrap();
+        assert_eq!(scene.camera.hsize(), 100);
+        assert_eq!(scene.camera.vsize(), 50);
+        assert_eq!(scene.world.lights().len(), 1);
+        assert_eq!(scene.world.objects().len(), 2);
+
+        let sphere = &scene.world.objects()[0];
+        assert_eq!(
+            *sphere.transform(),
+            Matrix::translation(0.0, 0.0, 1.0) * Matrix::scaling(0.5, 0.5, 0.5)
+        );
+        assert_eq!(sphere.material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(sphere.material().specular, 0.0);
+        assert_eq!(sphere.material().reflective, 0.5);
+
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = scene.world.intersect_world(&r);
+        assert!((xs[0].t() - 4.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn scene_errors_say_where_they_are() {
+        let missing_camera = "- add: light\n  at: [0, 0, 0]\n  intensity: [1, 1, 1]\n";
+        assert_eq!(
+            Scene::parse(missing_camera).err().unwrap(),
+            "scene has no camera"
+        );
+
+        let bad_shape = SCENE.to_string() + "- add: teapot\n";
+        assert_eq!(
+            Scene::parse(&bad_shape).err().unwrap(),
+            "item 8: unknown shape teapot"
+        );
+
+        let bad_transform = SCENE.replace("[scale, 0.5, 0.5, 0.5]", "[scale, 0.5]");
+        assert_eq!(
+            Scene::parse(&bad_transform).err().unwrap(),
+            "item 6: scale takes 3 numbers, not 1"
+        );
+        let flat = SCENE.replace("[scale, 0.5, 0.5, 0.5]", "[scale, 0, 0.5, 0.5]");
+        assert_eq!(
+            Scene::parse(&flat).err().unwrap(),
+            "item 6: transform is not invertible"
+        );
+        let small = SCENE.replace("[scale, 0.5, 0.5, 0.5]", "[scale, 0.02, 0.02, 0.02]");
+        assert!(Scene::parse(&small).is_ok());
+        assert!(Scene::parse("not: a list").is_err());
+    }
+
+    #[test]
+    fn scenes_choose_a_quality() {
+        let draft = format!("{}- quality: draft\n", SCENE);
+        let scene = Scene::parse(&draft).unwrap();
+        assert_eq!((scene.camera.hsize(), scene.camera.vsize()), (50, 25));
+        assert_eq!(scene.world.max_recursion(), Quality::draft().max_recursion);
+
+        let custom = format!("{}- quality: {{ samples: 3, max_recursion: 2 }}\n", SCENE);
+        let file = SceneFile::parse(&custom).unwrap();
+        assert_eq!(file.quality().unwrap().samples, 3);
+        let scene = file.start().unwrap();
+        assert_eq!(scene.camera.samples(), 3);
+        assert_eq!(scene.camera.hsize(), 100);
+
+        let plain = SceneFile::parse(&custom).unwrap().set_quality(None);
+        assert_eq!(plain.start().unwrap().camera.samples(), 1);
+        let unknown = format!("{}- quality: ultra\n", SCENE);
+        assert_eq!(
+            SceneFile::parse(&unknown).err().unwrap(),
+            "item 8: unknown quality ultra"
+        );
+    }
+
+    #[test]
+    fn example_scene_loads() {
+        let scene = Scene::load("scenes/spheres.yaml").unwrap();
+        assert_eq!(scene.world.objects().len(), 6);
+    }
+
+    const ANIMATED: &str = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 0.785
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+  keyframes:
+    - { time: 2, from: [0, 0, -10] }
+    - { time: 4, from: [0, 0, -20] }
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- add: sphere
+  keyframes:
+    - time: 2
+      transform: [[translate, 0, 0, 0]]
+    - time: 4
+      transform: [[translate, 4, 0, 0]]
+      material: { color: [1, 0, 0] }
+";
+
+    #[test]
+    fn keyframes_blend_between_times() {
+        let file = SceneFile::parse(ANIMATED).unwrap();
+        assert_eq!(file.time_range(), Some((2.0, 4.0)));
+        let sphere_at = |time| {
+            let scene = file.at(time).unwrap();
+            scene.world.objects()[0].transform().clone()
+        };
+        assert_eq!(sphere_at(3.0), Matrix::translation(2.0, 0.0, 0.0));
+        assert_eq!(sphere_at(0.0), Matrix::identity());
+        assert_eq!(sphere_at(9.0), Matrix::translation(4.0, 0.0, 0.0));
+
+        let camera = file.at(3.0).unwrap().camera;
+        let ray = camera.ray_for_pixel(5, 5);
+        assert_eq!(ray.origin(), Point::new(0.0, 0.0, -15.0));
+        assert!(SceneFile::parse(SCENE).unwrap().time_range().is_none());
+    }
+
+    #[test]
+    fn keyframes_must_match_to_blend() {
+        let bad = ANIMATED.replace("[[translate, 4, 0, 0]]", "[[scale, 4, 1, 1]]");
+        let file = SceneFile::parse(&bad).unwrap();
+        assert!(file.at(1.0).is_ok());
+        assert_eq!(
+            file.at(3.0).err().unwrap(),
+            "item 3: keyframes at 2 and 4 have different layouts for transform"
+        );
+    }
+}
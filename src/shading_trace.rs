@@ -0,0 +1,58 @@
+use crate::color::Color;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::vector::Vector;
+
+// How the color seen along one ray was worked out, from World::debug_trace,
+// for finding out why a pixel looks wrong.
+#[derive(Clone, Debug)]
+pub struct ShadingTrace<'a> {
+    pub ray: Ray,
+    // Everything along the ray, behind it too, nearest first.
+    pub intersections: Vec<Intersection<'a>>,
+    // None when the ray missed, and color is the background.
+    pub hit: Option<TracedHit<'a>>,
+    pub color: Color,
+}
+
+// The hit the ray was shaded with and what went into its color, which is
+// the sum of the light terms, the environment, and the reflection and
+// refraction colors times their weights.
+#[derive(Clone, Debug)]
+pub struct TracedHit<'a> {
+    pub intersection: Intersection<'a>,
+    pub point: Point,
+    // Turned to face the eye; inside says whether it had to be.
+    pub normal: Vector,
+    pub eye: Vector,
+    pub inside: bool,
+    // One for each of the world's lights, in order.
+    pub lights: Vec<LightTerm>,
+    pub environment: Color,
+    // Traced only if the material reflects or refracts and the recursion
+    // limit allows it.
+    pub reflection: Option<Box<ShadingTrace<'a>>>,
+    pub reflection_weight: f32,
+    // None also under total internal reflection.
+    pub refraction: Option<Box<ShadingTrace<'a>>>,
+    pub refraction_weight: f32,
+    pub color: Color,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightTerm {
+    pub position: Point,
+    pub shadowed: bool,
+    pub color: Color,
+}
+
+impl<'a> ShadingTrace<'a> {
+    // How many rays were traced, this one included.
+    pub fn ray_count(&self) -> usize {
+        let children = self
+            .hit
+            .iter()
+            .flat_map(|h| h.reflection.iter().chain(h.refraction.iter()));
+        1 + children.map(|c| c.ray_count()).sum::<usize>()
+    }
+}
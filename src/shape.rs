@@ -0,0 +1,242 @@
+use crate::bounding_box::BoundingBox;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::stats;
+use crate::transform::Transform;
+use crate::vector::Vector;
+use std::any::Any;
+use std::fmt::Debug;
+
+// Anything that can be placed in a World. Implementors work in object space:
+// local_intersect receives the ray already transformed by the inverse of the
+// shape's transform, and local_normal_at an object-space point.
+#[cfg_attr(feature = "serde", typetag::serde)]
+pub trait Shape: ObjectSpace + Debug + Send + Sync {
+    fn transformation(&self) -> &Transform;
+
+    fn material(&self) -> &Material;
+
+    // For changing a shape's material in place, as animation does.
+    fn material_mut(&mut self) -> &mut Material;
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>>;
+
+    fn local_normal_at(&self, point: Point) -> Vector;
+
+    // For shapes whose normals depend on more of the hit than the point,
+    // such as meshes, which need the face hit and where on it.
+    fn local_hit_normal_at(&self, point: Point, _hit: &Intersection) -> Vector {
+        self.local_normal_at(point)
+    }
+
+    // Bounds in object space, before the shape's own transform.
+    fn bounds(&self) -> BoundingBox;
+
+    fn box_clone(&self) -> Box<dyn Shape>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn shape_eq(&self, other: &dyn Shape) -> bool;
+
+    // Premultiplies the shape's transform by a parent's, placing the shape in
+    // the parent's coordinate space.
+    fn apply_parent_transform(&mut self, parent: &Matrix);
+
+    fn transform(&self) -> &Matrix {
+        self.transformation().matrix()
+    }
+
+    fn parent_space_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.transform())
+    }
+
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        stats::count_intersection_test();
+        let local_ray = ray.transform(self.transformation().inverse().clone());
+        self.local_intersect(&local_ray)
+    }
+
+    fn normal_to_world(&self, normal: Vector) -> Vector {
+        let world_normal = self.transformation().inverse_transpose() * &normal;
+        world_normal.normalize()
+    }
+
+    fn normal_at(&self, point: Point) -> Vector {
+        let local_point = self.world_to_object(point);
+        let local_normal = self.local_normal_at(local_point);
+        let local_normal = self.material().perturb_normal(local_point, local_normal);
+        self.normal_to_world(local_normal)
+    }
+}
+
+// Takes world-space points into a shape's object space, as patterns need.
+// Every shape does; so does a hit on a shape inside groups, undoing the
+// groups' transforms too.
+pub trait ObjectSpace {
+    fn world_to_object(&self, point: Point) -> Point;
+}
+
+impl<T: Shape + ?Sized> ObjectSpace for T {
+    fn world_to_object(&self, point: Point) -> Point {
+        self.transformation().inverse() * &point
+    }
+}
+
+impl Clone for Box<dyn Shape> {
+    fn clone(&self) -> Box<dyn Shape> {
+        self.box_clone()
+    }
+}
+
+impl<'a> PartialEq for dyn Shape + 'a {
+    fn eq(&self, other: &(dyn Shape + 'a)) -> bool {
+        self.shape_eq(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::pattern::StripePattern;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct TestShape {
+        transform: Transform,
+        material: Material,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        saved_ray: Arc<Mutex<Option<Ray>>>,
+    }
+
+    impl TestShape {
+        fn new() -> TestShape {
+            TestShape {
+                transform: Transform::default(),
+                material: Material::default(),
+                saved_ray: Arc::new(Mutex::new(None)),
+            }
+        }
+
+        fn set_transform(mut self, m: Matrix) -> Self {
+            self.transform = Transform::new(m);
+            self
+        }
+    }
+
+    #[cfg_attr(feature = "serde", typetag::serde)]
+    impl Shape for TestShape {
+        fn transformation(&self) -> &Transform {
+            &self.transform
+        }
+
+        fn material(&self) -> &Material {
+            &self.material
+        }
+
+        fn material_mut(&mut self) -> &mut Material {
+            &mut self.material
+        }
+
+        fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+            *self.saved_ray.lock().unwrap() = Some(*ray);
+            vec![]
+        }
+
+        fn local_normal_at(&self, point: Point) -> Vector {
+            Vector::new(point.x, point.y, point.z)
+        }
+
+        fn bounds(&self) -> BoundingBox {
+            BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+        }
+
+        fn box_clone(&self) -> Box<dyn Shape> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn shape_eq(&self, other: &dyn Shape) -> bool {
+            other
+                .as_any()
+                .downcast_ref::<TestShape>()
+                .is_some_and(|o| o.transform == self.transform && o.material == self.material)
+        }
+
+        fn apply_parent_transform(&mut self, parent: &Matrix) {
+            self.transform = Transform::new(parent * self.transform.matrix());
+        }
+    }
+
+    #[test]
+    fn intersecting_scaled_shape_with_ray() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = TestShape::new().set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        s.intersect(&r);
+        let saved = s.saved_ray.lock().unwrap().unwrap();
+        assert_eq!(saved.origin(), Point::new(0.0, 0.0, -2.5));
+        assert_eq!(saved.direction(), Vector::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn intersecting_translated_shape_with_ray() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = TestShape::new().set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        s.intersect(&r);
+        let saved = s.saved_ray.lock().unwrap().unwrap();
+        assert_eq!(saved.origin(), Point::new(-5.0, 0.0, -5.0));
+        assert_eq!(saved.direction(), Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn computing_normal_on_translated_shape() {
+        let s = TestShape::new().set_transform(Matrix::translation(0.0, 1.0, 0.0));
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let n = s.normal_at(Point::new(0.0, 1.0 + x, -x));
+        assert_eq!(n, Vector::new(0.0, x, -x));
+    }
+
+    #[test]
+    fn computing_normal_on_transformed_shape() {
+        let s = TestShape::new().set_transform(
+            Matrix::scaling(1.0, 0.5, 1.0) * Matrix::rotation_z(std::f32::consts::PI / 5.0),
+        );
+        let x = 2.0_f32.sqrt() / 2.0;
+        let n = s.normal_at(Point::new(0.0, x, -x));
+        assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn normal_map_perturbs_world_normal() {
+        let tilt = Color::new(1.0, 0.5, 0.5);
+        let mut s = TestShape::new().set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        s.material = Material::default().set_normal_map(Box::new(StripePattern::new(tilt, tilt)));
+        let n = s.normal_at(Point::new(0.0, 0.0, -2.0));
+        assert_eq!(n, Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn parent_space_bounds_apply_the_shape_transform() {
+        let s = TestShape::new()
+            .set_transform(Matrix::translation(1.0, -3.0, 5.0) * Matrix::scaling(0.5, 2.0, 4.0));
+        let b = s.parent_space_bounds();
+        assert_eq!(b.min(), Point::new(0.5, -5.0, 1.0));
+        assert_eq!(b.max(), Point::new(1.5, -1.0, 9.0));
+    }
+
+    #[test]
+    fn boxed_shapes_compare_by_value() {
+        let a: Box<dyn Shape> = Box::new(TestShape::new());
+        let b = a.clone();
+        let c: Box<dyn Shape> =
+            Box::new(TestShape::new().set_transform(Matrix::translation(1.0, 0.0, 0.0)));
+        assert!(a == b);
+        assert!(a != c);
+    }
+}
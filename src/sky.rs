@@ -0,0 +1,229 @@
+use crate::color::Color;
+use crate::point::Point;
+use crate::point_light::PointLight;
+use crate::vector::Vector;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+// Preetham luminance comes out in kcd/m²; this brings a clear noon sky to
+// roughly 0.5.
+const SKY_SCALE: f32 = 0.05;
+
+// How far away the sun's point light is placed.
+const SUN_DISTANCE: f32 = 1.0e6;
+
+// The Preetham et al. analytic daylight model: sky color in any direction
+// for a given sun position and atmospheric turbidity (2 is very clear, 10
+// hazy). Angles are in radians; azimuth 0 is along +z, turning towards +x.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SunSky {
+    elevation: f32,
+    azimuth: f32,
+    turbidity: f32,
+}
+
+impl SunSky {
+    pub fn new(elevation: f32, azimuth: f32) -> SunSky {
+        SunSky {
+            elevation,
+            azimuth,
+            turbidity: 2.5,
+        }
+    }
+
+    // The sun rises in +x at 6, is highest towards +z at 12 and sets in -x
+    // at 18.
+    pub fn from_time_of_day(hour: f32, max_elevation: f32) -> SunSky {
+        let day = PI * (hour - 6.0) / 12.0;
+        SunSky::new(max_elevation * day.sin(), FRAC_PI_2 - day)
+    }
+
+    pub fn set_turbidity(mut self, turbidity: f32) -> Self {
+        self.turbidity = turbidity.clamp(1.7, 10.0);
+        self
+    }
+
+    pub fn elevation(&self) -> f32 {
+        self.elevation
+    }
+
+    pub fn azimuth(&self) -> f32 {
+        self.azimuth
+    }
+
+    pub fn turbidity(&self) -> f32 {
+        self.turbidity
+    }
+
+    pub fn sun_direction(&self) -> Vector {
+        let (e, a) = (self.elevation, self.azimuth);
+        Vector::new(e.cos() * a.sin(), e.sin(), e.cos() * a.cos())
+    }
+
+    // A distant light in the sun's direction, reddening towards the horizon
+    // and black once the sun has set.
+    pub fn sun(&self) -> PointLight {
+        let d = self.sun_direction() * SUN_DISTANCE;
+        let height = self.elevation.sin().max(0.0);
+        let color = Color::from_temperature(2000.0 + 3800.0 * height.sqrt());
+        PointLight::new(Point::new(d.x, d.y, d.z), color * (height * 4.0).min(1.0))
+    }
+
+    pub fn color_in_direction(&self, direction: Vector) -> Color {
+        let d = direction.normalize();
+        // Below the horizon the model is undefined; show the horizon.
+        let view = Vector::new(d.x, d.y.max(0.001), d.z).normalize();
+        let theta = view.y.acos();
+        let sun = {
+            let s = self.sun_direction();
+            Vector::new(s.x, s.y.max(0.0), s.z).normalize()
+        };
+        let theta_s = sun.y.acos();
+        let gamma = view.dot(&sun).clamp(-1.0, 1.0).acos();
+
+        let t = self.turbidity;
+        let (big_y, x, y) = (
+            zenith_luminance(t, theta_s)
+                * perez_ratio(luminance_coefficients(t), theta, gamma, theta_s),
+            zenith_x(t, theta_s) * perez_ratio(x_coefficients(t), theta, gamma, theta_s),
+            zenith_y(t, theta_s) * perez_ratio(y_coefficients(t), theta, gamma, theta_s),
+        );
+
+        // Fade to black over the few degrees after sunset.
+        let dusk = (1.0 + self.elevation / 0.1).clamp(0.0, 1.0);
+        xyy_to_rgb(x, y, big_y * SKY_SCALE * dusk)
+    }
+}
+
+fn luminance_coefficients(t: f32) -> [f32; 5] {
+    [
+        0.1787 * t - 1.4630,
+        -0.3554 * t + 0.4275,
+        -0.0227 * t + 5.3251,
+        0.1206 * t - 2.5771,
+        -0.0670 * t + 0.3703,
+    ]
+}
+
+fn x_coefficients(t: f32) -> [f32; 5] {
+    [
+        -0.0193 * t - 0.2592,
+        -0.0665 * t + 0.0008,
+        -0.0004 * t + 0.2125,
+        -0.0641 * t - 0.8989,
+        -0.0033 * t + 0.0452,
+    ]
+}
+
+fn y_coefficients(t: f32) -> [f32; 5] {
+    [
+        -0.0167 * t - 0.2608,
+        -0.0950 * t + 0.0092,
+        -0.0079 * t + 0.2102,
+        -0.0441 * t - 1.6537,
+        -0.0109 * t + 0.0529,
+    ]
+}
+
+fn perez(c: [f32; 5], theta: f32, gamma: f32) -> f32 {
+    (1.0 + c[0] * (c[1] / theta.cos().max(0.01)).exp())
+        * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * gamma.cos() * gamma.cos())
+}
+
+// A sky value relative to its value at the zenith.
+fn perez_ratio(c: [f32; 5], theta: f32, gamma: f32, theta_s: f32) -> f32 {
+    perez(c, theta, gamma) / perez(c, 0.0, theta_s)
+}
+
+fn zenith_luminance(t: f32, theta_s: f32) -> f32 {
+    let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+    (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192
+}
+
+fn zenith_x(t: f32, s: f32) -> f32 {
+    let (s2, s3) = (s * s, s * s * s);
+    t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+        + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+        + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886)
+}
+
+fn zenith_y(t: f32, s: f32) -> f32 {
+    let (s2, s3) = (s * s, s * s * s);
+    t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+        + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+        + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688)
+}
+
+// CIE xyY chromaticity and luminance to linear sRGB.
+fn xyy_to_rgb(x: f32, y: f32, big_y: f32) -> Color {
+    let big_x = x / y * big_y;
+    let big_z = (1.0 - x - y) / y * big_y;
+    Color::new(
+        (3.2406 * big_x - 1.5372 * big_y - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * big_y + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * big_y + 1.0570 * big_z).max(0.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::Light;
+    use crate::utils::fp_equal;
+
+    #[test]
+    fn sun_direction_follows_elevation_and_azimuth() {
+        let sky = SunSky::new(FRAC_PI_2, 0.0);
+        assert_eq!(sky.sun_direction(), Vector::new(0.0, 1.0, 0.0));
+        let sky = SunSky::new(0.0, FRAC_PI_2);
+        assert_eq!(sky.sun_direction(), Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn time_of_day_moves_sun_from_east_to_west() {
+        let max = PI / 3.0;
+        let dawn = SunSky::from_time_of_day(6.0, max);
+        let noon = SunSky::from_time_of_day(12.0, max);
+        let dusk = SunSky::from_time_of_day(18.0, max);
+        assert!(fp_equal(dawn.elevation(), 0.0));
+        assert!(fp_equal(noon.elevation(), max));
+        assert!(dusk.elevation().abs() < 0.0001);
+        assert!(dawn.sun_direction().x > 0.99);
+        assert!(dusk.sun_direction().x < -0.99);
+        assert!(SunSky::from_time_of_day(0.0, max).elevation() < 0.0);
+    }
+
+    #[test]
+    fn clear_midday_sky_is_blue() {
+        let sky = SunSky::new(PI / 3.0, 0.0);
+        let zenith = sky.color_in_direction(Vector::new(0.0, 1.0, 0.0));
+        assert!(zenith.blue > zenith.red);
+        assert!(zenith.blue > 0.1 && zenith.blue < 2.0);
+    }
+
+    #[test]
+    fn sky_is_brighter_around_the_sun() {
+        let sky = SunSky::new(PI / 6.0, 0.0);
+        let near = sky.color_in_direction(Vector::new(0.0, 0.6, 1.0));
+        let away = sky.color_in_direction(Vector::new(0.0, 0.6, -1.0));
+        assert!(near.luminance() > away.luminance());
+    }
+
+    #[test]
+    fn sky_and_sun_go_dark_at_night() {
+        let sky = SunSky::new(-0.5, 0.0);
+        assert_eq!(
+            sky.color_in_direction(Vector::new(0.0, 1.0, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+        assert_eq!(sky.sun().intensity(), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn low_sun_is_redder_than_high_sun() {
+        let low = SunSky::new(0.05, 0.0).sun().intensity();
+        let high = SunSky::new(1.2, 0.0).sun().intensity();
+        assert!(low.blue / low.red < high.blue / high.red);
+        assert!(SunSky::new(1.2, 0.0).sun().position().y > 1000.0);
+    }
+}
@@ -1,51 +1,99 @@
+use crate::bounding_box::BoundingBox;
 use crate::material::Material;
 use crate::matrix::Matrix;
 use crate::point::Point;
+use crate::ray::{Intersection, Ray};
+use crate::shape::Shape;
+use crate::transform::Transform;
 use crate::vector::Vector;
+use std::any::Any;
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sphere {
-    matrix: Matrix,
+    transform: Transform,
     pub material: Material,
 }
 
-impl Default for Sphere {
-    fn default() -> Sphere {
+impl Sphere {
+    pub fn new(matrix: Matrix, material: Material) -> Sphere {
         Sphere {
-            matrix: Matrix::identity(),
-            material: Material::default(),
+            transform: Transform::new(matrix),
+            material,
         }
     }
-}
 
-impl Sphere {
-    pub fn new(matrix: Matrix, material: Material) -> Sphere {
-        Sphere { matrix, material }
+    pub fn glass() -> Sphere {
+        Sphere::default().set_material(Material::glass())
+    }
+
+    pub fn set_material(mut self, m: Material) -> Self {
+        self.material = m;
+        self
     }
 
-    pub fn transform(&self) -> &Matrix {
-        &self.matrix
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.transform = Transform::new(m);
+        self
     }
+}
 
-    pub fn material(&self) -> &Material {
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Shape for Sphere {
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn material(&self) -> &Material {
         &self.material
     }
 
-    pub fn set_material(mut self, m: Material) -> Self {
-        self.material = m;
-        self
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
     }
 
-    pub fn set_transform(mut self, m: Matrix) -> Self {
-        self.matrix = m;
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let sphere_to_ray = ray.origin() - Point::new(0.0, 0.0, 0.0);
+        let a = ray.direction().dot(&ray.direction());
+        let b = 2.0 * ray.direction().dot(&sphere_to_ray);
+        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
+        let discriminant = b.powi(2) - 4.0 * a * c;
+
+        if discriminant < 0.0 {
+            return vec![];
+        }
+
+        let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
+        let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
+
+        vec![Intersection::new(t1, self), Intersection::new(t2, self)]
+    }
+
+    fn local_normal_at(&self, point: Point) -> Vector {
+        point - Point::new(0.0, 0.0, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
+    fn box_clone(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
         self
     }
 
-    pub fn normal_at(&self, p: Point) -> Vector {
-        let object_point = self.transform().inverse() * p;
-        let object_normal = object_point - Point::new(0.0, 0.0, 0.0);
-        let world_normal = self.transform().inverse().transpose() * object_normal;
-        world_normal.normalize()
+    fn shape_eq(&self, other: &dyn Shape) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Sphere>()
+            .is_some_and(|o| self == o)
+    }
+
+    fn apply_parent_transform(&mut self, parent: &Matrix) {
+        self.transform = Transform::new(parent * self.transform.matrix());
     }
 }
 
@@ -126,8 +174,9 @@ mod tests {
     #[test]
     fn computinog_normal_on_translated_sphere() {
         let s = Sphere::default().set_transform(Matrix::translation(0.0, 1.0, 0.0));
-        let n = s.normal_at(Point::new(0.0, 1.70711, -0.70711));
-        assert!(n == Vector::new(0.0, 0.70711, -0.70711));
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let n = s.normal_at(Point::new(0.0, 1.0 + x, -x));
+        assert!(n == Vector::new(0.0, x, -x));
     }
 
     #[test]
@@ -154,4 +203,12 @@ mod tests {
         let s = Sphere::default().set_material(m);
         assert!(s.material() == &m1);
     }
+
+    #[test]
+    fn helper_for_a_glassy_sphere() {
+        let s = Sphere::glass();
+        assert!(*s.transform() == Matrix::identity());
+        assert!(fp_equal(s.material.transparency, 0.9));
+        assert!(fp_equal(s.material.refractive_index, 1.5));
+    }
 }
@@ -0,0 +1,177 @@
+use crate::color::Color;
+use crate::light::{Attenuation, Light};
+use crate::point::Point;
+use crate::vector::Vector;
+use std::any::Any;
+
+// A light shining a cone from position along direction. angle is the cone's
+// half-angle in radians; the last `falloff` radians inside its edge fade
+// smoothly to nothing.
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpotLight {
+    position: Point,
+    direction: Vector,
+    intensity: Color,
+    angle: f32,
+    falloff: f32,
+    attenuation: Attenuation,
+}
+
+impl SpotLight {
+    pub fn new(position: Point, direction: Vector, intensity: Color, angle: f32) -> SpotLight {
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            intensity,
+            angle,
+            falloff: 0.0,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn set_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff.clamp(0.0, self.angle);
+        self
+    }
+
+    pub fn set_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn direction(&self) -> Vector {
+        self.direction
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    pub fn falloff(&self) -> f32 {
+        self.falloff
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Light for SpotLight {
+    fn position(&self) -> Point {
+        self.position
+    }
+
+    fn intensity_at(&self, pos: Point) -> Color {
+        let v = pos - self.position;
+        let cos_theta = v.normalize().dot(&self.direction);
+        let outer = self.angle.cos();
+        let inner = (self.angle - self.falloff).cos();
+        let cone = if cos_theta >= inner {
+            1.0
+        } else if cos_theta <= outer {
+            0.0
+        } else {
+            let t = (cos_theta - outer) / (inner - outer);
+            t * t * (3.0 - 2.0 * t)
+        };
+        self.intensity * (cone * self.attenuation.factor(v.magnitude()))
+    }
+
+    fn box_clone(&self) -> Box<dyn Light> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn light_eq(&self, other: &dyn Light) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<SpotLight>()
+            .is_some_and(|o| self == o)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Material;
+    use crate::sphere::Sphere;
+    use std::f32::consts::PI;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn straight_down(angle: f32) -> SpotLight {
+        SpotLight::new(
+            Point::new(0.0, 10.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+            white(),
+            angle,
+        )
+    }
+
+    #[test]
+    fn spot_light_is_full_inside_cone_and_dark_outside() {
+        let light = straight_down(PI / 4.0);
+        assert_eq!(light.intensity_at(Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(light.intensity_at(Point::new(9.0, 0.0, 0.0)), white());
+        assert_eq!(
+            light.intensity_at(Point::new(11.0, 0.0, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn spot_light_edge_fades_smoothly() {
+        let light = straight_down(PI / 4.0).set_falloff(PI / 8.0);
+        let mid = (PI / 4.0 - PI / 16.0).tan() * 10.0;
+        let c = light.intensity_at(Point::new(mid, 0.0, 0.0));
+        assert!(c.red > 0.0 && c.red < 1.0);
+        let inner = (PI / 8.0).tan() * 10.0 - 0.01;
+        assert_eq!(light.intensity_at(Point::new(0.0, 0.0, inner)), white());
+    }
+
+    #[test]
+    fn spot_light_attenuates_with_distance() {
+        let light = straight_down(PI / 4.0).set_attenuation(Attenuation::Linear { radius: 20.0 });
+        assert_eq!(
+            light.intensity_at(Point::new(0.0, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn lighting_outside_spot_cone_is_black() {
+        let light = straight_down(PI / 8.0);
+        let m = Material::default();
+        let eye = Vector::new(0.0, 1.0, 0.0);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let lit = light.lighting(
+            &m,
+            &Sphere::default(),
+            Point::new(0.0, 0.0, 0.0),
+            eye,
+            normal,
+            false,
+        );
+        let unlit = light.lighting(
+            &m,
+            &Sphere::default(),
+            Point::new(10.0, 0.0, 0.0),
+            eye,
+            normal,
+            false,
+        );
+        assert_eq!(lit, Color::new(1.9, 1.9, 1.9));
+        assert_eq!(unlit, Color::new(0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,152 @@
+use std::cell::Cell;
+use std::ops::{Add, Sub};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+// Work done during a render, for measuring performance.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    // Rays traced through the world: camera rays, shadow rays and bounces.
+    pub rays_cast: u64,
+    // Ray-shape tests, counting each child of a group that gets tested.
+    pub intersection_tests: u64,
+    // Rays reflected, refracted or bounced off a surface, a subset of
+    // rays_cast.
+    pub bounces: u64,
+    // Times one light's contribution to a surface point was worked out.
+    pub shading_calls: u64,
+    pub elapsed: Duration,
+}
+
+impl RenderStats {
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            0.0
+        } else {
+            self.rays_cast as f64 / seconds
+        }
+    }
+}
+
+impl Add for RenderStats {
+    type Output = RenderStats;
+
+    fn add(self, other: RenderStats) -> RenderStats {
+        RenderStats {
+            rays_cast: self.rays_cast + other.rays_cast,
+            intersection_tests: self.intersection_tests + other.intersection_tests,
+            bounces: self.bounces + other.bounces,
+            shading_calls: self.shading_calls + other.shading_calls,
+            elapsed: self.elapsed + other.elapsed,
+        }
+    }
+}
+
+impl Sub for RenderStats {
+    type Output = RenderStats;
+
+    fn sub(self, other: RenderStats) -> RenderStats {
+        RenderStats {
+            rays_cast: self.rays_cast - other.rays_cast,
+            intersection_tests: self.intersection_tests - other.intersection_tests,
+            bounces: self.bounces - other.bounces,
+            shading_calls: self.shading_calls - other.shading_calls,
+            elapsed: self.elapsed.saturating_sub(other.elapsed),
+        }
+    }
+}
+
+// Times a render. std has no clock on wasm32-unknown-unknown, so renders
+// there go untimed.
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Stopwatch {
+        Stopwatch {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: Instant::now(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+// Counts are kept per thread so that counting costs no synchronisation, and
+// so renders running side by side don't see each other's work. A render
+// takes the difference in its own threads' counts from before to after.
+thread_local! {
+    static COUNTS: Cell<RenderStats> = Cell::new(RenderStats::default());
+}
+
+fn count(update: fn(&mut RenderStats)) {
+    COUNTS.with(|counts| {
+        let mut c = counts.get();
+        update(&mut c);
+        counts.set(c);
+    });
+}
+
+pub(crate) fn count_ray() {
+    count(|c| c.rays_cast += 1);
+}
+
+pub(crate) fn count_intersection_test() {
+    count(|c| c.intersection_tests += 1);
+}
+
+pub(crate) fn count_bounce() {
+    count(|c| c.bounces += 1);
+}
+
+pub(crate) fn count_shading() {
+    count(|c| c.shading_calls += 1);
+}
+
+// Everything counted on this thread so far.
+pub(crate) fn thread_counts() -> RenderStats {
+    COUNTS.with(Cell::get)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting_on_this_thread() {
+        let before = thread_counts();
+        count_ray();
+        count_intersection_test();
+        count_intersection_test();
+        count_bounce();
+        count_shading();
+        let counted = thread_counts() - before;
+        assert_eq!(counted.rays_cast, 1);
+        assert_eq!(counted.intersection_tests, 2);
+        assert_eq!(counted.bounces, 1);
+        assert_eq!(counted.shading_calls, 1);
+    }
+
+    #[test]
+    fn rays_per_second() {
+        let stats = RenderStats {
+            rays_cast: 500,
+            elapsed: Duration::from_millis(250),
+            ..RenderStats::default()
+        };
+        assert_eq!(stats.rays_per_second(), 2000.0);
+        assert_eq!(RenderStats::default().rays_per_second(), 0.0);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::group::Group;
+use crate::mesh::Mesh;
+use crate::point::Point;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Triangles read from an STL file, in either the binary or the ASCII form.
+// The per-facet normals STL stores are ignored; many exporters write zeros
+// there and the mesh works out face normals from the vertices anyway.
+#[derive(Clone, Debug, Default)]
+pub struct StlFile {
+    name: String,
+    triangles: Vec<[Point; 3]>,
+}
+
+impl StlFile {
+    // A file is taken as binary when its length matches the triangle count
+    // in its header, since binary files may also begin with "solid".
+    pub fn parse(bytes: &[u8]) -> Result<StlFile, String> {
+        if bytes.len() >= 84 {
+            let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+            if bytes.len() == 84 + count * 50 {
+                return Ok(StlFile::parse_binary(bytes, count));
+            }
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(text) if text.trim_start().starts_with("solid") => StlFile::parse_ascii(text),
+            _ => Err(String::from("not a binary or ASCII STL file")),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<StlFile> {
+        StlFile::parse(&fs::read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn triangles(&self) -> &[[Point; 3]] {
+        &self.triangles
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    // Shared corners are merged so the mesh stores each position once.
+    pub fn to_mesh(&self) -> Mesh {
+        let mut lookup: HashMap<[u32; 3], u32> = HashMap::new();
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        for p in self.triangles.iter().flatten() {
+            let key = [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()];
+            let index = *lookup.entry(key).or_insert_with(|| {
+                vertices.push(*p);
+                vertices.len() as u32 - 1
+            });
+            indices.push(index);
+        }
+        Mesh::new(vertices, indices)
+    }
+
+    pub fn to_group(&self) -> Group {
+        Group::default().add_child(Box::new(self.to_mesh()))
+    }
+
+    fn parse_binary(bytes: &[u8], count: usize) -> StlFile {
+        let name = String::from_utf8_lossy(&bytes[..80])
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+        let float = |at: usize| {
+            f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        let point = |at: usize| Point::new(float(at), float(at + 4), float(at + 8));
+        let triangles = (0..count)
+            .map(|i| {
+                // Skip the 12-byte normal; the 2-byte attribute follows the
+                // three vertices.
+                let at = 84 + i * 50 + 12;
+                [point(at), point(at + 12), point(at + 24)]
+            })
+            .collect();
+        StlFile { name, triangles }
+    }
+
+    fn parse_ascii(text: &str) -> Result<StlFile, String> {
+        let mut lines = text.lines().enumerate();
+        let name = lines
+            .next()
+            .map(|(_, l)| l.trim().trim_start_matches("solid").trim().to_string())
+            .unwrap_or_default();
+        let mut corners = vec![];
+        for (n, line) in lines {
+            let mut words = line.split_whitespace();
+            if words.next() != Some("vertex") {
+                continue;
+            }
+            let coords: Result<Vec<f32>, _> = words.map(|w| w.parse::<f32>()).collect();
+            match coords.as_deref() {
+                Ok([x, y, z]) => corners.push(Point::new(*x, *y, *z)),
+                _ => return Err(format!("line {}: malformed vertex", n + 1)),
+            }
+        }
+        if corners.len() % 3 != 0 {
+            return Err(String::from("vertex count is not a multiple of three"));
+        }
+        let triangles = corners.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+        Ok(StlFile { name, triangles })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+    use crate::shape::Shape;
+    use crate::vector::Vector;
+
+    const ASCII: &str = "solid square
+facet normal 0 0 -1
+  outer loop
+    vertex -1 1 0
+    vertex -1 -1 0
+    vertex 1 -1 0
+  endloop
+endfacet
+facet normal 0 0 -1
+  outer loop
+    vertex -1 1 0
+    vertex 1 -1 0
+    vertex 1 1 0
+  endloop
+endfacet
+endsolid square
+";
+
+    fn binary(name: &str, triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut bytes = vec![0; 80];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for tri in triangles {
+            bytes.extend_from_slice(&[0; 12]);
+            for v in tri.iter().flatten() {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+            bytes.extend_from_slice(&[0; 2]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn parsing_ascii_stl() {
+        let stl = StlFile::parse(ASCII.as_bytes()).unwrap();
+        assert_eq!(stl.name(), "square");
+        assert_eq!(stl.triangle_count(), 2);
+        assert_eq!(stl.triangles()[1][2], Point::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn parsing_binary_stl() {
+        let bytes = binary(
+            "solid but binary",
+            &[[[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]]],
+        );
+        let stl = StlFile::parse(&bytes).unwrap();
+        assert_eq!(stl.name(), "solid but binary");
+        assert_eq!(stl.triangle_count(), 1);
+        assert_eq!(stl.triangles()[0][0], Point::new(0.0, 1.0, 0.0));
+        assert_eq!(stl.triangles()[0][2], Point::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn shared_corners_are_merged_in_the_mesh() {
+        let mesh = StlFile::parse(ASCII.as_bytes()).unwrap().to_mesh();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.vertices().len(), 4);
+    }
+
+    #[test]
+    fn stl_group_can_be_intersected() {
+        let g = StlFile::parse(ASCII.as_bytes()).unwrap().to_group();
+        let r = Ray::new(Point::new(0.5, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).len(), 1);
+    }
+
+    #[test]
+    fn malformed_stl_is_rejected() {
+        assert!(StlFile::parse(b"not an stl").is_err());
+        assert!(StlFile::parse(b"solid x\nvertex 1 2\n").is_err());
+        assert!(StlFile::parse(b"solid x\nvertex 1 2 3\n").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::group::Group;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::obj::ObjFile;
+use crate::pattern::CheckerPattern;
+use crate::point::Point;
+use crate::point_light::PointLight;
+use crate::rectangle::Rectangle;
+use crate::scene::Scene;
+use crate::shape::Shape;
+use crate::sphere::Sphere;
+use crate::vector::Vector;
+use crate::world::World;
+use std::f32::consts::PI;
+
+// Well-known scenes for checking renders against references. Each comes
+// with a camera of the given size framing it, set up for the Whitted
+// renderer; switch to Renderer::PathTraced with plenty of samples to check
+// global illumination.
+
+fn matte(color: Color) -> Material {
+    Material::default().set_color(color).set_specular(0.0)
+}
+
+fn wall(material: &Material, transform: Matrix) -> Box<dyn Shape> {
+    Box::new(
+        Rectangle::new(2.0, 2.0)
+            .set_material(material.clone())
+            .set_transform(transform),
+    )
+}
+
+// The Cornell box: a white room two units on a side with a red wall on the
+// left, a green one on the right, a square light in the ceiling and two
+// white blocks on the floor. The room's floor is at y = 0, and its open
+// front faces the camera down +z.
+pub fn cornell_box(width: u32, height: u32) -> Scene {
+    let white = matte(Color::new(0.73, 0.73, 0.73));
+    let red = matte(Color::new(0.65, 0.05, 0.05));
+    let green = matte(Color::new(0.12, 0.45, 0.15));
+    let lamp = Material::default()
+        .set_color(Color::new(0.0, 0.0, 0.0))
+        .set_emissive(Color::new(15.0, 15.0, 15.0));
+    let objects = vec![
+        wall(&white, Matrix::identity()),
+        wall(
+            &white,
+            Matrix::translation(0.0, 2.0, 0.0) * Matrix::rotation_x(PI),
+        ),
+        wall(
+            &white,
+            Matrix::translation(0.0, 1.0, 1.0) * Matrix::rotation_x(-PI / 2.0),
+        ),
+        wall(
+            &red,
+            Matrix::translation(-1.0, 1.0, 0.0) * Matrix::rotation_z(-PI / 2.0),
+        ),
+        wall(
+            &green,
+            Matrix::translation(1.0, 1.0, 0.0) * Matrix::rotation_z(PI / 2.0),
+        ),
+        Box::new(
+            Rectangle::new(0.5, 0.5)
+                .set_material(lamp)
+                .set_transform(Matrix::translation(0.0, 1.99, 0.0) * Matrix::rotation_x(PI)),
+        ),
+        Box::new(Cube::default().set_material(white.clone()).set_transform(
+            Matrix::translation(-0.35, 0.6, 0.3)
+                * Matrix::rotation_y(0.3)
+                * Matrix::scaling(0.3, 0.6, 0.3),
+        )),
+        Box::new(Cube::default().set_material(white).set_transform(
+            Matrix::translation(0.35, 0.3, -0.3)
+                * Matrix::rotation_y(-0.3)
+                * Matrix::scaling(0.3, 0.3, 0.3),
+        )),
+    ];
+    // Just under the lamp, for renderers that only see point lights.
+    let light = PointLight::new(Point::new(0.0, 1.9, 0.0), Color::new(1.0, 1.0, 1.0));
+    let camera = Camera::new(width, height, 0.7).look_at(
+        Point::new(0.0, 1.0, -3.5),
+        Point::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    Scene {
+        camera,
+        world: World::new(objects, Box::new(light)),
+    }
+}
+
+// A black and white checked floor, in unit squares, stretching ten units
+// each way from the origin.
+fn checker_floor() -> Box<dyn Shape> {
+    let checks = CheckerPattern::new(Color::new(1.0, 1.0, 1.0), Color::new(0.1, 0.1, 0.1))
+        // Off the boundary between checks that the floor lies on.
+        .set_transform(Matrix::translation(0.0, 0.5, 0.0));
+    let material = matte(Color::new(1.0, 1.0, 1.0)).set_pattern(Box::new(checks));
+    Box::new(Rectangle::new(20.0, 20.0).set_material(material))
+}
+
+fn outdoor_light() -> Box<PointLight> {
+    Box::new(PointLight::new(
+        Point::new(-10.0, 10.0, -10.0),
+        Color::new(1.0, 1.0, 1.0),
+    ))
+}
+
+// A glass sphere of radius one resting on a checked floor, for checking
+// refraction: the checks seen through it should be shrunk and upside down.
+pub fn glass_sphere(width: u32, height: u32) -> Scene {
+    let sphere = Sphere::glass().set_transform(Matrix::translation(0.0, 1.0, 0.0));
+    let camera = Camera::new(width, height, PI / 3.0).look_at(
+        Point::new(0.0, 2.0, -5.0),
+        Point::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    Scene {
+        camera,
+        world: World::new(vec![checker_floor(), Box::new(sphere)], outdoor_light()),
+    }
+}
+
+// Any OBJ model, such as the Utah teapot, scaled to two units across,
+// turned to stand with +z up as most models are drawn, and set on a
+// checked floor.
+pub fn teapot(obj: &ObjFile, width: u32, height: u32) -> Scene {
+    let material = Material::default().set_color(Color::new(0.8, 0.3, 0.2));
+    let model = obj.group_names().iter().fold(Group::default(), |g, name| {
+        let mesh = obj.to_mesh(name).unwrap().set_material(material.clone());
+        g.add_child(Box::new(mesh))
+    });
+    let upright = Matrix::rotation_x(-PI / 2.0);
+    let bounds = model.bounds().transform(&upright);
+    let (min, max) = (bounds.min(), bounds.max());
+    let size = (max.x - min.x).max(max.y - min.y).max(max.z - min.z);
+    let scale = if size > 0.0 { 2.0 / size } else { 1.0 };
+    let center = Point::new((min.x + max.x) / 2.0, min.y, (min.z + max.z) / 2.0);
+    let fit = Matrix::scaling(scale, scale, scale)
+        * Matrix::translation(-center.x, -center.y, -center.z)
+        * upright;
+    let camera = Camera::new(width, height, PI / 3.0).look_at(
+        Point::new(0.0, 2.5, -4.5),
+        Point::new(0.0, 0.6, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    let objects = vec![
+        checker_floor(),
+        Box::new(model.set_transform(fit)) as Box<dyn Shape>,
+    ];
+    Scene {
+        camera,
+        world: World::new(objects, outdoor_light()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looking_into_the_cornell_box() {
+        let scene = cornell_box(20, 20);
+        assert_eq!(scene.world.objects().len(), 8);
+        // The back wall, between the blocks.
+        let (id, point) = scene.camera.pick(&scene.world, 10, 6).unwrap();
+        assert_eq!(id, scene.world.object_ids()[2]);
+        assert!((point.z - 1.0).abs() < 0.001);
+        let image = scene.camera.render(scene.world);
+        let (left, right) = (image.pixel_at(1, 10), image.pixel_at(18, 10));
+        assert!(left.red > left.green && right.green > right.red);
+    }
+
+    #[test]
+    fn glass_sphere_on_a_checked_floor() {
+        let scene = glass_sphere(20, 20);
+        let sphere = scene.world.objects()[1].material();
+        assert_eq!(sphere.refractive_index, 1.5);
+        let (id, _) = scene.camera.pick(&scene.world, 10, 10).unwrap();
+        assert_eq!(id, scene.world.object_ids()[1]);
+        let (id, _) = scene.camera.pick(&scene.world, 10, 19).unwrap();
+        assert_eq!(id, scene.world.object_ids()[0]);
+    }
+
+    #[test]
+    fn fitting_a_model_on_the_floor() {
+        // A tetrahedron 4 units across, drawn with +z up.
+        let obj = ObjFile::parse(
+            "v 0 0 0\nv 4 0 0\nv 0 4 0\nv 0 0 4\nf 1 3 2\nf 1 2 4\nf 1 4 3\nf 2 3 4\n",
+        );
+        let scene = teapot(&obj, 20, 20);
+        let model = &scene.world.objects()[1];
+        let bounds = model.parent_space_bounds();
+        assert_eq!(bounds.min(), Point::new(-1.0, 0.0, -1.0));
+        assert_eq!(bounds.max(), Point::new(1.0, 2.0, 1.0));
+        assert!(scene.camera.pick(&scene.world, 10, 10).is_some());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::uv::UvPattern;
+use std::any::Any;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+// An image sampled over the unit square, for use with a TextureMap. v runs
+// up the image, so (0, 0) is the bottom-left pixel.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageTexture {
+    // Shared, so that cloning the material holding it doesn't copy the
+    // image.
+    canvas: Arc<Canvas>,
+}
+
+impl ImageTexture {
+    // Takes a Canvas, or an Arc<Canvas> to share one image between textures.
+    pub fn new(canvas: impl Into<Arc<Canvas>>) -> ImageTexture {
+        let canvas = canvas.into();
+        assert!(canvas.width > 0 && canvas.height > 0, "empty texture");
+        ImageTexture { canvas }
+    }
+
+    // PPM and Radiance HDR files are always understood; PNG and JPEG need
+    // the `image` feature. The format is taken from the file's contents.
+    pub fn parse(bytes: &[u8]) -> Result<ImageTexture, String> {
+        let canvas = if bytes.starts_with(b"P3") || bytes.starts_with(b"P6") {
+            Canvas::from_ppm(bytes)?
+        } else if bytes.starts_with(b"#?") {
+            Canvas::from_hdr(bytes)?
+        } else {
+            decode_image(bytes)?
+        };
+        if canvas.width == 0 || canvas.height == 0 {
+            return Err(String::from("image has no pixels"));
+        }
+        Ok(ImageTexture::new(canvas))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<ImageTexture> {
+        ImageTexture::parse(&fs::read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl UvPattern for ImageTexture {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Color {
+        let v = 1.0 - v;
+        let x = u.clamp(0.0, 1.0) * (self.canvas.width - 1) as f32;
+        let y = v.clamp(0.0, 1.0) * (self.canvas.height - 1) as f32;
+        self.canvas.pixel_at(x.round() as usize, y.round() as usize)
+    }
+
+    fn box_clone(&self) -> Box<dyn UvPattern> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn uv_pattern_eq(&self, other: &dyn UvPattern) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<ImageTexture>()
+            .is_some_and(|o| self == o)
+    }
+}
+
+#[cfg(feature = "image")]
+fn decode_image(bytes: &[u8]) -> Result<Canvas, String> {
+    Canvas::decode_image(bytes)
+}
+
+#[cfg(not(feature = "image"))]
+fn decode_image(_bytes: &[u8]) -> Result<Canvas, String> {
+    Err(String::from(
+        "only PPM textures are supported without the `image` feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::Pattern;
+    use crate::point::Point;
+    use crate::uv::{Mapping, TextureMap};
+
+    fn corners() -> ImageTexture {
+        let ppm = b"P3\n2 2\n255\n255 0 0  0 255 0\n0 0 255  255 255 255\n";
+        ImageTexture::parse(ppm).unwrap()
+    }
+
+    #[test]
+    fn image_texture_samples_nearest_pixel() {
+        let t = corners();
+        assert_eq!(t.uv_pattern_at(0.0, 1.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(t.uv_pattern_at(1.0, 1.0), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(t.uv_pattern_at(0.2, 0.1), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(t.uv_pattern_at(0.9, 0.0), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn clones_share_the_image() {
+        let t = corners();
+        assert!(std::ptr::eq(t.canvas(), t.clone().canvas()));
+        let shared = Arc::new(t.canvas().clone());
+        let (a, b) = (ImageTexture::new(shared.clone()), ImageTexture::new(shared));
+        assert!(std::ptr::eq(a.canvas(), b.canvas()));
+    }
+
+    #[test]
+    fn image_texture_on_a_sphere() {
+        let pattern = TextureMap::new(Box::new(corners()), Mapping::Spherical);
+        assert_eq!(
+            pattern.pattern_at(Point::new(0.0, 1.0, 0.0)),
+            Color::new(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            pattern.pattern_at(Point::new(0.0, -1.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn unrecognised_images_are_rejected() {
+        assert!(ImageTexture::parse(b"GIF89a").is_err());
+        assert!(ImageTexture::parse(b"P3\n0 0\n255\n").is_err());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn loading_png_texture() {
+        let mut bytes = vec![];
+        let img = image::RgbImage::from_raw(2, 1, vec![255, 0, 0, 0, 0, 255]).unwrap();
+        img.write_to(
+            &mut io::Cursor::new(&mut bytes),
+            image::ImageOutputFormat::Png,
+        )
+        .unwrap();
+        let t = ImageTexture::parse(&bytes).unwrap();
+        assert_eq!(t.uv_pattern_at(1.0, 0.0), Color::new(0.0, 0.0, 1.0));
+    }
+}
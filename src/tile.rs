@@ -0,0 +1,65 @@
+// A rectangle of pixels rendered as one unit of work.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Tile {
+        Tile {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn pixel_count(&self) -> u32 {
+        self.width * self.height
+    }
+
+    // Every pixel in the tile, a row at a time.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> {
+        let tile = *self;
+        (tile.y..tile.y + tile.height)
+            .flat_map(move |y| (tile.x..tile.x + tile.width).map(move |x| (x, y)))
+    }
+}
+
+// Covers a width x height image with size x size tiles, row by row. Tiles on
+// the right and bottom edges are cut short to fit.
+pub fn tiles(width: u32, height: u32, size: u32) -> Vec<Tile> {
+    let size = size.max(1);
+    let mut tiles = vec![];
+    for y in (0..height).step_by(size as usize) {
+        for x in (0..width).step_by(size as usize) {
+            tiles.push(Tile::new(x, y, size.min(width - x), size.min(height - y)));
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_cover_image_exactly_once() {
+        let ts = tiles(10, 7, 4);
+        assert_eq!(ts.len(), 6);
+        assert_eq!(ts[0], Tile::new(0, 0, 4, 4));
+        assert_eq!(ts[2], Tile::new(8, 0, 2, 4));
+        assert_eq!(ts[5], Tile::new(8, 4, 2, 3));
+        let total: u32 = ts.iter().map(|t| t.pixel_count()).sum();
+        assert_eq!(total, 70);
+    }
+
+    #[test]
+    fn tile_pixels_go_row_by_row() {
+        let pixels: Vec<(u32, u32)> = Tile::new(2, 3, 2, 2).pixels().collect();
+        assert_eq!(pixels, vec![(2, 3), (3, 3), (2, 4), (3, 4)]);
+    }
+}
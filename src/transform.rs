@@ -0,0 +1,217 @@
+use crate::matrix::{Matrix, MatrixError};
+use crate::quaternion::Quaternion;
+use crate::utils::fp_equal;
+use crate::vector::Vector;
+use std::convert::TryFrom;
+
+// A shape's transform, along with its inverse and the inverse's transpose.
+// Every ray is moved into object space by the inverse, and every normal back
+// out by the transpose, so both are worked out once when the transform is
+// set rather than for each ray. Only the matrix itself is serialized.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Matrix", into = "Matrix")
+)]
+pub struct Transform {
+    matrix: Matrix,
+    inverse: Matrix,
+    inverse_transpose: Matrix,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix) -> Transform {
+        Transform::try_new(matrix).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(matrix: Matrix) -> Result<Transform, MatrixError> {
+        let inverse = matrix.try_inverse()?;
+        Ok(Transform {
+            matrix,
+            inverse_transpose: inverse.clone().transpose(),
+            inverse,
+        })
+    }
+
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Matrix {
+        &self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> &Matrix {
+        &self.inverse_transpose
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::new(Matrix::identity())
+    }
+}
+
+// Fails for matrices with no inverse, so that a scene file with one is
+// rejected when it's read rather than panicking.
+impl TryFrom<Matrix> for Transform {
+    type Error = MatrixError;
+
+    fn try_from(matrix: Matrix) -> Result<Transform, MatrixError> {
+        Transform::try_new(matrix)
+    }
+}
+
+impl From<Transform> for Matrix {
+    fn from(transform: Transform) -> Matrix {
+        transform.matrix
+    }
+}
+
+// A transform taken apart into scaling, then rotation, then translation,
+// which can be blended piece by piece. Blending the matrices themselves
+// element by element shrinks whatever is turning part way through.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Decomposed {
+    pub translation: Vector,
+    pub rotation: Quaternion,
+    // Negative along x for transforms that mirror.
+    pub scale: Vector,
+}
+
+impl Decomposed {
+    // None for transforms that shear, flatten or project, which have no such
+    // parts.
+    pub fn from_matrix(m: &Matrix) -> Option<Decomposed> {
+        let bottom = [m.get(3, 0), m.get(3, 1), m.get(3, 2), m.get(3, 3)];
+        if bottom != [0.0, 0.0, 0.0, 1.0] {
+            return None;
+        }
+        let column = |c| Vector::new(m.get(0, c), m.get(1, c), m.get(2, c));
+        let (x, y, z) = (column(0), column(1), column(2));
+        let mut scale = Vector::new(x.magnitude(), y.magnitude(), z.magnitude());
+        if [scale.x, scale.y, scale.z]
+            .iter()
+            .any(|&s| fp_equal(s, 0.0))
+        {
+            return None;
+        }
+        if x.dot(&y.cross(&z)) < 0.0 {
+            scale.x = -scale.x;
+        }
+        let (x, y, z) = (x / scale.x, y / scale.y, z / scale.z);
+        let perpendicular = |a: Vector, b: Vector| a.dot(&b).abs() < 0.0001;
+        if !(perpendicular(x, y) && perpendicular(x, z) && perpendicular(y, z)) {
+            return None;
+        }
+        let rotation = Matrix::new_filled(&[
+            &[x.x, y.x, z.x, 0.0],
+            &[x.y, y.y, z.y, 0.0],
+            &[x.z, y.z, z.z, 0.0],
+            &[0.0, 0.0, 0.0, 1.0],
+        ]);
+        Some(Decomposed {
+            translation: column(3),
+            rotation: Quaternion::from_matrix(&rotation),
+            scale,
+        })
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        let t = self.translation;
+        let s = self.scale;
+        Matrix::translation(t.x, t.y, t.z)
+            * self.rotation.to_matrix()
+            * Matrix::scaling(s.x, s.y, s.z)
+    }
+
+    // Translation and scale move in straight lines; rotation turns at a steady
+    // rate.
+    pub fn lerp(&self, other: &Decomposed, t: f32) -> Decomposed {
+        Decomposed {
+            translation: self.translation.lerp(&other.translation, t),
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: self.scale.lerp(&other.scale, t),
+        }
+    }
+}
+
+// From at t = 0 to to at t = 1, through their decomposed parts where both
+// have them, and element by element where either doesn't.
+pub fn interpolate(from: &Matrix, to: &Matrix, t: f32) -> Matrix {
+    match (Decomposed::from_matrix(from), Decomposed::from_matrix(to)) {
+        (Some(a), Some(b)) => a.lerp(&b, t).to_matrix(),
+        _ => from.lerp(to, t),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn transform_keeps_its_inverses() {
+        let m = Matrix::translation(1.0, 2.0, 3.0) * Matrix::scaling(2.0, 4.0, 8.0);
+        let t = Transform::new(m.clone());
+        assert_eq!(t.matrix(), &m);
+        assert_eq!(t.inverse(), &m.inverse());
+        assert_eq!(t.inverse_transpose(), &m.inverse().transpose());
+        assert_eq!(Transform::default().inverse(), &Matrix::identity());
+        let flat = Transform::try_new(Matrix::scaling(0.0, 1.0, 1.0));
+        assert_eq!(flat, Err(MatrixError::NotInvertible));
+    }
+
+    #[test]
+    fn small_scales_make_a_transform() {
+        let t = Transform::new(Matrix::scaling(0.02, 0.02, 0.02));
+        assert_eq!(t.inverse(), &Matrix::scaling(50.0, 50.0, 50.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializing_a_singular_transform_fails() {
+        let m = Matrix::translation(1.0, 2.0, 3.0);
+        let json = serde_json::to_string(&Transform::new(m.clone())).unwrap();
+        let back: Transform = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.inverse(), &m.inverse());
+        let flat = serde_json::to_string(&Matrix::scaling(0.0, 1.0, 1.0)).unwrap();
+        assert!(serde_json::from_str::<Transform>(&flat).is_err());
+    }
+
+    #[test]
+    fn decomposing_and_rebuilding_a_transform() {
+        let m = Matrix::translation(1.0, -2.0, 3.0)
+            * Matrix::rotation_axis(Vector::new(1.0, 2.0, 0.5), 2.0)
+            * Matrix::scaling(2.0, 0.5, 3.0);
+        let d = Decomposed::from_matrix(&m).unwrap();
+        assert_eq!(d.translation, Vector::new(1.0, -2.0, 3.0));
+        assert_eq!(d.scale, Vector::new(2.0, 0.5, 3.0));
+        assert_eq!(d.to_matrix(), m);
+
+        let mirrored = Matrix::rotation_z(0.5) * Matrix::scaling(1.0, 1.0, -1.0);
+        assert_eq!(
+            Decomposed::from_matrix(&mirrored).unwrap().to_matrix(),
+            mirrored
+        );
+        assert!(Decomposed::from_matrix(&Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn interpolating_transforms() {
+        let from = Matrix::translation(0.0, 0.0, 0.0);
+        let to = Matrix::translation(4.0, 0.0, 0.0) * Matrix::rotation_y(PI / 2.0);
+        let halfway = interpolate(&from, &to, 0.5);
+        assert_eq!(
+            halfway,
+            Matrix::translation(2.0, 0.0, 0.0) * Matrix::rotation_y(PI / 4.0)
+        );
+        assert_eq!(interpolate(&from, &to, 1.0), to);
+
+        // Shears can't be taken apart, so they're blended element by element.
+        let shear = Matrix::shearing(2.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        let blended = interpolate(&Matrix::identity(), &shear, 0.5);
+        assert_eq!(blended, Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0));
+    }
+}
@@ -1,4 +1,134 @@
+// How far over_point and under_point are moved off a surface along its
+// normal, so that rays leaving a hit don't find the surface again. Scenes
+// much larger or smaller than a few units may need a different value; see
+// World::set_shadow_epsilon.
+pub const SHADOW_EPSILON: f32 = 0.005;
+
+// How near to zero a quantity must be to count as zero in intersection
+// tests, such as a ray's direction running parallel to a plane.
+pub const EPSILON: f32 = 0.00001;
+
+// How close two floats must be to count as equal. They match if they're
+// within any one of the three: absolute suits values near zero, where
+// rounding leaves tiny differences that are huge relative to the values;
+// relative and ulps scale with the values, so that large coordinates, whose
+// rounding errors are larger than absolute, still match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    pub absolute: f32,
+    // A fraction of the larger magnitude of the two.
+    pub relative: f32,
+    // Steps between adjacent f32 values.
+    pub ulps: u32,
+}
+
+impl Tolerance {
+    pub const DEFAULT: Tolerance = Tolerance {
+        absolute: EPSILON,
+        relative: 4.0 * f32::EPSILON,
+        ulps: 4,
+    };
+
+    pub fn absolute(absolute: f32) -> Tolerance {
+        Tolerance {
+            absolute,
+            relative: 0.0,
+            ulps: 0,
+        }
+    }
+
+    pub fn relative(relative: f32) -> Tolerance {
+        Tolerance {
+            absolute: 0.0,
+            relative,
+            ulps: 0,
+        }
+    }
+
+    pub fn ulps(ulps: u32) -> Tolerance {
+        Tolerance {
+            absolute: 0.0,
+            relative: 0.0,
+            ulps,
+        }
+    }
+
+    pub fn equal(&self, a: f32, b: f32) -> bool {
+        if a == b {
+            return true;
+        }
+        let diff = (a - b).abs();
+        diff <= self.absolute
+            || diff <= self.relative * a.abs().max(b.abs())
+            || ulps_apart(a, b) <= self.ulps as u64
+    }
+}
+
+impl Default for Tolerance {
+    fn default() -> Tolerance {
+        Tolerance::DEFAULT
+    }
+}
+
+// Floats of the same sign are ordered like their bits, so the difference in
+// bits counts the floats in between.
+fn ulps_apart(a: f32, b: f32) -> u64 {
+    if a.is_nan() || b.is_nan() || a.is_sign_negative() != b.is_sign_negative() {
+        return u64::MAX;
+    }
+    (a.to_bits() as i64 - b.to_bits() as i64).unsigned_abs()
+}
+
+// Equality to within a tolerance, component by component.
+pub trait ApproxEq {
+    fn approx_eq_with(&self, other: &Self, tolerance: Tolerance) -> bool;
+
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.approx_eq_with(other, Tolerance::DEFAULT)
+    }
+}
+
+impl ApproxEq for f32 {
+    fn approx_eq_with(&self, other: &f32, tolerance: Tolerance) -> bool {
+        tolerance.equal(*self, *other)
+    }
+}
+
 pub fn fp_equal(a: f32, b: f32) -> bool {
-    let epsilon = 0.00001;
-    f32::abs(a - b) < epsilon
+    a.approx_eq(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_tolerance_suits_small_values() {
+        assert!(fp_equal(0.0, 0.000001));
+        assert!(!fp_equal(0.0, 0.0001));
+        assert!(Tolerance::absolute(0.01).equal(1.0, 1.005));
+        assert!(!Tolerance::relative(0.01).equal(0.0, 0.000001));
+    }
+
+    #[test]
+    fn relative_tolerance_suits_large_values() {
+        // Neighbouring floats near a million are 0.0625 apart.
+        let a = 1_000_000.0_f32;
+        let b = a + 0.0625;
+        assert!(!Tolerance::absolute(0.00001).equal(a, b));
+        assert!(fp_equal(a, b));
+        assert!(Tolerance::relative(0.001).equal(a, 1_000_500.0));
+        assert!(!fp_equal(a, 1_000_500.0));
+    }
+
+    #[test]
+    fn ulps_count_the_floats_between() {
+        let a = 1.0_f32;
+        let b = f32::from_bits(a.to_bits() + 3);
+        assert!(Tolerance::ulps(3).equal(a, b));
+        assert!(!Tolerance::ulps(2).equal(a, b));
+        assert!(!Tolerance::ulps(3).equal(-a, b));
+        assert!(!Tolerance::DEFAULT.equal(f32::NAN, f32::NAN));
+        assert!(Tolerance::ulps(0).equal(f32::INFINITY, f32::INFINITY));
+    }
 }
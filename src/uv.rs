@@ -0,0 +1,370 @@
+use crate::color::Color;
+use crate::matrix::Matrix;
+use crate::pattern::Pattern;
+use crate::point::Point;
+use std::any::Any;
+use std::f32::consts::PI;
+use std::fmt::Debug;
+
+// A colour defined over the unit square, u running left to right and v
+// bottom to top.
+#[cfg_attr(feature = "serde", typetag::serde)]
+pub trait UvPattern: Debug + Send + Sync {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Color;
+
+    fn box_clone(&self) -> Box<dyn UvPattern>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn uv_pattern_eq(&self, other: &dyn UvPattern) -> bool;
+}
+
+impl Clone for Box<dyn UvPattern> {
+    fn clone(&self) -> Box<dyn UvPattern> {
+        self.box_clone()
+    }
+}
+
+impl<'a> PartialEq for dyn UvPattern + 'a {
+    fn eq(&self, other: &(dyn UvPattern + 'a)) -> bool {
+        self.uv_pattern_eq(other)
+    }
+}
+
+// A width by height grid of squares across the unit square.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UvCheckers {
+    width: f32,
+    height: f32,
+    a: Color,
+    b: Color,
+}
+
+impl UvCheckers {
+    pub fn new(width: f32, height: f32, a: Color, b: Color) -> UvCheckers {
+        UvCheckers {
+            width,
+            height,
+            a,
+            b,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl UvPattern for UvCheckers {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Color {
+        let u2 = (u * self.width).floor() as i64;
+        let v2 = (v * self.height).floor() as i64;
+        if (u2 + v2).rem_euclid(2) == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn UvPattern> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn uv_pattern_eq(&self, other: &dyn UvPattern) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<UvCheckers>()
+            .is_some_and(|o| self == o)
+    }
+}
+
+// Longitude and latitude on the unit sphere.
+pub fn spherical_map(p: Point) -> (f32, f32) {
+    let theta = p.x.atan2(p.z);
+    let radius = (p.x * p.x + p.y * p.y + p.z * p.z).sqrt();
+    let phi = (p.y / radius).acos();
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u, v)
+}
+
+// The xz plane, repeating every unit.
+pub fn planar_map(p: Point) -> (f32, f32) {
+    (p.x.rem_euclid(1.0), p.z.rem_euclid(1.0))
+}
+
+// Around the y axis, repeating every unit of height.
+pub fn cylindrical_map(p: Point) -> (f32, f32) {
+    let theta = p.x.atan2(p.z);
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    (u, p.y.rem_euclid(1.0))
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    // The face of the -1..1 cube a point lies on, by its largest coordinate.
+    pub fn from_point(p: Point) -> CubeFace {
+        let coord = p.x.abs().max(p.y.abs()).max(p.z.abs());
+        if coord == p.x {
+            CubeFace::Right
+        } else if coord == -p.x {
+            CubeFace::Left
+        } else if coord == p.y {
+            CubeFace::Up
+        } else if coord == -p.y {
+            CubeFace::Down
+        } else if coord == p.z {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+}
+
+// Each face of the -1..1 cube mapped to the whole unit square, as seen from
+// outside the cube.
+pub fn cubic_map(p: Point) -> (f32, f32) {
+    let (u, v) = match CubeFace::from_point(p) {
+        CubeFace::Front => (p.x + 1.0, p.y + 1.0),
+        CubeFace::Back => (1.0 - p.x, p.y + 1.0),
+        CubeFace::Left => (p.z + 1.0, p.y + 1.0),
+        CubeFace::Right => (1.0 - p.z, p.y + 1.0),
+        CubeFace::Up => (p.x + 1.0, 1.0 - p.z),
+        CubeFace::Down => (p.x + 1.0, p.z + 1.0),
+    };
+    ((u % 2.0) / 2.0, (v % 2.0) / 2.0)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mapping {
+    Spherical,
+    Planar,
+    Cylindrical,
+    Cubic,
+}
+
+impl Mapping {
+    pub fn map(&self, p: Point) -> (f32, f32) {
+        match self {
+            Mapping::Spherical => spherical_map(p),
+            Mapping::Planar => planar_map(p),
+            Mapping::Cylindrical => cylindrical_map(p),
+            Mapping::Cubic => cubic_map(p),
+        }
+    }
+}
+
+// A pattern that wraps a UvPattern onto a surface through a mapping.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureMap {
+    uv_pattern: Box<dyn UvPattern>,
+    mapping: Mapping,
+    matrix: Matrix,
+}
+
+impl TextureMap {
+    pub fn new(uv_pattern: Box<dyn UvPattern>, mapping: Mapping) -> TextureMap {
+        TextureMap {
+            uv_pattern,
+            mapping,
+            matrix: Matrix::identity(),
+        }
+    }
+
+    pub fn mapping(&self) -> Mapping {
+        self.mapping
+    }
+
+    pub fn set_transform(mut self, m: Matrix) -> Self {
+        self.matrix = m;
+        self
+    }
+}
+
+impl PartialEq for TextureMap {
+    fn eq(&self, other: &TextureMap) -> bool {
+        *self.uv_pattern == *other.uv_pattern
+            && self.mapping == other.mapping
+            && self.matrix == other.matrix
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Pattern for TextureMap {
+    fn transform(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let (u, v) = self.mapping.map(point);
+        self.uv_pattern.uv_pattern_at(u, v)
+    }
+
+    fn box_clone(&self) -> Box<dyn Pattern> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn pattern_eq(&self, other: &dyn Pattern) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<TextureMap>()
+            .is_some_and(|o| self == o)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fp_equal;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    fn uv_equal(a: (f32, f32), b: (f32, f32)) -> bool {
+        fp_equal(a.0, b.0) && fp_equal(a.1, b.1)
+    }
+
+    #[test]
+    fn checker_pattern_in_2d() {
+        let c = UvCheckers::new(2.0, 2.0, black(), white());
+        assert_eq!(c.uv_pattern_at(0.0, 0.0), black());
+        assert_eq!(c.uv_pattern_at(0.5, 0.0), white());
+        assert_eq!(c.uv_pattern_at(0.0, 0.5), white());
+        assert_eq!(c.uv_pattern_at(0.5, 0.5), black());
+        assert_eq!(c.uv_pattern_at(1.0, 1.0), black());
+    }
+
+    #[test]
+    fn using_spherical_mapping_on_3d_point() {
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let cases = [
+            (Point::new(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (Point::new(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (Point::new(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (Point::new(-1.0, 0.0, 0.0), (0.75, 0.5)),
+            (Point::new(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Point::new(0.0, -1.0, 0.0), (0.5, 0.0)),
+            (Point::new(x, x, 0.0), (0.25, 0.75)),
+        ];
+        for (p, uv) in cases {
+            assert!(uv_equal(spherical_map(p), uv));
+        }
+    }
+
+    #[test]
+    fn texture_map_pattern_with_spherical_map() {
+        let checkers = UvCheckers::new(16.0, 8.0, black(), white());
+        let pattern = TextureMap::new(Box::new(checkers), Mapping::Spherical);
+        let cases = [
+            (Point::new(0.4315, 0.4670, 0.7719), white()),
+            (Point::new(-0.9654, 0.2552, -0.0534), black()),
+            (Point::new(0.1039, 0.7090, 0.6975), white()),
+            (Point::new(-0.4986, -0.7856, -0.3663), black()),
+            (Point::new(-0.0317, -0.9395, 0.3411), black()),
+            (Point::new(0.4809, -0.7721, 0.4154), black()),
+            (Point::new(0.0285, -0.9612, -0.2745), black()),
+            (Point::new(-0.5734, -0.2162, -0.7903), white()),
+            (Point::new(0.7688, -0.1470, 0.6223), black()),
+            (Point::new(-0.7652, 0.2175, 0.6060), black()),
+        ];
+        for (p, c) in cases {
+            assert_eq!(pattern.pattern_at(p), c);
+        }
+    }
+
+    #[test]
+    fn using_planar_mapping_on_3d_point() {
+        let cases = [
+            (Point::new(0.25, 0.0, 0.5), (0.25, 0.5)),
+            (Point::new(0.25, 0.0, -0.25), (0.25, 0.75)),
+            (Point::new(0.25, 0.5, -0.25), (0.25, 0.75)),
+            (Point::new(1.25, 0.0, 0.5), (0.25, 0.5)),
+            (Point::new(0.25, 0.0, -1.75), (0.25, 0.25)),
+            (Point::new(1.0, 0.0, -1.0), (0.0, 0.0)),
+            (Point::new(0.0, 0.0, 0.0), (0.0, 0.0)),
+        ];
+        for (p, uv) in cases {
+            assert!(uv_equal(planar_map(p), uv));
+        }
+    }
+
+    #[test]
+    fn using_cylindrical_mapping_on_3d_point() {
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let cases = [
+            (Point::new(0.0, 0.0, -1.0), (0.0, 0.0)),
+            (Point::new(0.0, 0.5, -1.0), (0.0, 0.5)),
+            (Point::new(0.0, 1.0, -1.0), (0.0, 0.0)),
+            (Point::new(x, 0.5, -x), (0.125, 0.5)),
+            (Point::new(1.0, 0.5, 0.0), (0.25, 0.5)),
+            (Point::new(x, 0.5, x), (0.375, 0.5)),
+            (Point::new(0.0, -0.25, 1.0), (0.5, 0.75)),
+            (Point::new(-x, 0.5, x), (0.625, 0.5)),
+            (Point::new(-1.0, 1.25, 0.0), (0.75, 0.25)),
+            (Point::new(-x, 0.5, -x), (0.875, 0.5)),
+        ];
+        for (p, uv) in cases {
+            assert!(uv_equal(cylindrical_map(p), uv));
+        }
+    }
+
+    #[test]
+    fn identifying_the_face_of_a_cube_from_a_point() {
+        let cases = [
+            (Point::new(-1.0, 0.5, -0.25), CubeFace::Left),
+            (Point::new(1.1, -0.75, 0.8), CubeFace::Right),
+            (Point::new(0.1, 0.6, 0.9), CubeFace::Front),
+            (Point::new(-0.7, 0.0, -2.0), CubeFace::Back),
+            (Point::new(0.5, 1.0, 0.9), CubeFace::Up),
+            (Point::new(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for (p, face) in cases {
+            assert_eq!(CubeFace::from_point(p), face);
+        }
+    }
+
+    #[test]
+    fn uv_mapping_the_faces_of_a_cube() {
+        let cases = [
+            (Point::new(-0.5, 0.5, 1.0), (0.25, 0.75)),
+            (Point::new(0.5, -0.5, 1.0), (0.75, 0.25)),
+            (Point::new(0.5, 0.5, -1.0), (0.25, 0.75)),
+            (Point::new(-0.5, -0.5, -1.0), (0.75, 0.25)),
+            (Point::new(-1.0, 0.5, -0.5), (0.25, 0.75)),
+            (Point::new(-1.0, -0.5, 0.5), (0.75, 0.25)),
+            (Point::new(1.0, 0.5, 0.5), (0.25, 0.75)),
+            (Point::new(1.0, -0.5, -0.5), (0.75, 0.25)),
+            (Point::new(-0.5, 1.0, -0.5), (0.25, 0.75)),
+            (Point::new(0.5, 1.0, 0.5), (0.75, 0.25)),
+            (Point::new(-0.5, -1.0, 0.5), (0.25, 0.75)),
+            (Point::new(0.5, -1.0, -0.5), (0.75, 0.25)),
+        ];
+        for (p, uv) in cases {
+            assert!(uv_equal(cubic_map(p), uv), "{:?}", p);
+        }
+    }
+}
@@ -1,6 +1,7 @@
-use crate::utils::fp_equal;
+use crate::utils::{ApproxEq, Tolerance};
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector {
     pub x: f32,
     pub y: f32,
@@ -44,16 +45,38 @@ impl Vector {
     pub fn reflect(&self, other: &Vector) -> Vector {
         *self - *other * 2.0 * self.dot(other)
     }
+
+    // In radians, from 0 to pi.
+    pub fn angle_between(&self, other: &Vector) -> f32 {
+        self.cross(other).magnitude().atan2(self.dot(other))
+    }
+
+    // The part of self that points along other.
+    pub fn project_onto(&self, other: &Vector) -> Vector {
+        *other * (self.dot(other) / other.dot(other))
+    }
+
+    // The part of self perpendicular to other.
+    pub fn reject_from(&self, other: &Vector) -> Vector {
+        *self - self.project_onto(other)
+    }
+
+    pub fn lerp(&self, other: &Vector, t: f32) -> Vector {
+        *self + (*other - *self) * t
+    }
+}
+
+impl ApproxEq for Vector {
+    fn approx_eq_with(&self, other: &Self, tolerance: Tolerance) -> bool {
+        [(self.x, other.x), (self.y, other.y), (self.z, other.z)]
+            .iter()
+            .all(|&(i, j)| tolerance.equal(i, j))
+    }
 }
 
 impl PartialEq for Vector {
     fn eq(&self, other: &Self) -> bool {
-        for (i, j) in [(self.x, other.x), (self.y, other.y), (self.z, other.z)] {
-            if !fp_equal(i, j) {
-                return false;
-            }
-        }
-        true
+        self.approx_eq(other)
     }
 }
 
@@ -104,6 +127,24 @@ impl std::ops::Mul<f32> for Vector {
     }
 }
 
+impl std::ops::AddAssign for Vector {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl std::ops::SubAssign for Vector {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl std::ops::MulAssign<f32> for Vector {
+    fn mul_assign(&mut self, scalar: f32) {
+        *self = *self * scalar;
+    }
+}
+
 impl std::ops::Div<f32> for Vector {
     type Output = Self;
 
@@ -115,6 +156,8 @@ impl std::ops::Div<f32> for Vector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::fp_equal;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
 
     #[test]
     fn adding_two_vectors() {
@@ -169,6 +212,48 @@ mod tests {
         assert!(a / 2.0 == Vector::new(0.5, -1.0, 1.5));
     }
 
+    #[test]
+    fn updating_a_vector_in_place() {
+        let mut a = Vector::new(1.0, -2.0, 3.0);
+        a += Vector::new(1.0, 1.0, 1.0);
+        assert!(a == Vector::new(2.0, -1.0, 4.0));
+        a -= Vector::new(0.0, 1.0, 2.0);
+        assert!(a == Vector::new(2.0, -2.0, 2.0));
+        a *= 0.5;
+        assert!(a == Vector::new(1.0, -1.0, 1.0));
+    }
+
+    #[test]
+    fn angle_between_vectors() {
+        let x = Vector::new(2.0, 0.0, 0.0);
+        assert!(fp_equal(
+            x.angle_between(&Vector::new(0.0, 3.0, 0.0)),
+            FRAC_PI_2
+        ));
+        assert!(fp_equal(
+            x.angle_between(&Vector::new(1.0, 1.0, 0.0)),
+            FRAC_PI_4
+        ));
+        assert!(fp_equal(x.angle_between(&-x), PI));
+        assert!(fp_equal(x.angle_between(&x), 0.0));
+    }
+
+    #[test]
+    fn projecting_and_rejecting() {
+        let v = Vector::new(3.0, 4.0, 5.0);
+        let onto = Vector::new(0.0, 2.0, 0.0);
+        assert!(v.project_onto(&onto) == Vector::new(0.0, 4.0, 0.0));
+        assert!(v.reject_from(&onto) == Vector::new(3.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn interpolating_vectors() {
+        let a = Vector::new(1.0, 0.0, -2.0);
+        let b = Vector::new(0.0, 4.0, 2.0);
+        assert!(a.lerp(&b, 0.5) == Vector::new(0.5, 2.0, 0.0));
+        assert!(a.lerp(&b, 2.0) == Vector::new(-1.0, 8.0, 6.0));
+    }
+
     #[test]
     fn magnitude_of_1_0_0_vector() {
         let v = Vector::new(1.0, 0.0, 0.0);
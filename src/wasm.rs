@@ -0,0 +1,93 @@
+use crate::camera::Camera;
+use crate::scene::Scene;
+use crate::world::World;
+use wasm_bindgen::prelude::*;
+
+// Renders YAML scenes in the browser, for drawing on an HTML canvas with
+//
+//   const pixels = renderer.render_rows(y0, y1);
+//   const image = new ImageData(new Uint8ClampedArray(pixels), renderer.width);
+//   context.putImageData(image, 0, y0);
+//
+// Rendering a few rows at a time lets the page stay responsive in between.
+// Everything runs on the calling thread. With auto exposure the frame is
+// metered once, when the renderer is made, so the strips match.
+#[wasm_bindgen]
+pub struct SceneRenderer {
+    camera: Camera,
+    world: World,
+    exposure: f32,
+}
+
+#[wasm_bindgen]
+impl SceneRenderer {
+    // The scene's camera is resized to width x height.
+    #[wasm_bindgen(constructor)]
+    pub fn new(scene: &str, width: u32, height: u32) -> Result<SceneRenderer, JsValue> {
+        let scene = Scene::parse(scene).map_err(|e| JsValue::from_str(&e))?;
+        if width == 0 || height == 0 {
+            let message = format!("image size {}x{} is empty", width, height);
+            return Err(JsValue::from_str(&message));
+        }
+        let camera = scene.camera.set_size(width, height).set_threads(1);
+        let exposure = camera.frame_exposure(&scene.world);
+        Ok(SceneRenderer {
+            camera,
+            world: scene.world,
+            exposure,
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.camera.hsize()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.camera.vsize()
+    }
+
+    // RGBA bytes for rows y0 up to but not including y1.
+    pub fn render_rows(&self, y0: u32, y1: u32) -> Vec<u8> {
+        let width = self.camera.hsize();
+        self.camera
+            .render_region_with_exposure(&self.world, 0, y0, width, y1, self.exposure)
+            .to_rgba()
+    }
+
+    // RGBA bytes for the whole image.
+    pub fn render(&self) -> Vec<u8> {
+        self.render_rows(0, self.camera.vsize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = "
+- add: camera
+  width: 100
+  height: 100
+  field-of-view: 1.0
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- add: sphere
+";
+
+    #[test]
+    fn rendering_rows_for_a_canvas() {
+        let renderer = SceneRenderer::new(SCENE, 6, 4).unwrap();
+        assert_eq!((renderer.width(), renderer.height()), (6, 4));
+        let image = renderer.render();
+        assert_eq!(image.len(), 6 * 4 * 4);
+        let row = 6 * 4;
+        assert_eq!(renderer.render_rows(1, 3), image[row..3 * row]);
+        assert!(image.chunks(4).all(|pixel| pixel[3] == 255));
+    }
+}
@@ -1,76 +1,713 @@
 use crate::{
+    animation::ObjectAnimation,
+    aov::SurfaceSample,
+    background::Background,
     color::Color,
+    environment::EnvironmentMap,
+    fog::{Fog, Medium},
+    light::Light,
     material::Material,
     matrix::Matrix,
+    orthonormal_basis::OrthonormalBasis,
     point::Point,
     point_light::PointLight,
+    quality::Quality,
     ray::{hit, Intersection, Ray},
+    rng::Rng,
+    shading_trace::{LightTerm, ShadingTrace, TracedHit},
+    shape::Shape,
+    sky::SunSky,
     sphere::Sphere,
+    stats,
+    utils::SHADOW_EPSILON,
+    vector::Vector,
     world_intersection::WorldIntersection,
 };
 
+// How many times a ray may bounce or refract before tracing stops, unless
+// the world sets its own limit.
+const MAX_RECURSION: u32 = 5;
+
+// Hard limit on path tracer bounces, past Russian roulette.
+const MAX_PATH_LENGTH: u32 = 64;
+
+// Names one of the world's objects for as long as it stays in the world.
+// Ids are handed out in the order objects are added and never reused, so
+// removing or replacing one object leaves the others' ids alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectId(usize);
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct World {
-    objects: Vec<Sphere>,
-    light: PointLight,
+    objects: Vec<Box<dyn Shape>>,
+    // The id of each object, in the same order.
+    ids: Vec<ObjectId>,
+    next_id: usize,
+    animations: Vec<(ObjectId, ObjectAnimation)>,
+    lights: Vec<Box<dyn Light>>,
+    environment: Option<EnvironmentMap>,
+    background: Background,
+    fog: Option<Fog>,
+    medium: Option<Medium>,
+    shadow_epsilon: f32,
+    max_recursion: u32,
 }
 
 impl World {
-    pub fn new(objects: Vec<Sphere>, light: PointLight) -> World {
-        World { objects, light }
+    pub fn new(objects: Vec<Box<dyn Shape>>, light: Box<dyn Light>) -> World {
+        World {
+            ids: (0..objects.len()).map(ObjectId).collect(),
+            next_id: objects.len(),
+            animations: vec![],
+            objects,
+            lights: vec![light],
+            environment: None,
+            background: Background::default(),
+            fog: None,
+            medium: None,
+            shadow_epsilon: SHADOW_EPSILON,
+            max_recursion: MAX_RECURSION,
+        }
+    }
+
+    pub fn add_light(mut self, light: Box<dyn Light>) -> Self {
+        self.lights.push(light);
+        self
+    }
+
+    // Seen by rays that miss everything, unless there is an environment map.
+    pub fn set_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    // Uses the sky as the background and adds its sun as a light.
+    pub fn set_sun_sky(self, sky: SunSky) -> Self {
+        let sun = sky.sun();
+        self.set_background(Background::Sky(sky))
+            .add_light(Box::new(sun))
+    }
+
+    // Seen by rays that miss everything, in place of the background, and
+    // lighting diffuse surfaces if it has diffuse samples.
+    pub fn set_environment(mut self, environment: EnvironmentMap) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    pub fn environment(&self) -> Option<&EnvironmentMap> {
+        self.environment.as_ref()
+    }
+
+    // Fog and media only affect camera rays, not reflections or refractions.
+    pub fn set_fog(mut self, fog: Fog) -> Self {
+        self.fog = Some(fog);
+        self
+    }
+
+    pub fn fog(&self) -> Option<&Fog> {
+        self.fog.as_ref()
+    }
+
+    pub fn set_medium(mut self, medium: Medium) -> Self {
+        self.medium = Some(medium);
+        self
+    }
+
+    pub fn medium(&self) -> Option<&Medium> {
+        self.medium.as_ref()
     }
 
-    pub fn objects(&self) -> &Vec<Sphere> {
+    // How far rays leaving a surface start from it. Raise it if large scenes
+    // show acne, lower it if small ones show gaps in their shadows.
+    pub fn set_shadow_epsilon(mut self, epsilon: f32) -> Self {
+        self.shadow_epsilon = epsilon;
+        self
+    }
+
+    pub fn shadow_epsilon(&self) -> f32 {
+        self.shadow_epsilon
+    }
+
+    // How many reflected or refracted rays may follow one another from a
+    // camera ray. Zero turns reflection and refraction off.
+    pub fn set_max_recursion(mut self, depth: u32) -> Self {
+        self.max_recursion = depth;
+        self
+    }
+
+    pub fn max_recursion(&self) -> u32 {
+        self.max_recursion
+    }
+
+    // Applies the profile's recursion limit; the camera takes the rest.
+    pub fn set_quality(self, quality: &Quality) -> Self {
+        self.set_max_recursion(quality.max_recursion)
+    }
+
+    pub fn objects(&self) -> &Vec<Box<dyn Shape>> {
         &self.objects
     }
 
-    pub fn light(&self) -> &PointLight {
-        &self.light
+    // The ids of the objects, in the same order as objects().
+    pub fn object_ids(&self) -> &[ObjectId] {
+        &self.ids
+    }
+
+    pub fn add_object(&mut self, object: Box<dyn Shape>) -> ObjectId {
+        let id = ObjectId(self.next_id);
+        self.next_id += 1;
+        self.objects.push(object);
+        self.ids.push(id);
+        id
+    }
+
+    // The object taken out, or None if it was already gone.
+    pub fn remove_object(&mut self, id: ObjectId) -> Option<Box<dyn Shape>> {
+        let index = self.position(id)?;
+        self.ids.remove(index);
+        self.animations.retain(|(i, _)| *i != id);
+        Some(self.objects.remove(index))
     }
 
-    pub fn intersect_world(&self, ray: &Ray) -> Vec<Intersection> {
+    // Puts object in place of the one with id, which keeps its id, and gives
+    // back the old one.
+    pub fn replace_object(
+        &mut self,
+        id: ObjectId,
+        object: Box<dyn Shape>,
+    ) -> Option<Box<dyn Shape>> {
+        let index = self.position(id)?;
+        Some(std::mem::replace(&mut self.objects[index], object))
+    }
+
+    pub fn object(&self, id: ObjectId) -> Option<&dyn Shape> {
+        let index = self.position(id)?;
+        Some(self.objects[index].as_ref())
+    }
+
+    pub fn object_mut(&mut self, id: ObjectId) -> Option<&mut Box<dyn Shape>> {
+        let index = self.position(id)?;
+        Some(&mut self.objects[index])
+    }
+
+    // Keyframes the object, in place of any animation it had.
+    pub fn animate(&mut self, id: ObjectId, animation: ObjectAnimation) {
+        self.animations.retain(|(i, _)| *i != id);
+        self.animations.push((id, animation));
+    }
+
+    pub fn animation(&self, id: ObjectId) -> Option<&ObjectAnimation> {
+        self.animations
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map(|(_, a)| a)
+    }
+
+    // A still of the world with every animated object as it is at time,
+    // for rendering one frame. The still has no animation of its own.
+    pub fn at_time(&self, time: f32) -> World {
+        let mut world = self.clone();
+        world.animations = vec![];
+        for (id, animation) in &self.animations {
+            if let Some(object) = world.object_mut(*id) {
+                animation.apply(object.as_mut(), time);
+            }
+        }
+        world
+    }
+
+    fn position(&self, id: ObjectId) -> Option<usize> {
+        self.ids.iter().position(|&i| i == id)
+    }
+
+    // The light the world was created with.
+    pub fn light(&self) -> &dyn Light {
+        self.lights[0].as_ref()
+    }
+
+    pub fn lights(&self) -> &[Box<dyn Light>] {
+        &self.lights
+    }
+
+    pub fn intersect_world(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        stats::count_ray();
         let mut inters: Vec<Intersection> = self
             .objects()
             .iter()
-            .map(|x| ray.intersect(x))
-            .flatten()
+            .flat_map(|x| ray.intersect(x.as_ref()))
             .collect();
         inters.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
         inters
     }
 
-    pub fn shade_hit(&self, comps: &WorldIntersection) -> Color {
-        let shadowed = self.is_shadowed(*comps.over_point());
-        self.light.lighting(
-            comps.inter().object().material(),
-            *comps.point(),
-            *comps.eye(),
-            *comps.normal(),
-            shadowed
-        )
+    pub fn shade_hit(&self, comps: &WorldIntersection, remaining: u32) -> Color {
+        // Each light contributes separately, with its own shadow test.
+        let surface = self
+            .lights
+            .iter()
+            .map(|light| {
+                let shadowed =
+                    self.is_shadowed_at(light.position(), *comps.over_point(), comps.time());
+                light.lighting(
+                    comps.inter().object().material(),
+                    comps.inter(),
+                    *comps.point(),
+                    *comps.eye(),
+                    *comps.normal(),
+                    shadowed,
+                )
+            })
+            .sum::<Color>()
+            + self.environment_lighting(comps);
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+
+        let material = comps.inter().object().material();
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
     }
 
     pub fn color_at(&self, ray: &Ray) -> Color {
+        if self.fog.is_none() && self.medium.is_none() {
+            return self.color_at_depth(ray, self.max_recursion);
+        }
+        let inters = self.intersect_world(ray);
+        let mut color = self.color_for(ray, &inters, self.max_recursion);
+        let distance = match hit(&inters) {
+            Some(int) => int.t() * ray.direction().magnitude(),
+            None => f32::INFINITY,
+        };
+        if let Some(medium) = &self.medium {
+            color =
+                color * medium.transmittance(distance) + self.in_scattered(ray, medium, distance);
+        }
+        if let Some(fog) = &self.fog {
+            color = fog.apply(color, distance);
+        }
+        color
+    }
+
+    // remaining counts the secondary rays still allowed below this one.
+    pub fn color_at_depth(&self, ray: &Ray, remaining: u32) -> Color {
         let inters = self.intersect_world(ray);
+        self.color_for(ray, &inters, remaining)
+    }
+
+    fn color_for(&self, ray: &Ray, inters: &[Intersection], remaining: u32) -> Color {
         match hit(inters) {
-            Some(int) => self.shade_hit(&WorldIntersection::precompute(int, ray)),
+            Some(int) => {
+                let comps = WorldIntersection::precompute_with_epsilon(
+                    int,
+                    ray,
+                    inters,
+                    self.shadow_epsilon,
+                );
+                self.shade_hit(&comps, remaining)
+            }
+            None => self.miss_color(ray),
+        }
+    }
+
+    // Light scattered towards the ray's origin by the medium in front of
+    // distance, found by testing each light at evenly spaced points.
+    fn in_scattered(&self, ray: &Ray, medium: &Medium, distance: f32) -> Color {
+        let length = distance.min(medium.reach());
+        let step = length / medium.steps() as f32;
+        let direction = ray.direction().normalize();
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        for i in 0..medium.steps() {
+            let t = (i as f32 + 0.5) * step;
+            let point = ray.origin() + direction * t;
+            let weight = medium.density() * medium.transmittance(t) * step;
+            for light in &self.lights {
+                if !self.is_shadowed_at(light.position(), point, ray.time()) {
+                    total += medium.color() * light.intensity_at(point) * weight;
+                }
+            }
+        }
+        total
+    }
+
+    fn miss_color(&self, ray: &Ray) -> Color {
+        match &self.environment {
+            Some(environment) => environment.color_in_direction(ray.direction()),
+            None => self.background.color_in_direction(ray.direction()),
+        }
+    }
+
+    // One Monte Carlo sample of the light arriving along ray, following a
+    // single path that bounces diffusely, reflects or refracts at random in
+    // proportion to the material. Direct light from the world's lights is
+    // added at every hit, without the Phong ambient term, and emissive
+    // surfaces light the scene through the bounces. Paths are ended by
+    // Russian roulette once they have bounced a few times.
+    pub fn trace_path(&self, ray: &Ray, rng: &mut Rng) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let mut color = black;
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let time = ray.time();
+        let mut ray = *ray;
+        for bounce in 0..MAX_PATH_LENGTH {
+            if bounce > 0 {
+                stats::count_bounce();
+            }
+            let inters = self.intersect_world(&ray);
+            let comps = match hit(&inters) {
+                Some(int) => WorldIntersection::precompute_with_epsilon(
+                    int,
+                    &ray,
+                    &inters,
+                    self.shadow_epsilon,
+                ),
+                None => return color + throughput * self.miss_color(&ray),
+            };
+            let object = comps.inter().object();
+            let material = object.material();
+            color += throughput * (material.emissive + self.direct_light(&comps));
+
+            let reflect_p = material.reflective.clamp(0.0, 1.0);
+            let refract_p = material.transparency.clamp(0.0, 1.0 - reflect_p);
+            let choice = rng.next_f32();
+            if choice < reflect_p {
+                throughput *= material.reflective / reflect_p;
+                ray = Ray::new(*comps.over_point(), *comps.reflectv()).set_time(time);
+            } else if choice < reflect_p + refract_p {
+                throughput *= material.transparency / refract_p;
+                ray = match refraction_direction(&comps) {
+                    Some(direction) => Ray::new(*comps.under_point(), direction),
+                    None => Ray::new(*comps.over_point(), *comps.reflectv()),
+                }
+                .set_time(time);
+            } else {
+                let diffuse_p = 1.0 - reflect_p - refract_p;
+                let albedo = material.color_at(comps.inter(), *comps.point()) * material.diffuse;
+                throughput *= albedo * (1.0 / diffuse_p);
+                let direction = cosine_weighted(*comps.normal(), rng);
+                ray = Ray::new(*comps.over_point(), direction).set_time(time);
+            }
+
+            if bounce >= 3 {
+                let survive = throughput
+                    .red
+                    .max(throughput.green)
+                    .max(throughput.blue)
+                    .clamp(0.05, 1.0);
+                if rng.next_f32() >= survive {
+                    break;
+                }
+                throughput *= 1.0 / survive;
+            }
+        }
+        color
+    }
+
+    // Diffuse and specular light from every light that can see the hit.
+    fn direct_light(&self, comps: &WorldIntersection) -> Color {
+        let material = comps.inter().object().material();
+        self.lights
+            .iter()
+            .filter(|light| {
+                !self.is_shadowed_at(light.position(), *comps.over_point(), comps.time())
+            })
+            .map(|light| {
+                light.lighting_with_ambient(
+                    material,
+                    0.0,
+                    comps.inter(),
+                    *comps.point(),
+                    *comps.eye(),
+                    *comps.normal(),
+                )
+            })
+            .sum::<Color>()
+    }
+
+    // Monte Carlo estimate of the diffuse light the environment sends to the
+    // hit, over directions importance-sampled from the map. They're drawn
+    // from the ray's seed, so each hit gets its own and the estimate
+    // converges as the camera takes more samples.
+    fn environment_lighting(&self, comps: &WorldIntersection) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let environment = match &self.environment {
+            Some(e) if e.diffuse_samples() > 0 => e,
+            _ => return black,
+        };
+        let object = comps.inter().object();
+        let material = object.material();
+        let albedo = material.color_at(comps.inter(), *comps.point()) * material.diffuse;
+        let samples = environment.diffuse_samples();
+        let mut rng = Rng::new(comps.seed());
+        let total = (0..samples)
+            .map(|_| environment.sample(&mut rng))
+            .filter_map(|s| {
+                let cos = s.direction.dot(comps.normal());
+                let r = Ray::new(*comps.over_point(), s.direction).set_time(comps.time());
+                if cos <= 0.0 || self.blocked(&r, f32::INFINITY) {
+                    return None;
+                }
+                let radiance = environment.color_in_direction(s.direction);
+                Some(radiance * (cos / (std::f32::consts::PI * s.pdf)))
+            })
+            .sum::<Color>();
+        albedo * total * (1.0 / samples as f32)
+    }
+
+    pub fn reflected_color(&self, comps: &WorldIntersection, remaining: u32) -> Color {
+        let reflective = comps.inter().object().material().reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let reflected = Ray::new(*comps.over_point(), *comps.reflectv())
+            .set_time(comps.time())
+            .set_seed(bounce_seed(comps));
+        stats::count_bounce();
+        self.color_at_depth(&reflected, remaining - 1) * reflective
+    }
+
+    pub fn refracted_color(&self, comps: &WorldIntersection, remaining: u32) -> Color {
+        let transparency = comps.inter().object().material().transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        match refraction_direction(comps) {
+            Some(direction) => {
+                let refracted = Ray::new(*comps.under_point(), direction)
+                    .set_time(comps.time())
+                    .set_seed(bounce_seed(comps));
+                stats::count_bounce();
+                self.color_at_depth(&refracted, remaining - 1) * transparency
+            }
             None => Color::new(0.0, 0.0, 0.0),
         }
     }
 
-    pub fn is_shadowed(&self, point: Point) -> bool {
-        let v = self.light.position() - point;
-        let distance = v.magnitude();
-        let direction = v.normalize();
+    // Everything that went into the color color_at_depth would give ray,
+    // down to each light's contribution and the reflected and refracted rays.
+    // Fog and media are left out.
+    pub fn debug_trace(&self, ray: &Ray) -> ShadingTrace<'_> {
+        self.trace_at_depth(ray, self.max_recursion)
+    }
 
-        let r = Ray::new(point, direction);
-        let intersections = self.intersect_world(&r);
+    fn trace_at_depth(&self, ray: &Ray, remaining: u32) -> ShadingTrace<'_> {
+        let intersections = self.intersect_world(ray);
+        let hit = hit(&intersections).map(|int| {
+            let comps = WorldIntersection::precompute_with_epsilon(
+                int,
+                ray,
+                &intersections,
+                self.shadow_epsilon,
+            );
+            self.trace_hit(comps, remaining)
+        });
+        let color = match &hit {
+            Some(h) => h.color,
+            None => self.miss_color(ray),
+        };
+        ShadingTrace {
+            ray: *ray,
+            intersections,
+            hit,
+            color,
+        }
+    }
+
+    // Follows shade_hit step by step.
+    fn trace_hit<'a>(&'a self, comps: WorldIntersection<'a>, remaining: u32) -> TracedHit<'a> {
+        let object = comps.inter().object();
+        let material = object.material();
+        let lights: Vec<LightTerm> = self
+            .lights
+            .iter()
+            .map(|light| {
+                let shadowed =
+                    self.is_shadowed_at(light.position(), *comps.over_point(), comps.time());
+                let color = light.lighting(
+                    material,
+                    comps.inter(),
+                    *comps.point(),
+                    *comps.eye(),
+                    *comps.normal(),
+                    shadowed,
+                );
+                LightTerm {
+                    position: light.position(),
+                    shadowed,
+                    color,
+                }
+            })
+            .collect();
+        let environment = self.environment_lighting(&comps);
 
-        if let Some(h) = hit(intersections) {
-            h.t() < distance
+        let (mut reflection_weight, mut refraction_weight) =
+            (material.reflective, material.transparency);
+        let reflection = if remaining > 0 && reflection_weight > 0.0 {
+            let r = Ray::new(*comps.over_point(), *comps.reflectv())
+                .set_time(comps.time())
+                .set_seed(bounce_seed(&comps));
+            Some(Box::new(self.trace_at_depth(&r, remaining - 1)))
         } else {
-            false   
+            None
+        };
+        let refraction = match refraction_direction(&comps) {
+            Some(direction) if remaining > 0 && refraction_weight > 0.0 => {
+                let r = Ray::new(*comps.under_point(), direction)
+                    .set_time(comps.time())
+                    .set_seed(bounce_seed(&comps));
+                Some(Box::new(self.trace_at_depth(&r, remaining - 1)))
+            }
+            _ => None,
+        };
+        if reflection_weight > 0.0 && refraction_weight > 0.0 {
+            let reflectance = comps.schlick();
+            reflection_weight *= reflectance;
+            refraction_weight *= 1.0 - reflectance;
+        }
+
+        let weighted = |trace: &Option<Box<ShadingTrace>>, weight: f32| match trace {
+            Some(t) => t.color * weight,
+            None => Color::new(0.0, 0.0, 0.0),
+        };
+        let color = lights.iter().map(|l| l.color).sum::<Color>()
+            + environment
+            + weighted(&reflection, reflection_weight)
+            + weighted(&refraction, refraction_weight);
+        TracedHit {
+            point: *comps.point(),
+            normal: *comps.normal(),
+            eye: *comps.eye(),
+            inside: comps.inside(),
+            intersection: comps.inter().clone(),
+            lights,
+            environment,
+            reflection,
+            reflection_weight,
+            refraction,
+            refraction_weight,
+            color,
+        }
+    }
+
+    // The nearest hit ahead along ray. Cheaper than taking the hit from
+    // intersect_world, which sorts every intersection.
+    pub fn first_hit(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        stats::count_ray();
+        self.objects
+            .iter()
+            .flat_map(|o| o.intersect(ray))
+            .filter(|i| i.t() > 0.0)
+            .min_by(|a, b| a.t().partial_cmp(&b.t()).unwrap())
+    }
+
+    // The id of the world object nearest ahead along ray, and where ray meets
+    // it. A hit on a group's child or a mesh's triangle picks the world
+    // object holding it.
+    pub fn pick(&self, ray: &Ray) -> Option<(ObjectId, Point)> {
+        let (id, hit) = self.nearest(ray)?;
+        Some((id, ray.position(hit.t())))
+    }
+
+    // Depth, normal, albedo and object for the nearest hit along ray.
+    pub fn surface_sample(&self, ray: &Ray) -> Option<SurfaceSample> {
+        let (object, hit) = self.nearest(ray)?;
+        let t = hit.t();
+        let comps = WorldIntersection::precompute(hit, ray, &[]);
+        let shape = comps.inter().object();
+        Some(SurfaceSample {
+            depth: t * ray.direction().magnitude(),
+            normal: *comps.normal(),
+            albedo: shape.material().color_at(comps.inter(), *comps.point()),
+            object,
+        })
+    }
+
+    fn nearest(&self, ray: &Ray) -> Option<(ObjectId, Intersection<'_>)> {
+        self.objects
+            .iter()
+            .zip(&self.ids)
+            .flat_map(|(o, &id)| o.intersect(ray).into_iter().map(move |h| (id, h)))
+            .filter(|(_, h)| h.t() > 0.0)
+            .min_by(|a, b| a.1.t().partial_cmp(&b.1.t()).unwrap())
+    }
+
+    // Whether anything lies on the line between from and to, stopping at the
+    // first object found in the way.
+    pub fn is_occluded(&self, from: Point, to: Point) -> bool {
+        self.is_occluded_at(from, to, 0.0)
+    }
+
+    fn is_occluded_at(&self, from: Point, to: Point, time: f32) -> bool {
+        let v = to - from;
+        let distance = v.magnitude();
+        // Nothing lies between a point and itself.
+        if distance == 0.0 {
+            return false;
         }
+        let r = Ray::new(from, v.normalize()).set_time(time);
+        self.blocked(&r, distance)
+    }
+
+    // Whether ray hits anything before distance.
+    fn blocked(&self, ray: &Ray, distance: f32) -> bool {
+        stats::count_ray();
+        self.objects.iter().any(|o| {
+            o.intersect(ray)
+                .iter()
+                .any(|i| i.t() > 0.0 && i.t() < distance)
+        })
+    }
+
+    pub fn is_shadowed(&self, light_position: Point, point: Point) -> bool {
+        self.is_shadowed_at(light_position, point, 0.0)
+    }
+
+    // As is_shadowed, with moving shapes where they are at time.
+    pub fn is_shadowed_at(&self, light_position: Point, point: Point, time: f32) -> bool {
+        self.is_occluded_at(point, light_position, time)
+    }
+}
+
+// Snell's law; past the critical angle all light is reflected and there is
+// no refracted ray.
+fn refraction_direction(comps: &WorldIntersection) -> Option<Vector> {
+    let n_ratio = comps.n1() / comps.n2();
+    let cos_i = comps.eye().dot(comps.normal());
+    let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+    if sin2_t > 1.0 {
+        return None;
     }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(*comps.normal() * (n_ratio * cos_i - cos_t) - *comps.eye() * n_ratio)
+}
+
+// A seed for rays leaving a hit, drawn from the hit's so that shading along
+// them doesn't repeat the random choices made at the hit.
+fn bounce_seed(comps: &WorldIntersection) -> u64 {
+    Rng::new(comps.seed()).next_u64()
+}
+
+// A direction in the hemisphere around normal, more likely the closer it is
+// to the normal (pdf cos / pi).
+fn cosine_weighted(normal: Vector, rng: &mut Rng) -> Vector {
+    let r = rng.next_f32().sqrt();
+    let phi = 2.0 * std::f32::consts::PI * rng.next_f32();
+    let (x, y) = (r * phi.cos(), r * phi.sin());
+    let z = (1.0 - r * r).max(0.0).sqrt();
+    OrthonormalBasis::from_normal(normal)
+        .to_world(Vector::new(x, y, z))
+        .normalize()
 }
 
 impl Default for World {
@@ -85,13 +722,21 @@ impl Default for World {
 
         let s2 = Sphere::default().set_transform(Matrix::scaling(0.5, 0.5, 0.5));
 
-        World::new(vec![s1, s2], light)
+        World::new(vec![Box::new(s1), Box::new(s2)], Box::new(light))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ray::Ray, vector::Vector, world_intersection::WorldIntersection};
+    use crate::{
+        animation::{Easing, Track},
+        canvas::Canvas,
+        ray::Ray,
+        rectangle::Rectangle,
+        spot_light::SpotLight,
+        texture::ImageTexture,
+        world_intersection::WorldIntersection,
+    };
 
     use super::*;
 
@@ -109,9 +754,10 @@ mod tests {
 
         let w = World::default();
 
-        assert!(w.light == light);
-        assert!(w.objects().contains(&s1));
-        assert!(w.objects().contains(&s2));
+        assert!(w.light() == &light as &dyn Light);
+        assert_eq!(w.lights().len(), 1);
+        assert!(w.objects().contains(&(Box::new(s1) as Box<dyn Shape>)));
+        assert!(w.objects().contains(&(Box::new(s2) as Box<dyn Shape>)));
     }
 
     #[test]
@@ -132,9 +778,9 @@ mod tests {
         let w = World::default();
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let shape = w.objects()[0].clone();
-        let i = Intersection::new(4.0, shape);
-        let comps = WorldIntersection::precompute(i, &r);
-        let c = w.shade_hit(&comps);
+        let i = Intersection::new(4.0, shape.as_ref());
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        let c = w.shade_hit(&comps, MAX_RECURSION);
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855))
     }
 
@@ -142,12 +788,12 @@ mod tests {
     fn shading_intersection_from_inside() {
         let wpre = World::default();
         let light = PointLight::new(Point::new(0.0, 0.25, 0.0), Color::new(1.0, 1.0, 1.0));
-        let w = World::new(wpre.objects().clone(), light);
+        let w = World::new(wpre.objects().clone(), Box::new(light));
         let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
         let shape = w.objects()[1].clone();
-        let i = Intersection::new(0.5, shape);
-        let comps = WorldIntersection::precompute(i, &r);
-        let c = w.shade_hit(&comps);
+        let i = Intersection::new(0.5, shape.as_ref());
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        let c = w.shade_hit(&comps, MAX_RECURSION);
         assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498))
     }
 
@@ -170,14 +816,18 @@ mod tests {
     #[test]
     fn color_with_intersection_behind_ray() {
         let w = World::default();
-        let mut outer = w.objects()[0].clone();
-        outer.material.ambient = 1.0;
-        let mut inner = w.objects()[1].clone();
-        inner.material.ambient = 1.0;
+        let outer = w.objects()[0].as_any().downcast_ref::<Sphere>().unwrap();
+        let outer = outer
+            .clone()
+            .set_material(outer.material().clone().set_ambient(1.0));
+        let inner = w.objects()[1].as_any().downcast_ref::<Sphere>().unwrap();
+        let inner = inner
+            .clone()
+            .set_material(inner.material().clone().set_ambient(1.0));
 
-        let light = (*w.light()).clone();
+        let light = w.light().box_clone();
 
-        let w2 = World::new(vec![outer, inner.clone()], light);
+        let w2 = World::new(vec![Box::new(outer), Box::new(inner.clone())], light);
         let r = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, -1.0));
         let c = w2.color_at(&r);
         assert_eq!(c, inner.material().color)
@@ -187,28 +837,163 @@ mod tests {
     fn no_shadow_when_nothing_colinear() {
         let w = World::default();
         let p = Point::new(0.0, 10.0, 0.0);
-        assert!(!w.is_shadowed(p));
+        assert!(!w.is_shadowed(w.light().position(), p));
     }
 
     #[test]
     fn shadow_when_object_between() {
         let w = World::default();
         let p = Point::new(10.0, -10.0, 10.0);
-        assert!(w.is_shadowed(p));
+        assert!(w.is_shadowed(w.light().position(), p));
     }
 
     #[test]
     fn no_shadow_object_behind_light() {
         let w = World::default();
         let p = Point::new(-20.0, 20.0, -20.0);
-        assert!(!w.is_shadowed(p));
+        assert!(!w.is_shadowed(w.light().position(), p));
     }
 
     #[test]
     fn no_shadow_object_behind_point() {
         let w = World::default();
         let p = Point::new(-2.0, 2.0, -2.0);
-        assert!(!w.is_shadowed(p));
+        assert!(!w.is_shadowed(w.light().position(), p));
+    }
+
+    #[test]
+    fn occlusion_between_two_points() {
+        let w = World::default();
+        let (front, back) = (Point::new(0.0, 0.0, -5.0), Point::new(0.0, 0.0, 5.0));
+        assert!(w.is_occluded(front, back));
+        assert!(w.is_occluded(back, front));
+        assert!(!w.is_occluded(front, Point::new(0.0, 0.0, -2.0)));
+        assert!(!w.is_occluded(front, Point::new(0.0, 5.0, 5.0)));
+        assert!(!w.is_occluded(front, front));
+    }
+
+    #[test]
+    fn first_hit_is_the_nearest_ahead() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let first = w.first_hit(&r).unwrap();
+        assert_eq!(Some(first), hit(&w.intersect_world(&r)));
+        let inside = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let nearest = w.first_hit(&inside).unwrap();
+        assert!(crate::utils::fp_equal(nearest.t(), 0.5));
+        let away = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(w.first_hit(&away).is_none());
+    }
+
+    #[test]
+    fn picking_the_nearest_object() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let (id, point) = w.pick(&r).unwrap();
+        assert_eq!(id, w.object_ids()[0]);
+        assert_eq!(point, Point::new(0.0, 0.0, -1.0));
+        let inside = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.pick(&inside).unwrap().0, w.object_ids()[1]);
+        let away = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert!(w.pick(&away).is_none());
+    }
+
+    #[test]
+    fn sampling_the_surface_a_ray_hits() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 2.0));
+        let sample = w.surface_sample(&r).unwrap();
+        assert_eq!(sample.depth, 4.0);
+        assert_eq!(sample.normal, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(sample.albedo, Color::new(0.8, 1.0, 0.6));
+        assert_eq!(sample.object, w.object_ids()[0]);
+        let away = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert!(w.surface_sample(&away).is_none());
+    }
+
+    #[test]
+    fn tracing_the_shading_of_a_ray() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let trace = w.debug_trace(&r);
+        assert_eq!(trace.intersections.len(), 4);
+        assert_eq!(trace.color, w.color_at(&r));
+        let hit = trace.hit.as_ref().unwrap();
+        assert_eq!(hit.intersection.t(), 4.0);
+        assert_eq!(hit.normal, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(hit.lights.len(), 1);
+        assert!(!hit.lights[0].shadowed);
+        assert_eq!(hit.lights[0].color, trace.color);
+        assert!(hit.reflection.is_none() && hit.refraction.is_none());
+        assert_eq!(trace.ray_count(), 1);
+
+        let away = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert!(w.debug_trace(&away).hit.is_none());
+    }
+
+    #[test]
+    fn tracing_reflected_and_refracted_rays() {
+        let glass = Sphere::default().set_material(
+            Material::default()
+                .set_reflective(0.5)
+                .set_transparency(0.5)
+                .set_refractive_index(1.5),
+        );
+        let w = World::new(vec![Box::new(glass)], World::default().light().box_clone());
+        let r = Ray::new(Point::new(0.0, 0.2, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let trace = w.debug_trace(&r);
+        assert_eq!(trace.color, w.color_at(&r));
+        let hit = trace.hit.as_ref().unwrap();
+        assert!(hit.reflection.is_some() && hit.refraction.is_some());
+        assert!(hit.reflection_weight > 0.0 && hit.reflection_weight < 0.5);
+        assert!(trace.ray_count() > 3);
+    }
+
+    #[test]
+    fn animated_worlds_at_a_time() {
+        let mut w = World::default();
+        let inner = w.object_ids()[1];
+        let slide = Track::new()
+            .add_key(0.0, Matrix::identity(), Easing::Linear)
+            .add_key(2.0, Matrix::translation(0.0, 4.0, 0.0), Easing::Linear);
+        w.animate(inner, ObjectAnimation::new().set_transform(slide));
+        assert!(w.animation(inner).is_some());
+
+        let still = w.at_time(1.0);
+        assert!(still.animation(inner).is_none());
+        assert_eq!(
+            still.object(inner).unwrap().transform(),
+            &(Matrix::translation(0.0, 2.0, 0.0) * Matrix::scaling(0.5, 0.5, 0.5))
+        );
+        assert_eq!(w.at_time(0.0).objects(), w.objects());
+        w.remove_object(inner);
+        assert!(w.animation(inner).is_none());
+    }
+
+    #[test]
+    fn editing_objects_by_id() {
+        let mut w = World::default();
+        let (outer, inner) = (w.object_ids()[0], w.object_ids()[1]);
+        let moved = Sphere::default().set_transform(Matrix::translation(0.0, 3.0, 0.0));
+        let extra = w.add_object(Box::new(moved));
+        assert_eq!(w.objects().len(), 3);
+
+        let removed = w.remove_object(outer).unwrap();
+        assert!(w.object(outer).is_none());
+        assert!(w.remove_object(outer).is_none());
+        assert_eq!(w.object_ids(), &[inner, extra]);
+        assert_eq!(w.add_object(removed.clone()), ObjectId(3));
+
+        let old = w.replace_object(inner, removed).unwrap();
+        assert_eq!(w.object_ids()[0], inner);
+        assert_eq!(old.transform(), &Matrix::scaling(0.5, 0.5, 0.5));
+        w.object_mut(extra)
+            .unwrap()
+            .apply_parent_transform(&Matrix::translation(1.0, 0.0, 0.0));
+        assert_eq!(
+            w.object(extra).unwrap().transform(),
+            &Matrix::translation(1.0, 3.0, 0.0)
+        );
     }
 
     #[test]
@@ -216,11 +1001,473 @@ mod tests {
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let s1 = Sphere::default();
         let s2 = Sphere::default().set_transform(Matrix::translation(0.0, 0.0, 10.0));
-        let w = World::new(vec![s1, s2.clone()], light);
+        let w = World::new(vec![Box::new(s1), Box::new(s2.clone())], Box::new(light));
         let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
-        let i = Intersection::new(4.0, s2);
-        let comps = WorldIntersection::precompute(i, &r);
-        let c = w.shade_hit(&comps);
+        let i = Intersection::new(4.0, &s2);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        let c = w.shade_hit(&comps, MAX_RECURSION);
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
+
+    #[test]
+    fn color_at_occluded_point_is_ambient_only() {
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let s1 = Sphere::default();
+        let s2 = Sphere::default().set_transform(Matrix::translation(0.0, 0.0, 10.0));
+        let w = World::new(vec![Box::new(s1), Box::new(s2)], Box::new(light));
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&r), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn shade_hit_sums_every_light() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = w.objects()[0].clone();
+        let i = Intersection::new(4.0, shape.as_ref());
+        let comps = WorldIntersection::precompute(i.clone(), &r, std::slice::from_ref(&i));
+        let single = w.shade_hit(&comps, MAX_RECURSION);
+        let light = w.light().box_clone();
+        let w = w.add_light(light);
+        assert_eq!(w.lights().len(), 2);
+        let c = w.shade_hit(&comps, MAX_RECURSION);
+        assert_eq!(c, single * 2.0);
+    }
+
+    #[test]
+    fn each_light_has_its_own_shadow_test() {
+        let s1 = Sphere::default();
+        let s2 = Sphere::default().set_transform(Matrix::translation(0.0, 0.0, 10.0));
+        let front = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let side = PointLight::new(Point::new(0.0, 5.0, 5.0), Color::new(1.0, 1.0, 1.0));
+        let w = World::new(vec![Box::new(s1), Box::new(s2.clone())], Box::new(front))
+            .add_light(Box::new(side));
+        let p = Point::new(0.0, 0.0, 9.0);
+        assert!(w.is_shadowed(w.lights()[0].position(), p));
+        assert!(!w.is_shadowed(w.lights()[1].position(), p));
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, &s2);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        let c = w.shade_hit(&comps, MAX_RECURSION);
+        assert!(c.red > 0.1);
+    }
+
+    #[test]
+    fn world_lit_by_a_spot_light() {
+        let spot = SpotLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0),
+            std::f32::consts::PI / 8.0,
+        );
+        let w = World::new(World::default().objects().clone(), Box::new(spot));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&r), Color::new(0.84, 1.0, 0.68));
+        let away = Ray::new(Point::new(0.0, 10.0, -5.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(w.color_at(&away), Color::new(0.0, 0.0, 0.0));
+    }
+
+    fn sky(color: Color) -> EnvironmentMap {
+        let mut c = Canvas::new(8, 4);
+        for pixel in c.grid.iter_mut().flatten() {
+            *pixel = color;
+        }
+        EnvironmentMap::new(ImageTexture::new(c))
+    }
+
+    #[test]
+    fn missing_ray_sees_the_background() {
+        let w = World::default().set_background(Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        });
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), Color::new(0.5, 0.7, 1.0));
+        let w = w.set_environment(sky(Color::new(0.2, 0.4, 0.8)));
+        assert_eq!(w.color_at(&r), Color::new(0.2, 0.4, 0.8));
+    }
+
+    #[test]
+    fn sun_sky_sets_background_and_adds_sun() {
+        let sky = SunSky::new(std::f32::consts::PI / 4.0, 0.0);
+        let w = World::default().set_sun_sky(sky.clone());
+        assert_eq!(w.lights().len(), 2);
+        assert!(w.lights()[1].light_eq(&sky.sun()));
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), up);
+        assert_eq!(w.color_at(&r), sky.color_in_direction(up));
+    }
+
+    #[test]
+    fn missing_ray_sees_the_environment() {
+        let w = World::default().set_environment(sky(Color::new(0.2, 0.4, 0.8)));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), Color::new(0.2, 0.4, 0.8));
+    }
+
+    #[test]
+    fn uniform_environment_lights_white_diffuse_surface_fully() {
+        let dark = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::new(0.0, 0.0, 0.0));
+        let s = Sphere::default().set_material(
+            Material::default()
+                .set_ambient(0.0)
+                .set_diffuse(1.0)
+                .set_specular(0.0),
+        );
+        let w = World::new(vec![Box::new(s)], Box::new(dark))
+            .set_environment(sky(Color::new(1.0, 1.0, 1.0)).set_diffuse_samples(8000));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = w.color_at(&r);
+        assert!((c.red - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn fog_fades_distant_hits_and_fills_misses() {
+        let grey = Color::new(0.5, 0.5, 0.5);
+        let w = World::default().set_fog(Fog::Linear {
+            color: grey,
+            start: 0.0,
+            end: 8.0,
+        });
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let c = Color::new(0.38066, 0.47583, 0.2855);
+        assert_eq!(w.color_at(&r), c * 0.5 + grey * 0.5);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), grey);
+    }
+
+    #[test]
+    fn medium_scatters_light_except_in_shadow() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let blocker = Rectangle::new(2.0, 2.0)
+            .set_transform(Matrix::translation(0.0, 5.0, 5.0))
+            .set_material(Material::default().set_color(Color::new(0.0, 0.0, 0.0)));
+        let w = World::new(vec![Box::new(blocker)], Box::new(light))
+            .set_medium(Medium::new(0.1, Color::new(1.0, 1.0, 1.0)));
+        // The blocker's shadow falls around (0, 0, 10).
+        let open = Ray::new(Point::new(-5.0, 0.0, -5.0), Vector::new(1.0, 0.0, 0.0));
+        let under_blocker = Ray::new(Point::new(-5.0, 0.0, 10.0), Vector::new(1.0, 0.0, 0.0));
+        let lit = w.color_at(&open);
+        let shaded = w.color_at(&under_blocker);
+        assert!(lit.red > 0.0);
+        assert!(shaded.red < lit.red);
+    }
+
+    #[test]
+    fn shadow_epsilon_defaults_to_crate_value() {
+        let w = World::default();
+        assert_eq!(w.shadow_epsilon(), SHADOW_EPSILON);
+        assert_eq!(w.set_shadow_epsilon(0.5).shadow_epsilon(), 0.5);
+    }
+
+    #[test]
+    fn large_shadow_epsilon_lifts_rays_past_thin_occluders() {
+        // A thin card just above the floor shadows it, unless the bias is
+        // large enough to start shadow rays above the card.
+        let floor = Rectangle::new(10.0, 10.0);
+        let card = Rectangle::new(2.0, 2.0).set_transform(Matrix::translation(0.0, 0.01, 0.0));
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point::new(5.0, 0.005, 0.0), Vector::new(-1.0, -0.001, 0.0));
+        let w = World::new(vec![Box::new(floor), Box::new(card)], Box::new(light));
+        let near = w.color_at(&r);
+        let w = w.set_shadow_epsilon(0.1);
+        let far = w.color_at(&r);
+        assert!(near.red < far.red);
+    }
+
+    fn lit_only_by(background: Color, material: Material) -> World {
+        let dark = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::new(0.0, 0.0, 0.0));
+        World::new(
+            vec![Box::new(Sphere::default().set_material(material))],
+            Box::new(dark),
+        )
+        .set_background(Background::Solid(background))
+    }
+
+    #[test]
+    fn path_tracing_sees_emissive_surfaces() {
+        let glow = Color::new(1.0, 0.5, 0.0);
+        let w = lit_only_by(
+            Color::new(0.0, 0.0, 0.0),
+            Material::default().set_emissive(glow).set_diffuse(0.0),
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(1);
+        assert_eq!(w.trace_path(&r, &mut rng), glow);
+    }
+
+    #[test]
+    fn path_tracing_gathers_indirect_light() {
+        let w = lit_only_by(
+            Color::new(1.0, 1.0, 1.0),
+            Material::default()
+                .set_color(Color::new(0.5, 0.25, 1.0))
+                .set_diffuse(1.0),
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(2);
+        for _ in 0..10 {
+            assert_eq!(w.trace_path(&r, &mut rng), Color::new(0.5, 0.25, 1.0));
+        }
+        assert_eq!(w.color_at(&r), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn path_tracing_terminates_between_parallel_mirrors() {
+        let w = parallel_mirrors();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let mut rng = Rng::new(3);
+        w.trace_path(&r, &mut rng);
+    }
+
+    fn parallel_mirrors() -> World {
+        let mirror = Material::default().set_reflective(1.0);
+        let lower = Rectangle::new(10.0, 10.0)
+            .set_transform(Matrix::translation(0.0, -1.0, 0.0))
+            .set_material(mirror.clone());
+        let upper = Rectangle::new(10.0, 10.0)
+            .set_transform(Matrix::translation(0.0, 1.0, 0.0))
+            .set_material(mirror);
+        World::new(
+            vec![Box::new(lower), Box::new(upper)],
+            World::default().light().box_clone(),
+        )
+    }
+
+    #[test]
+    fn color_at_terminates_between_parallel_mirrors() {
+        let w = parallel_mirrors();
+        assert_eq!(w.max_recursion(), MAX_RECURSION);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        // The light is behind both mirrors, so every bounce adds only its
+        // ambient 0.1.
+        assert_eq!(w.color_at(&r), Color::new(0.6, 0.6, 0.6));
+    }
+
+    #[test]
+    fn max_recursion_limits_mirror_bounces() {
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let w = parallel_mirrors().set_max_recursion(0);
+        assert_eq!(w.color_at(&r), Color::new(0.1, 0.1, 0.1));
+        let w = parallel_mirrors().set_max_recursion(2);
+        assert_eq!(w.color_at(&r), Color::new(0.3, 0.3, 0.3));
+        let w = parallel_mirrors().set_max_recursion(1000);
+        assert!(w.color_at(&r).red > 99.0);
+    }
+
+    #[test]
+    fn quality_profile_sets_max_recursion() {
+        let w = parallel_mirrors().set_quality(&Quality::draft());
+        assert_eq!(w.max_recursion(), Quality::draft().max_recursion);
+    }
+
+    fn world_with_mirror_floor(material: Material) -> (World, Rectangle) {
+        let floor = Rectangle::new(100.0, 100.0)
+            .set_transform(Matrix::translation(0.0, -1.0, 0.0))
+            .set_material(material);
+        let mut objects = World::default().objects().clone();
+        objects.push(Box::new(floor.clone()));
+        let light = World::default().light().box_clone();
+        (World::new(objects, light), floor)
+    }
+
+    #[test]
+    fn reflected_color_for_nonreflective_material() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = w.objects()[1].as_any().downcast_ref::<Sphere>().unwrap();
+        let shape = shape
+            .clone()
+            .set_material(shape.material().clone().set_ambient(1.0));
+        let i = Intersection::new(1.0, &shape);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        assert_eq!(w.reflected_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn reflected_color_for_reflective_material() {
+        let (w, floor) = world_with_mirror_floor(Material::default().set_reflective(0.5));
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -x, x));
+        let i = Intersection::new(2.0_f32.sqrt(), &floor);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        let c = w.reflected_color(&comps, 5);
+        // The 0.005 over_point offset moves the reflected ray a little from
+        // where the book's smaller epsilon puts it.
+        assert!((c.red - 0.19032).abs() < 0.002);
+        assert!((c.green - 0.2379).abs() < 0.002);
+        assert!((c.blue - 0.14274).abs() < 0.002);
+    }
+
+    #[test]
+    fn shade_hit_with_reflective_material() {
+        let (w, floor) = world_with_mirror_floor(Material::default().set_reflective(0.5));
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -x, x));
+        let i = Intersection::new(2.0_f32.sqrt(), &floor);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        let c = w.shade_hit(&comps, 5);
+        assert!((c.red - 0.87677).abs() < 0.002);
+        assert!((c.green - 0.92436).abs() < 0.002);
+        assert!((c.blue - 0.82918).abs() < 0.002);
+    }
+
+    #[test]
+    fn reflected_color_at_maximum_recursive_depth() {
+        let (w, floor) = world_with_mirror_floor(Material::default().set_reflective(0.5));
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -x, x));
+        let i = Intersection::new(2.0_f32.sqrt(), &floor);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        assert_eq!(w.reflected_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn shade_hit_with_reflective_transparent_material() {
+        let (w, floor) = world_with_mirror_floor(
+            Material::default()
+                .set_reflective(0.5)
+                .set_transparency(0.5)
+                .set_refractive_index(1.5),
+        );
+        let ball = Sphere::default()
+            .set_transform(Matrix::translation(0.0, -3.5, -0.5))
+            .set_material(
+                Material::default()
+                    .set_color(Color::new(1.0, 0.0, 0.0))
+                    .set_ambient(0.5),
+            );
+        let mut objects = w.objects().clone();
+        objects.push(Box::new(ball));
+        let w = World::new(objects, w.light().box_clone());
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -x, x));
+        let xs = vec![Intersection::new(2.0_f32.sqrt(), &floor)];
+        let comps = WorldIntersection::precompute(xs[0].clone(), &r, &xs);
+        let c = w.shade_hit(&comps, 5);
+        assert!((c.red - 0.93391).abs() < 0.001);
+        assert!((c.green - 0.69643).abs() < 0.001);
+        assert!((c.blue - 0.69243).abs() < 0.001);
+    }
+
+    #[test]
+    fn refracted_color_with_opaque_surface() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = w.objects()[0].clone();
+        let xs = vec![
+            Intersection::new(4.0, shape.as_ref()),
+            Intersection::new(6.0, shape.as_ref()),
+        ];
+        let comps = WorldIntersection::precompute(xs[0].clone(), &r, &xs);
+        assert_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_at_maximum_recursive_depth() {
+        let shape = Sphere::default().set_material(
+            Material::default()
+                .set_transparency(1.0)
+                .set_refractive_index(1.5),
+        );
+        let w = World::new(
+            vec![Box::new(shape.clone())],
+            World::default().light().box_clone(),
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = vec![
+            Intersection::new(4.0, &shape),
+            Intersection::new(6.0, &shape),
+        ];
+        let comps = WorldIntersection::precompute(xs[0].clone(), &r, &xs);
+        assert_eq!(w.refracted_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let shape = Sphere::default().set_material(
+            Material::default()
+                .set_transparency(1.0)
+                .set_refractive_index(1.5),
+        );
+        let w = World::new(
+            vec![Box::new(shape.clone())],
+            World::default().light().box_clone(),
+        );
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let r = Ray::new(Point::new(0.0, 0.0, x), Vector::new(0.0, 1.0, 0.0));
+        let xs = vec![Intersection::new(-x, &shape), Intersection::new(x, &shape)];
+        let comps = WorldIntersection::precompute(xs[1].clone(), &r, &xs);
+        assert_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn shade_hit_with_transparent_material() {
+        let floor = Rectangle::new(100.0, 100.0)
+            .set_transform(Matrix::translation(0.0, -1.0, 0.0))
+            .set_material(
+                Material::default()
+                    .set_transparency(0.5)
+                    .set_refractive_index(1.5),
+            );
+        let ball = Sphere::default()
+            .set_transform(Matrix::translation(0.0, -3.5, -0.5))
+            .set_material(
+                Material::default()
+                    .set_color(Color::new(1.0, 0.0, 0.0))
+                    .set_ambient(0.5),
+            );
+        let mut objects = World::default().objects().clone();
+        objects.push(Box::new(floor.clone()));
+        objects.push(Box::new(ball));
+        let w = World::new(objects, World::default().light().box_clone());
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -x, x));
+        let xs = vec![Intersection::new(2.0_f32.sqrt(), &floor)];
+        let comps = WorldIntersection::precompute(xs[0].clone(), &r, &xs);
+        let c = w.shade_hit(&comps, 5);
+        assert!((c.red - 0.93642).abs() < 0.001);
+        assert!((c.green - 0.68642).abs() < 0.001);
+        assert!((c.blue - 0.68642).abs() < 0.001);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn world_round_trips_through_serde() {
+        use crate::{group::Group, pattern::StripePattern};
+
+        let striped = Material::default().set_pattern(Box::new(StripePattern::new(
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+        )));
+        let group = Group::new(
+            Matrix::translation(0.0, 0.0, 2.0),
+            vec![Box::new(Sphere::default().set_material(striped))],
+        );
+        let spot = SpotLight::new(
+            Point::new(0.0, 5.0, -5.0),
+            Vector::new(0.0, -1.0, 1.0),
+            Color::new(0.5, 0.5, 0.5),
+            0.5,
+        );
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let w = World::new(
+            vec![Box::new(Sphere::default()), Box::new(group)],
+            Box::new(light),
+        )
+        .add_light(Box::new(spot))
+        .set_fog(Fog::Exponential {
+            color: Color::new(0.5, 0.5, 0.5),
+            density: 0.1,
+        })
+        .set_background(Background::Solid(Color::new(0.1, 0.2, 0.3)));
+
+        let json = serde_json::to_string(&w).unwrap();
+        let back: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.objects().len(), 2);
+        assert_eq!(back.lights().len(), 2);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(back.color_at(&r), w.color_at(&r));
+    }
 }
@@ -1,36 +1,74 @@
 use crate::{
     point::Point,
     ray::{Intersection, Ray},
+    shape::Shape,
+    utils::SHADOW_EPSILON,
     vector::Vector,
 };
 
-pub struct WorldIntersection {
-    inter: Intersection,
+pub struct WorldIntersection<'a> {
+    inter: Intersection<'a>,
     point: Point,
     eye: Vector,
     normal: Vector,
+    reflectv: Vector,
     inside: bool,
+    time: f32,
+    seed: u64,
+    uv: Option<(f32, f32)>,
     over_point: Point,
+    under_point: Point,
+    n1: f32,
+    n2: f32,
 }
 
-impl WorldIntersection {
-    pub fn precompute(inter: Intersection, ray: &Ray) -> WorldIntersection {
+impl<'a> WorldIntersection<'a> {
+    // xs is every intersection along the ray, sorted, including inter. It is
+    // used to find which objects the ray is inside on either side of the hit,
+    // giving the refractive indices n1 and n2.
+    pub fn precompute(
+        inter: Intersection<'a>,
+        ray: &Ray,
+        xs: &[Intersection],
+    ) -> WorldIntersection<'a> {
+        WorldIntersection::precompute_with_epsilon(inter, ray, xs, SHADOW_EPSILON)
+    }
+
+    // As precompute, with over_point and under_point epsilon off the surface.
+    pub fn precompute_with_epsilon(
+        inter: Intersection<'a>,
+        ray: &Ray,
+        xs: &[Intersection],
+        epsilon: f32,
+    ) -> WorldIntersection<'a> {
         let point = ray.position(inter.t());
         let eye = -ray.direction();
-        let normal = inter.object().normal_at(point);
+        let normal = inter.normal_at(point);
         let inside = normal.dot(&eye) < 0.0;
-        let over_point = point + normal * 0.005;
+        let normal = if inside { -normal } else { normal };
+        let reflectv = ray.direction().reflect(&normal);
+        let over_point = point + normal * epsilon;
+        let under_point = point - normal * epsilon;
+        let (n1, n2) = refractive_indices(&inter, xs);
+        let uv = inter.uv();
         WorldIntersection {
             point,
             eye,
-            normal: if inside { -normal } else { normal },
+            normal,
+            reflectv,
             inter,
             inside,
+            time: ray.time(),
+            seed: ray.seed(),
+            uv,
             over_point,
+            under_point,
+            n1,
+            n2,
         }
     }
 
-    pub fn inter(&self) -> &Intersection {
+    pub fn inter(&self) -> &Intersection<'a> {
         &self.inter
     }
 
@@ -46,6 +84,26 @@ impl WorldIntersection {
         &self.normal
     }
 
+    pub fn reflectv(&self) -> &Vector {
+        &self.reflectv
+    }
+
+    // When the ray that made the hit was cast; rays leaving the hit share it.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    // The seed of the ray that made the hit, for random choices made in
+    // shading it.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // The hit's u and v, if the shape hit gives them.
+    pub fn uv(&self) -> Option<(f32, f32)> {
+        self.uv
+    }
+
     pub fn inside(&self) -> bool {
         self.inside
     }
@@ -53,11 +111,67 @@ impl WorldIntersection {
     pub fn over_point(&self) -> &Point {
         &self.over_point
     }
+
+    pub fn under_point(&self) -> &Point {
+        &self.under_point
+    }
+
+    pub fn n1(&self) -> f32 {
+        self.n1
+    }
+
+    pub fn n2(&self) -> f32 {
+        self.n2
+    }
+
+    // Schlick's approximation of the fraction of light reflected rather than
+    // refracted at this hit.
+    pub fn schlick(&self) -> f32 {
+        let mut cos = self.eye.dot(&self.normal);
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n * n * (1.0 - cos * cos);
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+            cos = (1.0 - sin2_t).sqrt();
+        }
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+// Objects are told apart by address, so the faces of one mesh all belong to
+// the same object.
+fn refractive_indices(hit: &Intersection, xs: &[Intersection]) -> (f32, f32) {
+    let index = |containers: &[&dyn Shape]| {
+        containers
+            .last()
+            .map_or(1.0, |o| o.material().refractive_index)
+    };
+    let same = |a: &dyn Shape, b: &dyn Shape| std::ptr::addr_eq(a, b);
+    let mut containers: Vec<&dyn Shape> = vec![];
+    for i in xs {
+        let is_hit = i.t() == hit.t() && same(i.object(), hit.object());
+        let n1 = index(&containers);
+        match containers.iter().position(|o| same(*o, i.object())) {
+            Some(p) => {
+                containers.remove(p);
+            }
+            None => containers.push(i.object()),
+        }
+        if is_hit {
+            return (n1, index(&containers));
+        }
+    }
+    // The hit was not among xs; treat it as entering from empty space.
+    (1.0, hit.object().material().refractive_index)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{matrix::Matrix, point::Point, ray::{Intersection, Ray}, sphere::Sphere, vector::Vector};
+    use crate::{material::Material, rectangle::Rectangle, utils::fp_equal};
 
     use super::*;
 
@@ -65,8 +179,8 @@ mod tests {
     fn precomputiong_state_of_intersection() {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let shape = Sphere::default();
-        let i = Intersection::new(4.0, shape);
-        let comps = WorldIntersection::precompute(i.clone(), &r);
+        let i = Intersection::new(4.0, &shape);
+        let comps = WorldIntersection::precompute(i.clone(), &r, std::slice::from_ref(&i));
         assert_eq!(comps.inter().t(), i.t());
         assert_eq!(comps.point(), &Point::new(0.0, 0.0, -1.0));
         assert_eq!(comps.eye(), &Vector::new(0.0, 0.0, -1.0));
@@ -77,8 +191,8 @@ mod tests {
     fn when_intersection_occurs_on_outside() {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let shape = Sphere::default();
-        let i = Intersection::new(4.0, shape);
-        let comps = WorldIntersection::precompute(i, &r);
+        let i = Intersection::new(4.0, &shape);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
         assert!(!comps.inside())
     }
 
@@ -86,8 +200,8 @@ mod tests {
     fn when_intersection_occurs_on_inside() {
         let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
         let shape = Sphere::default();
-        let i = Intersection::new(1.0, shape);
-        let comps = WorldIntersection::precompute(i, &r);
+        let i = Intersection::new(1.0, &shape);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
         assert!(comps.inside());
         assert_eq!(comps.point(), &Point::new(0.0, 0.0, 1.0));
         assert_eq!(comps.eye(), &Vector::new(0.0, 0.0, -1.0));
@@ -98,9 +212,114 @@ mod tests {
     fn hit_should_offset_point() {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let shape = Sphere::default().set_transform(Matrix::translation(0.0, 0.0, 1.0));
-        let i = Intersection::new(5.0, shape);
-        let comps = WorldIntersection::precompute(i, &r);
-        assert!(comps.over_point().z < -std::f32::EPSILON/2.0);
+        let i = Intersection::new(5.0, &shape);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        assert!(comps.over_point().z < -f32::EPSILON / 2.0);
         assert!(comps.point().z > comps.over_point().z);
     }
+
+    #[test]
+    fn over_and_under_points_use_given_epsilon() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = Sphere::default();
+        let i = Intersection::new(4.0, &shape);
+        let comps = WorldIntersection::precompute_with_epsilon(i.clone(), &r, &[i], 0.1);
+        assert_eq!(comps.over_point(), &Point::new(0.0, 0.0, -1.1));
+        assert_eq!(comps.under_point(), &Point::new(0.0, 0.0, -0.9));
+    }
+
+    #[test]
+    fn over_point_is_offset_toward_eye_from_inside() {
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = Sphere::default();
+        let i = Intersection::new(1.0, &shape);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        assert!(comps.over_point().z < comps.point().z);
+    }
+
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let shape = Rectangle::new(10.0, 10.0);
+        let r = Ray::new(Point::new(0.0, 1.0, -1.0), Vector::new(0.0, -x, x));
+        let i = Intersection::new(2.0_f32.sqrt(), &shape);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        assert_eq!(comps.reflectv(), &Vector::new(0.0, x, x));
+    }
+
+    #[test]
+    fn schlick_under_total_internal_reflection() {
+        let x = std::f32::consts::FRAC_1_SQRT_2;
+        let shape = Sphere::glass();
+        let r = Ray::new(Point::new(0.0, 0.0, x), Vector::new(0.0, 1.0, 0.0));
+        let xs = vec![Intersection::new(-x, &shape), Intersection::new(x, &shape)];
+        let comps = WorldIntersection::precompute(xs[1].clone(), &r, &xs);
+        assert!(fp_equal(comps.schlick(), 1.0));
+    }
+
+    #[test]
+    fn schlick_with_perpendicular_viewing_angle() {
+        let shape = Sphere::glass();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = vec![
+            Intersection::new(-1.0, &shape),
+            Intersection::new(1.0, &shape),
+        ];
+        let comps = WorldIntersection::precompute(xs[1].clone(), &r, &xs);
+        assert!((comps.schlick() - 0.04).abs() < 0.0001);
+    }
+
+    #[test]
+    fn schlick_with_small_angle_and_n2_greater_than_n1() {
+        let shape = Sphere::glass();
+        let r = Ray::new(Point::new(0.0, 0.99, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = vec![Intersection::new(1.8589, &shape)];
+        let comps = WorldIntersection::precompute(xs[0].clone(), &r, &xs);
+        assert!((comps.schlick() - 0.48873).abs() < 0.0001);
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let a = Sphere::glass()
+            .set_transform(Matrix::scaling(2.0, 2.0, 2.0))
+            .set_material(Material::glass().set_refractive_index(1.5));
+        let b = Sphere::glass()
+            .set_transform(Matrix::translation(0.0, 0.0, -0.25))
+            .set_material(Material::glass().set_refractive_index(2.0));
+        let c = Sphere::glass()
+            .set_transform(Matrix::translation(0.0, 0.0, 0.25))
+            .set_material(Material::glass().set_refractive_index(2.5));
+        let r = Ray::new(Point::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = vec![
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ];
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (i, (n1, n2)) in expected.iter().enumerate() {
+            let comps = WorldIntersection::precompute(xs[i].clone(), &r, &xs);
+            assert!(fp_equal(comps.n1(), *n1));
+            assert!(fp_equal(comps.n2(), *n2));
+        }
+    }
+
+    #[test]
+    fn under_point_is_offset_below_surface() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = Sphere::glass().set_transform(Matrix::translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+        let comps = WorldIntersection::precompute(i.clone(), &r, &[i]);
+        assert!(comps.under_point().z > f32::EPSILON / 2.0);
+        assert!(comps.point().z < comps.under_point().z);
+    }
 }